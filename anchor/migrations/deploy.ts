@@ -13,6 +13,7 @@ import {
   createAssociatedTokenAccountInstruction,
   createInitializeMetadataPointerInstruction,
   createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
   createInitializeTransferHookInstruction,
   createMintToInstruction,
   createSetAuthorityInstruction,
@@ -38,6 +39,8 @@ module.exports = async function (provider: anchor.AnchorProvider) {
   const tokenSupplyEnv = process.env.TOKEN_SUPPLY;
   const tokenDecimalsEnv = process.env.TOKEN_DECIMALS;
   const recipientEnv = process.env.MINT_RECIPIENT;
+  const transferFeeBpsEnv = process.env.TOKEN_TRANSFER_FEE_BPS;
+  const maxTransferFeeEnv = process.env.TOKEN_MAX_TRANSFER_FEE;
   if (
     !tokenName ||
     !tokenSymbol ||
@@ -73,6 +76,26 @@ module.exports = async function (provider: anchor.AnchorProvider) {
     throw new Error("TOKEN_SUPPLY must be an integer string");
   }
   supply = supply * BigInt(10 ** decimals);
+  // Optional transfer fee. Deposits record the amount received net of the fee
+  let transferFee: { basisPoints: number; maxFee: bigint } | null = null;
+  if (transferFeeBpsEnv || maxTransferFeeEnv) {
+    if (!transferFeeBpsEnv || !maxTransferFeeEnv) {
+      throw new Error(
+        "TOKEN_TRANSFER_FEE_BPS and TOKEN_MAX_TRANSFER_FEE must be set together"
+      );
+    }
+    const basisPoints = Number(transferFeeBpsEnv);
+    if (!Number.isInteger(basisPoints) || basisPoints < 0 || basisPoints > 10_000) {
+      throw new Error("TOKEN_TRANSFER_FEE_BPS must be an integer between 0 and 10000");
+    }
+    let maxFee: bigint;
+    try {
+      maxFee = BigInt(maxTransferFeeEnv);
+    } catch {
+      throw new Error("TOKEN_MAX_TRANSFER_FEE must be an integer string");
+    }
+    transferFee = { basisPoints, maxFee };
+  }
   const tokenMetadata: TokenMetadata = {
     updateAuthority: payer,
     mint: mint.publicKey,
@@ -84,12 +107,11 @@ module.exports = async function (provider: anchor.AnchorProvider) {
 
   console.log("Creating mint with metadata:", tokenMetadata);
 
-  let mintLen = getMintLen(
-    [
-      ExtensionType.MetadataPointer,
-      ExtensionType.TransferHook,
-    ]
-  );
+  const extensions = [ExtensionType.MetadataPointer, ExtensionType.TransferHook];
+  if (transferFee) {
+    extensions.push(ExtensionType.TransferFeeConfig);
+  }
+  let mintLen = getMintLen(extensions);
 
   const metadataLen = TYPE_SIZE + LENGTH_SIZE + pack(tokenMetadata).length;
   const lamports = await connection.getMinimumBalanceForRentExemption(mintLen + metadataLen);
@@ -107,6 +129,28 @@ module.exports = async function (provider: anchor.AnchorProvider) {
     transferHookProgram.programId
   );
 
+  // The transfer tree is created in the same transaction as the mint so it can't exist without one
+  const initializeTreeIx = await transferHookProgram.methods
+    .initialize()
+    .accountsStrict({
+      treeAccount,
+      mint: mint.publicKey,
+      authority: payer,
+      systemProgram: SystemProgram.programId,
+    })
+    .instruction();
+  const initializeExtraAccountMetaListIx = await transferHookProgram.methods
+    .initializeExtraAccountMetaList()
+    .accountsStrict({
+      payer,
+      extraAccountMetaList,
+      mint: mint.publicKey,
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .instruction();
+
   const createMintTx = new Transaction().add(
     SystemProgram.createAccount({
       fromPubkey: payer,
@@ -127,6 +171,18 @@ module.exports = async function (provider: anchor.AnchorProvider) {
       transferHookProgram.programId,
       TOKEN_2022_PROGRAM_ID
     ),
+    ...(transferFee
+      ? [
+          createInitializeTransferFeeConfigInstruction(
+            mint.publicKey,
+            payer,
+            payer,
+            transferFee.basisPoints,
+            transferFee.maxFee,
+            TOKEN_2022_PROGRAM_ID
+          ),
+        ]
+      : []),
     createInitializeMintInstruction(
       mint.publicKey,
       decimals,
@@ -134,6 +190,13 @@ module.exports = async function (provider: anchor.AnchorProvider) {
       null,
       TOKEN_2022_PROGRAM_ID
     ),
+    initializeTreeIx,
+    initializeExtraAccountMetaListIx
+  );
+  await provider.sendAndConfirm(createMintTx, [mint]);
+
+  // Metadata is written into the space paid for above, separately to keep the first transaction within the size limit
+  const metadataTx = new Transaction().add(
     createInitializeMetadataInstruction({
       programId: TOKEN_2022_PROGRAM_ID,
       metadata: mint.publicKey,
@@ -145,8 +208,7 @@ module.exports = async function (provider: anchor.AnchorProvider) {
       uri: tokenMetadata.uri,
     })
   );
-
-  await provider.sendAndConfirm(createMintTx, [mint]);
+  await provider.sendAndConfirm(metadataTx, []);
 
   const mintInfo = await getMint(
     connection,
@@ -161,28 +223,6 @@ module.exports = async function (provider: anchor.AnchorProvider) {
     transferHook ? transferHook.programId.toBase58() : "none"
   );
 
-  await transferHookProgram.methods
-    .initialize()
-    .accountsStrict({
-      treeAccount,
-      mint: mint.publicKey,
-      authority: payer,
-      systemProgram: SystemProgram.programId,
-    })
-    .rpc();
-
-  await transferHookProgram.methods
-    .initializeExtraAccountMetaList()
-    .accountsStrict({
-      payer,
-      extraAccountMetaList,
-      mint: mint.publicKey,
-      tokenProgram: TOKEN_2022_PROGRAM_ID,
-      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-      systemProgram: SystemProgram.programId,
-    })
-    .rpc();
-
   const payerAta = getAssociatedTokenAddressSync(
     mint.publicKey,
     payer,
@@ -241,6 +281,15 @@ module.exports = async function (provider: anchor.AnchorProvider) {
 
   console.log("Mint (token address):", mint.publicKey.toBase58());
   console.log("Mint authority PDA:", mintAuthority.toBase58());
+  // The payer keeps every other authority. As transfer hook authority it administers the tree and verifying keys
+  console.log("Transfer hook authority:", payer.toBase58());
+  console.log("Metadata pointer and update authority:", payer.toBase58());
+  if (transferFee) {
+    console.log(
+      "Transfer fee config and withdraw withheld authority:",
+      payer.toBase58()
+    );
+  }
   console.log("Recipient owner:", recipientOwner.toBase58());
   console.log("Recipient ATA:", recipientAta.toBase58());
   console.log(
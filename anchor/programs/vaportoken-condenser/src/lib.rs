@@ -1,10 +1,13 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::{mint_to, Mint, MintTo, TokenAccount, TokenInterface};
 use gnark_verifier_solana::{proof::GnarkProof, verifier::GnarkVerifier, witness::GnarkWitness};
-use utils::unpack_bytes_from_le_fields;
-use vaportoken_transfer_hook::MerkleTreeAccount;
+use registry::{
+    Circuit, VerifyingKeyAccount, VerifyingKeyHeader, MAX_COMMITMENTS, NR_PUBLIC_INPUTS,
+};
+use utils::CondenserPublicInputs;
+use vaportoken_transfer_hook::{transfer_hook_authority, MerkleTreeAccount};
 
-mod vk;
+pub mod registry;
 
 declare_id!("Bs5oDuMEnM4VzseKjNndM4wgzZUhrWNJ2DRpiMp9xVFv");
 
@@ -20,28 +23,30 @@ pub mod condenser {
         proof_bytes: Vec<u8>,
         pub_witness_bytes: Vec<u8>,
     ) -> Result<()> {
-        const NR_INPUTS: usize = vk::VK.nr_pubinputs;
         let proof = GnarkProof::from_bytes(&proof_bytes).unwrap();
-        let pub_witness = GnarkWitness::<NR_INPUTS>::from_bytes(&pub_witness_bytes).unwrap();
+        let pub_witness = GnarkWitness::<NR_PUBLIC_INPUTS>::from_bytes(&pub_witness_bytes).unwrap();
 
         // Deserialize public inputs
-        let recipient_bytes = unpack_bytes_from_le_fields(&pub_witness.entries[0..2], 32);
-        let recipient_from_witness = Pubkey::new_from_array(
-            recipient_bytes
-                .as_slice()
-                .try_into()
-                .map_err(|_| ErrorCode::InvalidRecipient)?,
-        );
-        let amount = u64::from_be_bytes(pub_witness.entries[2][24..].try_into().unwrap());
-        let merkle_root = pub_witness.entries[3];
+        let public_inputs = CondenserPublicInputs::from_entries(&pub_witness.entries)
+            .ok_or(ErrorCode::InvalidRecipient)?;
+        let recipient_from_witness = Pubkey::new_from_array(public_inputs.recipient);
+        let amount = public_inputs.amount;
+        let merkle_root = public_inputs.merkle_root;
 
         // Check recipient matches
         if recipient != recipient_from_witness {
             return Err(ErrorCode::RecipientMismatch.into());
         }
 
-        // Verify proof
-        let mut verifier: GnarkVerifier<NR_INPUTS> = GnarkVerifier::new(&vk::VK);
+        // Verify proof against the verifying key version chosen by the caller
+        let verifying_key = &ctx.accounts.verifying_key;
+        require!(!verifying_key.retired, ErrorCode::VerifyingKeyRetired);
+        require!(
+            verifying_key.is_complete(),
+            ErrorCode::VerifyingKeyIncomplete
+        );
+        let vk = verifying_key.verifying_key();
+        let mut verifier: GnarkVerifier<NR_PUBLIC_INPUTS> = GnarkVerifier::new(&vk);
         verifier
             .verify(proof, pub_witness)
             .map_err(|_| ErrorCode::InvalidProof)?;
@@ -68,7 +73,8 @@ pub mod condenser {
             signer_seeds,
         );
 
-        // Mint the delta between the total withdrawn and the amount specified in the proof
+        // Mint the delta between the total withdrawn and the amount specified in the proof.
+        // The circuit only bounds the amount by the deposit, so a deposit can be condensed over several proofs
         let withdrawn = &mut ctx.accounts.withdrawn;
         let delta = amount
             .checked_sub(withdrawn.total_withdrawn)
@@ -81,6 +87,92 @@ pub mod condenser {
 
         Ok(())
     }
+
+    /// Register a verifying key for condensing under `version`.
+    /// The account is sized for the whole key, which is filled in by `write_verifying_key`
+    pub fn register_verifying_key(
+        ctx: Context<RegisterVerifyingKey>,
+        version: u32,
+        header: VerifyingKeyHeader,
+    ) -> Result<()> {
+        let bump = ctx.bumps.verifying_key;
+        register(
+            &mut ctx.accounts.verifying_key,
+            Circuit::Condenser,
+            version,
+            bump,
+            header,
+        )
+    }
+
+    /// Register the verifying key disclosures are checked against under `version`.
+    /// It is never used on chain, registering it publishes the key of the mint's trusted setup.
+    /// Written and retired like a condenser key
+    pub fn register_disclosure_key(
+        ctx: Context<RegisterDisclosureKey>,
+        version: u32,
+        header: VerifyingKeyHeader,
+    ) -> Result<()> {
+        let bump = ctx.bumps.verifying_key;
+        register(
+            &mut ctx.accounts.verifying_key,
+            Circuit::Disclosure,
+            version,
+            bump,
+            header,
+        )
+    }
+
+    /// Append K points and commitment keys to a registered verifying key
+    pub fn write_verifying_key(
+        ctx: Context<UpdateVerifyingKey>,
+        k: Vec<[u8; registry::G1_LEN]>,
+        commitment_keys: Vec<[u8; registry::COMMITMENT_KEY_LEN]>,
+    ) -> Result<()> {
+        let verifying_key = &mut ctx.accounts.verifying_key;
+        let nr_commitments = verifying_key.header.nr_commitments;
+        require!(
+            verifying_key.k.len() + k.len()
+                <= VerifyingKeyAccount::k_len(verifying_key.circuit, nr_commitments)
+                && verifying_key.commitment_keys.len() + commitment_keys.len()
+                    <= nr_commitments as usize,
+            ErrorCode::VerifyingKeyOverflow
+        );
+
+        verifying_key.k.extend(k);
+        verifying_key.commitment_keys.extend(commitment_keys);
+
+        Ok(())
+    }
+
+    /// Stop a verifying key from being used to condense. Proofs made for it must be remade for another version
+    pub fn retire_verifying_key(ctx: Context<UpdateVerifyingKey>) -> Result<()> {
+        ctx.accounts.verifying_key.retired = true;
+        Ok(())
+    }
+}
+
+fn register(
+    verifying_key: &mut VerifyingKeyAccount,
+    circuit: Circuit,
+    version: u32,
+    bump: u8,
+    header: VerifyingKeyHeader,
+) -> Result<()> {
+    require!(
+        header.nr_commitments as usize <= MAX_COMMITMENTS,
+        ErrorCode::TooManyCommitments
+    );
+
+    verifying_key.version = version;
+    verifying_key.bump = bump;
+    verifying_key.retired = false;
+    verifying_key.circuit = circuit;
+    verifying_key.header = header;
+    verifying_key.k = Vec::new();
+    verifying_key.commitment_keys = Vec::new();
+
+    Ok(())
 }
 
 #[derive(Accounts)]
@@ -91,7 +183,11 @@ pub struct Condense<'info> {
     pub mint: InterfaceAccount<'info, Mint>,
 
     /// Recipient token account (Token-2022)
-    #[account(mut)]
+    /// Must belong to the recipient so a condense seen in flight can't be replayed to mint to another account
+    #[account(
+        mut,
+        constraint = to.owner == recipient @ ErrorCode::DestinationNotOwnedByRecipient
+    )]
     pub to: InterfaceAccount<'info, TokenAccount>,
 
     /// PDA that is set as the mint's mint_authority
@@ -114,6 +210,14 @@ pub struct Condense<'info> {
     )]
     pub tree_account: AccountLoader<'info, MerkleTreeAccount>,
 
+    /// The verifying key the proof is checked against, which selects the version of the circuit
+    #[account(
+        seeds = [b"verifying_key", mint.key().as_ref(), &verifying_key.version.to_le_bytes()],
+        bump = verifying_key.bump,
+        constraint = verifying_key.circuit == Circuit::Condenser @ ErrorCode::InvalidProof
+    )]
+    pub verifying_key: Box<Account<'info, VerifyingKeyAccount>>,
+
     /// Tracks the total withdrawn amount for each recipient
     /// This is how double-spends are prevented without nullifiers
     #[account(
@@ -131,9 +235,80 @@ pub struct Condense<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(version: u32, header: VerifyingKeyHeader)]
+pub struct RegisterVerifyingKey<'info> {
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VerifyingKeyAccount::space(Circuit::Condenser, header.nr_commitments),
+        seeds = [b"verifying_key", mint.key().as_ref(), &version.to_le_bytes()],
+        bump
+    )]
+    pub verifying_key: Box<Account<'info, VerifyingKeyAccount>>,
+
+    /// Verifying keys are managed by the transfer hook authority of the mint
+    #[account(
+        mut,
+        constraint = transfer_hook_authority(&mint.to_account_info())? == Some(authority.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(version: u32, header: VerifyingKeyHeader)]
+pub struct RegisterDisclosureKey<'info> {
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VerifyingKeyAccount::space(Circuit::Disclosure, header.nr_commitments),
+        seeds = [b"disclosure_key", mint.key().as_ref(), &version.to_le_bytes()],
+        bump
+    )]
+    pub verifying_key: Box<Account<'info, VerifyingKeyAccount>>,
+
+    #[account(
+        mut,
+        constraint = transfer_hook_authority(&mint.to_account_info())? == Some(authority.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateVerifyingKey<'info> {
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            verifying_key.circuit.seed(),
+            mint.key().as_ref(),
+            &verifying_key.version.to_le_bytes()
+        ],
+        bump = verifying_key.bump
+    )]
+    pub verifying_key: Box<Account<'info, VerifyingKeyAccount>>,
+
+    #[account(
+        constraint = transfer_hook_authority(&mint.to_account_info())? == Some(authority.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
 #[account]
 pub struct WithdrawnTracker {
-    total_withdrawn: u64,
+    pub total_withdrawn: u64,
 }
 
 #[error_code]
@@ -150,4 +325,16 @@ pub enum ErrorCode {
     MerkleRootNotInHistory,
     #[msg("arithmetic overflow")]
     ArithmeticOverflow,
+    #[msg("destination token account is not owned by the recipient")]
+    DestinationNotOwnedByRecipient,
+    #[msg("signer is not the transfer hook authority of the mint")]
+    Unauthorized,
+    #[msg("verifying key has more commitments than supported")]
+    TooManyCommitments,
+    #[msg("write exceeds the size of the verifying key")]
+    VerifyingKeyOverflow,
+    #[msg("verifying key has not been completely written")]
+    VerifyingKeyIncomplete,
+    #[msg("verifying key has been retired")]
+    VerifyingKeyRetired,
 }
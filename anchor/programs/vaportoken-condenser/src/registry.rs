@@ -0,0 +1,119 @@
+//! Registry of the verifying keys condense proofs are checked against.
+//!
+//! Keys are registered per mint under a version by the transfer hook authority of the mint.
+//! The keys of the `disclosure` circuit are registered alongside so that anyone checking a disclosure
+//! verifies it against the key the mint's authority published rather than one set up on their own machine.
+//! A key does not fit in one transaction, so it is registered with its fixed size points and
+//! then filled in by one or more writes. It can be used to condense once complete and until it is retired,
+//! so a circuit change only needs a new key registered rather than a program redeploy.
+use anchor_lang::prelude::*;
+use gnark_verifier_solana::vk::GnarkVerifyingkey;
+
+/// Number of public inputs of the condenser circuit: the recipient packed into two fields, the amount and the merkle root
+pub const NR_PUBLIC_INPUTS: usize = 4;
+
+/// Number of public inputs of the disclosure circuit: the recipient packed into two fields and the vapor address bytes
+pub const DISCLOSURE_NR_PUBLIC_INPUTS: usize = 2 + 32;
+
+/// Most commitment keys a registered verifying key can have
+pub const MAX_COMMITMENTS: usize = 4;
+
+pub const G1_LEN: usize = 64;
+pub const G2_LEN: usize = 128;
+/// A Pedersen commitment key is two G2 points
+pub const COMMITMENT_KEY_LEN: usize = 2 * G2_LEN;
+
+/// The points of a verifying key that do not depend on the number of commitments.
+/// Only keys whose commitments commit to no public inputs can be registered, as is the case for sunspot circuits
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VerifyingKeyHeader {
+    pub alpha_g1: [u8; G1_LEN],
+    pub beta_g2: [u8; G2_LEN],
+    pub gamma_g2: [u8; G2_LEN],
+    pub delta_g2: [u8; G2_LEN],
+    pub nr_commitments: u8,
+}
+
+impl VerifyingKeyHeader {
+    pub const LEN: usize = G1_LEN + 3 * G2_LEN + 1;
+}
+
+/// The circuit a registered key verifies proofs of
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Circuit {
+    Condenser,
+    Disclosure,
+}
+
+impl Circuit {
+    pub fn nr_public_inputs(self) -> usize {
+        match self {
+            Circuit::Condenser => NR_PUBLIC_INPUTS,
+            Circuit::Disclosure => DISCLOSURE_NR_PUBLIC_INPUTS,
+        }
+    }
+
+    /// First seed of the PDA keys of this circuit are registered at, followed by the mint and the version
+    pub fn seed(self) -> &'static [u8] {
+        match self {
+            Circuit::Condenser => b"verifying_key",
+            Circuit::Disclosure => b"disclosure_key",
+        }
+    }
+}
+
+#[account]
+#[derive(Debug)]
+pub struct VerifyingKeyAccount {
+    pub version: u32,
+    pub bump: u8,
+    /// Retired keys can no longer be used to condense
+    pub retired: bool,
+    pub circuit: Circuit,
+    pub header: VerifyingKeyHeader,
+    /// One point for each public input plus one, then one for each commitment
+    pub k: Vec<[u8; G1_LEN]>,
+    pub commitment_keys: Vec<[u8; COMMITMENT_KEY_LEN]>,
+}
+
+impl VerifyingKeyAccount {
+    /// Space taken by a complete key of `circuit` with `nr_commitments` commitments, excluding the discriminator
+    pub fn space(circuit: Circuit, nr_commitments: u8) -> usize {
+        4 + 1
+            + 1
+            + 1
+            + VerifyingKeyHeader::LEN
+            + 4
+            + G1_LEN * Self::k_len(circuit, nr_commitments)
+            + 4
+            + COMMITMENT_KEY_LEN * nr_commitments as usize
+    }
+
+    pub fn k_len(circuit: Circuit, nr_commitments: u8) -> usize {
+        circuit.nr_public_inputs() + 1 + nr_commitments as usize
+    }
+
+    /// Whether every point has been written
+    pub fn is_complete(&self) -> bool {
+        self.k.len() == Self::k_len(self.circuit, self.header.nr_commitments)
+            && self.commitment_keys.len() == self.header.nr_commitments as usize
+    }
+
+    /// The key in the form taken by the verifier. Only meaningful once complete
+    pub fn verifying_key(&self) -> GnarkVerifyingkey<'_> {
+        GnarkVerifyingkey {
+            nr_pubinputs: self.circuit.nr_public_inputs(),
+            alpha_g1: self.header.alpha_g1,
+            beta_g2: self.header.beta_g2,
+            gamma_g2: self.header.gamma_g2,
+            delta_g2: self.header.delta_g2,
+            k: &self.k,
+            commitment_keys: &self.commitment_keys,
+            public_and_commitment_committed: &no_committed_inputs()[..self.commitment_keys.len()],
+        }
+    }
+}
+
+fn no_committed_inputs<T>() -> &'static [&'static [T]] {
+    &[&[] as &[T]; MAX_COMMITMENTS]
+}
@@ -1,24 +0,0 @@
-// === Auto-generated verifying key constant ===
-use gnark_verifier_solana::vk::GnarkVerifyingkey;
-#[rustfmt::skip]
-pub const VK: GnarkVerifyingkey<'static> = GnarkVerifyingkey {
-    nr_pubinputs: 4,
-    alpha_g1: [0x15, 0xc5, 0x4c, 0x80, 0x47, 0x80, 0x13, 0xa4, 0x62, 0x24, 0x4a, 0xdd, 0x1a, 0xc0, 0x68, 0x89, 0xcf, 0xc1, 0x06, 0x2b, 0x7f, 0xa6, 0x9d, 0xe8, 0x11, 0x54, 0xfb, 0xbd, 0xc1, 0x91, 0xdc, 0x7a, 0x1a, 0x0a, 0x33, 0x2a, 0xbd, 0xa5, 0xc9, 0x6b, 0x09, 0x3f, 0x0b, 0x9d, 0x14, 0xef, 0xba, 0x67, 0x32, 0x66, 0xcb, 0xe2, 0x39, 0xc2, 0xb0, 0xed, 0xe0, 0x5d, 0xe8, 0x86, 0x0d, 0xaf, 0x4a, 0xac],
-    beta_g2: [0x05, 0x70, 0x03, 0x8c, 0x1a, 0xae, 0x26, 0x5f, 0x7a, 0x7c, 0x9b, 0xc8, 0xf0, 0x95, 0xe7, 0xab, 0x65, 0x89, 0x5b, 0x39, 0x38, 0xb1, 0x1b, 0x7d, 0x9f, 0x27, 0x8d, 0x7f, 0x8e, 0xa8, 0xb0, 0xfe, 0x0a, 0x1e, 0xe3, 0x06, 0x2e, 0xd9, 0xc3, 0xef, 0xe2, 0x84, 0xa4, 0x52, 0x7b, 0x61, 0x3c, 0xed, 0xeb, 0x51, 0xfc, 0x94, 0x72, 0x1b, 0x4a, 0xc4, 0x0b, 0x2f, 0x3b, 0x42, 0x4d, 0xc7, 0x58, 0x77, 0x09, 0xff, 0xa4, 0x84, 0x04, 0x69, 0x17, 0x41, 0xf8, 0x0c, 0xe6, 0xdd, 0x86, 0x8a, 0x91, 0x56, 0xb4, 0xaa, 0xfe, 0xfb, 0x9d, 0x24, 0xe3, 0xe8, 0x32, 0xc8, 0x9a, 0x1e, 0xb5, 0x25, 0x20, 0x17, 0x10, 0x6d, 0xcc, 0x21, 0x30, 0xad, 0x26, 0x99, 0xe3, 0x9e, 0x46, 0x1e, 0x3e, 0x0e, 0xc9, 0x49, 0x61, 0x95, 0xf7, 0xaf, 0x30, 0x88, 0xb9, 0x18, 0x46, 0x61, 0x9d, 0x9a, 0x6e, 0x42, 0x7b, 0x02],
-    gamma_g2: [0x1a, 0x9c, 0x7b, 0x56, 0x30, 0x39, 0xba, 0x45, 0x7b, 0x2a, 0x6e, 0x92, 0x94, 0x22, 0x06, 0xf6, 0x56, 0xe2, 0x3a, 0x3f, 0x7a, 0xab, 0xea, 0x0d, 0x24, 0x9a, 0x7a, 0xf3, 0x2e, 0x89, 0xc6, 0x40, 0x07, 0xcc, 0xf6, 0x07, 0x6e, 0x28, 0x48, 0xd5, 0x21, 0x83, 0x76, 0x96, 0x18, 0xfc, 0xc5, 0xff, 0x92, 0xd9, 0xe9, 0x2a, 0x68, 0xfa, 0x8e, 0xdd, 0xa7, 0x3d, 0xa4, 0xb7, 0x17, 0xba, 0xf6, 0xb7, 0x1a, 0xcd, 0xe9, 0x42, 0xb1, 0x2e, 0xfd, 0x81, 0x59, 0x78, 0xd2, 0xd0, 0xb8, 0x85, 0x5a, 0xf7, 0xf4, 0x5e, 0x49, 0x42, 0x64, 0xff, 0x04, 0x3c, 0xdc, 0x9d, 0x8b, 0x0d, 0xfe, 0x50, 0x5a, 0xc1, 0x17, 0x84, 0x0f, 0x96, 0xce, 0x2a, 0xd2, 0x47, 0x6a, 0x43, 0x2c, 0x76, 0x8a, 0xb7, 0x81, 0x80, 0xcf, 0x05, 0x9c, 0x77, 0xef, 0xa8, 0x4c, 0xec, 0x72, 0xb7, 0x18, 0xf4, 0x02, 0x89, 0x92, 0x87],
-    delta_g2: [0x07, 0x95, 0xe7, 0xf9, 0x75, 0xca, 0x3a, 0x13, 0x82, 0x68, 0x2f, 0xb2, 0xb9, 0x5c, 0x39, 0x48, 0x45, 0x90, 0x2c, 0x76, 0xa8, 0x11, 0x1a, 0xd1, 0x38, 0x59, 0x5f, 0xbc, 0x40, 0x60, 0xf4, 0x3c, 0x1d, 0x95, 0x99, 0x88, 0x01, 0x28, 0x15, 0x2e, 0xea, 0x24, 0xfd, 0x6b, 0xcc, 0xf1, 0x5d, 0x3b, 0x72, 0xda, 0x58, 0xc4, 0x8a, 0x9f, 0x56, 0x06, 0xb5, 0x71, 0x35, 0x3c, 0x89, 0xf3, 0x26, 0x1b, 0x04, 0x3e, 0x2f, 0xd8, 0xbd, 0x77, 0x1b, 0xd8, 0xa4, 0xda, 0x68, 0xbf, 0x34, 0x1e, 0x5d, 0x67, 0xb1, 0x68, 0x72, 0x27, 0x59, 0x93, 0x5c, 0xe6, 0x5d, 0x4f, 0x30, 0xf1, 0x97, 0xa9, 0x9f, 0x70, 0x0e, 0x30, 0x37, 0xc3, 0xfd, 0x3e, 0xa4, 0x8e, 0x7f, 0x38, 0xea, 0xea, 0xdb, 0xe8, 0x81, 0x66, 0x7d, 0xaa, 0xed, 0xcc, 0x3f, 0x68, 0x32, 0x68, 0x6c, 0x38, 0xce, 0x39, 0x3b, 0x8b, 0x36, 0x4d],
-    k: &[
-        [0x28, 0xfe, 0x4a, 0xa3, 0xae, 0xf7, 0x83, 0xba, 0x61, 0x9c, 0x95, 0x65, 0xe4, 0x8a, 0x20, 0x95, 0x02, 0x05, 0xe0, 0xa6, 0x5e, 0xdd, 0x0d, 0x68, 0xea, 0x91, 0x49, 0xf2, 0xf4, 0xbb, 0xbf, 0x52, 0x2d, 0x66, 0xf1, 0x7b, 0xf2, 0xb7, 0xab, 0x8a, 0x1e, 0x4e, 0x3f, 0xe7, 0x96, 0x57, 0xf3, 0x7e, 0xa1, 0x26, 0xa0, 0xed, 0x0b, 0xdb, 0x13, 0x84, 0xc8, 0x82, 0x49, 0xd1, 0x3c, 0x01, 0x45, 0x8c],
-        [0x2e, 0x83, 0x26, 0xc8, 0x7b, 0xf9, 0xd2, 0x01, 0x63, 0xfa, 0xe8, 0x95, 0xb2, 0x15, 0xf2, 0xf7, 0x9a, 0xb6, 0xc2, 0x2e, 0x43, 0x20, 0xe8, 0x7a, 0xc3, 0xba, 0x29, 0xf5, 0x64, 0x0c, 0xa4, 0x49, 0x0a, 0xe1, 0xdb, 0xf7, 0x94, 0x98, 0x43, 0xa8, 0xc0, 0x50, 0xc4, 0xf5, 0x86, 0x2e, 0x5f, 0xdc, 0xac, 0xdc, 0xab, 0xfb, 0x36, 0x74, 0x00, 0x6e, 0x2c, 0x09, 0x0b, 0x76, 0x3d, 0x97, 0x8f, 0xf1],
-        [0x14, 0xee, 0xa9, 0xbb, 0xbc, 0xe0, 0x71, 0x1b, 0x0e, 0xb1, 0x97, 0x91, 0x54, 0x09, 0x6a, 0x75, 0x8d, 0xc8, 0xa5, 0x9f, 0x12, 0x25, 0xbc, 0x47, 0x5f, 0x82, 0xe4, 0x4f, 0x94, 0x85, 0x5e, 0x63, 0x0c, 0x5d, 0xf1, 0xc3, 0x1a, 0x0f, 0x90, 0x31, 0xb2, 0x4b, 0x69, 0xb4, 0x62, 0xc5, 0xe0, 0xd0, 0x01, 0xf9, 0x88, 0xa6, 0x36, 0xd7, 0x94, 0xf1, 0xa9, 0xbc, 0xfd, 0x4e, 0x0a, 0x1b, 0xe5, 0xe7],
-        [0x17, 0x07, 0xaf, 0xba, 0x7b, 0xcb, 0xbf, 0xc3, 0xe6, 0xed, 0x69, 0x5a, 0xe9, 0x5f, 0x53, 0xa7, 0x59, 0xc5, 0xca, 0x71, 0x13, 0x96, 0xed, 0xaf, 0x9e, 0x60, 0x8d, 0x66, 0x88, 0x3a, 0xb8, 0xe4, 0x1e, 0xe4, 0x4c, 0x92, 0xe9, 0x95, 0x69, 0x36, 0x32, 0xdb, 0xa3, 0xda, 0xa8, 0x70, 0x28, 0x29, 0x68, 0xa5, 0xc9, 0x4d, 0x8c, 0xcd, 0x5f, 0x59, 0x21, 0xb5, 0xee, 0xfc, 0x6a, 0xb7, 0x82, 0xdb],
-        [0x2c, 0x6c, 0xfc, 0xac, 0x0a, 0x7a, 0x89, 0x49, 0x6a, 0xd2, 0x2f, 0x21, 0x7a, 0xf5, 0x0e, 0xfb, 0xa4, 0x35, 0x82, 0xea, 0x41, 0x61, 0x9e, 0xb2, 0x43, 0x9f, 0xdf, 0x03, 0xf9, 0x2f, 0xe1, 0xc1, 0x13, 0x3c, 0x55, 0xf3, 0x49, 0xb2, 0x83, 0x3c, 0xf8, 0x47, 0xc9, 0x63, 0x37, 0x0a, 0xd6, 0x81, 0x72, 0xa8, 0xde, 0x60, 0x8c, 0x64, 0x92, 0xbd, 0x51, 0x4e, 0xd8, 0x2f, 0x73, 0xd4, 0x52, 0x1f],
-        [0x02, 0xe9, 0x38, 0x74, 0xd6, 0x3a, 0xdf, 0x8f, 0xa6, 0xfe, 0xc4, 0xb6, 0xec, 0xe2, 0x20, 0x6a, 0xb2, 0xa7, 0x1e, 0xa1, 0x54, 0x1e, 0x6f, 0xd4, 0xd7, 0xeb, 0xcd, 0x2f, 0x83, 0x7a, 0x14, 0x42, 0x21, 0x40, 0xcd, 0xeb, 0x12, 0x1e, 0x4d, 0xce, 0xe0, 0xbf, 0xd8, 0x7c, 0x15, 0x65, 0x68, 0xdd, 0x2e, 0x10, 0xa9, 0x91, 0x21, 0x51, 0xad, 0xe3, 0x03, 0xcc, 0x64, 0x6f, 0xcb, 0xc4, 0x64, 0x5d],
-    ],
-    commitment_keys: &[
-        [0x0f, 0x3b, 0xa0, 0x54, 0x68, 0xe6, 0x17, 0x3d, 0xfd, 0xef, 0x22, 0xa4, 0xb9, 0x52, 0x51, 0x83, 0x93, 0x51, 0xf5, 0xa7, 0xee, 0x6e, 0x17, 0x63, 0x4d, 0x19, 0x44, 0x40, 0x3e, 0xe9, 0xde, 0xd4, 0x27, 0x1b, 0x70, 0x6a, 0xa4, 0xab, 0xa8, 0x5f, 0x2e, 0x65, 0x69, 0x21, 0xb0, 0x21, 0x88, 0x84, 0x94, 0xf2, 0x05, 0xef, 0xd1, 0x0b, 0x2b, 0x9e, 0x28, 0xe0, 0xfc, 0x79, 0xba, 0x99, 0xaa, 0xe4, 0x20, 0x34, 0xfb, 0x51, 0xe4, 0xe2, 0xa3, 0x18, 0x37, 0x44, 0x02, 0xf7, 0xa7, 0xed, 0x26, 0x13, 0xab, 0x14, 0x86, 0x7f, 0x98, 0xf4, 0xfb, 0x77, 0xdc, 0x70, 0x09, 0x78, 0x1a, 0x85, 0x24, 0x6e, 0x1a, 0x5c, 0x24, 0xd0, 0x91, 0x59, 0xa9, 0x5d, 0x91, 0x4a, 0xba, 0x7b, 0x16, 0xb1, 0xae, 0xa9, 0x8c, 0x45, 0x72, 0x51, 0x14, 0x51, 0xe2, 0xd2, 0xfd, 0xba, 0x0e, 0x45, 0xbd, 0x9c, 0x8f, 0x2b, 0x10, 0xd3, 0x02, 0x01, 0x4d, 0x46, 0x31, 0xea, 0x0c, 0x6b, 0xad, 0xa5, 0x68, 0xab, 0x8c, 0xbc, 0x93, 0x5d, 0xad, 0x0c, 0xcf, 0x50, 0xc5, 0x3f, 0x7f, 0x22, 0xdd, 0x9f, 0xc5, 0xb8, 0xaf, 0x5e, 0x0a, 0x75, 0x6e, 0xde, 0xbb, 0x4a, 0x5c, 0x79, 0x26, 0xc0, 0xd7, 0x48, 0xf1, 0xec, 0x70, 0x23, 0x99, 0x9e, 0x4e, 0xa9, 0xd8, 0x2c, 0xed, 0x85, 0xa0, 0xf0, 0xb4, 0x56, 0x30, 0xf1, 0x49, 0x55, 0x25, 0x2a, 0xfa, 0x43, 0x0c, 0x16, 0x83, 0x3b, 0x0b, 0x7f, 0x87, 0xf6, 0xcb, 0xd4, 0x10, 0xc6, 0xfc, 0x7e, 0x8d, 0x26, 0x45, 0xca, 0x44, 0x9f, 0xb0, 0x13, 0x25, 0x74, 0x9c, 0xe6, 0xf8, 0x6a, 0x2b, 0x22, 0xe7, 0x9a, 0x3e, 0x67, 0x02, 0xfb, 0xfc, 0x6c, 0xcb, 0x5a, 0x9f, 0x64, 0xb0, 0xc0, 0xfb, 0x6e, 0xab, 0xc2, 0x45, 0x34, 0x29, 0x31, 0x6d, 0x36, 0xb0, 0x2b, 0x99, 0x63, 0xc6, 0x0c],
-    ],
-    public_and_commitment_committed: &[
-        &[],
-    ],
-};
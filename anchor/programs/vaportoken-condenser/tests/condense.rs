@@ -0,0 +1,1085 @@
+//! In-process tests for the transfer hook and condenser programs
+//!
+//! These load the programs built by `anchor build` into LiteSVM and replay the scenario described by
+//! `fixtures/Prover.toml`. The fixture proof, public witness and verifying key in `fixtures` were produced
+//! from that same Prover.toml by one trusted setup, and are refreshed together with `just condenser_fixture`.
+use std::{path::PathBuf, str::FromStr};
+
+use anchor_lang::{AccountDeserialize, Discriminator, InstructionData, ToAccountMetas};
+use anchor_spl::{
+    associated_token::spl_associated_token_account::{
+        get_associated_token_address_with_program_id,
+        instruction::create_associated_token_account_idempotent,
+    },
+    token_2022::spl_token_2022::{
+        self,
+        extension::{transfer_fee, transfer_hook, ExtensionType, StateWithExtensions},
+        instruction::AuthorityType,
+        state::{Account as TokenAccount, Mint},
+    },
+};
+use ark_bn254::Fr as NoirField;
+use litesvm::LiteSVM;
+use solana_sdk::{
+    account::Account,
+    compute_budget::ComputeBudgetInstruction,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use transfer_tree::{Poseidon, TransferTree};
+use utils::{fr_to_be_32, unpack_bytes_from_le_fields};
+use vaportoken_condenser::{
+    registry::{Circuit, VerifyingKeyAccount, VerifyingKeyHeader},
+    ErrorCode as CondenserError, WithdrawnTracker,
+};
+use vaportoken_transfer_hook::{
+    AdmissionPolicy, ErrorCode as HookError, MerkleTree, MerkleTreeAccount,
+};
+
+const TREE_HEIGHT: usize = 26;
+const DECIMALS: u8 = 9;
+/// Version the fixture verifying key is registered under
+const VK_VERSION: u32 = 1;
+
+const PROVER_TOML: &str = include_str!("fixtures/Prover.toml");
+const PROOF: &[u8] = include_bytes!("fixtures/condenser.proof");
+const PUBLIC_WITNESS: &[u8] = include_bytes!("fixtures/condenser.pw");
+const VERIFYING_KEY: &[u8] = include_bytes!("fixtures/condenser.vk");
+
+/// The deposit described by `fixtures/Prover.toml`
+struct Fixture {
+    vapor_addr: Pubkey,
+    /// Total withdrawn to the recipient once the fixture proof is applied
+    amount: u64,
+    /// Amount transferred to the vapor address, at least `amount`
+    deposit_amount: u64,
+    leaf_index: usize,
+    /// Leaves that precede the deposit, taken from the sibling nodes of the proof
+    preceding_leaves: Vec<[u8; 32]>,
+    merkle_root: [u8; 32],
+}
+
+impl Fixture {
+    fn load() -> Self {
+        let prover: toml::Table = PROVER_TOML.parse().expect("invalid Prover.toml");
+
+        let strings = |key: &str| -> Vec<String> {
+            prover[key]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|v| v.as_str().unwrap().to_string())
+                .collect()
+        };
+        let field = |s: &str| fr_to_be_32(&NoirField::from_str(s).unwrap());
+
+        let vapor_addr: [u8; 32] = strings("vapor_addr")
+            .iter()
+            .map(|b| b.parse::<u8>().unwrap())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let amount = prover["amount"].as_str().unwrap().parse().unwrap();
+        let deposit_amount = prover["deposit_amount"].as_str().unwrap().parse().unwrap();
+        let merkle_root = field(prover["merkle_root"].as_str().unwrap());
+
+        let leaf_index = strings("merkle_proof_indices")
+            .iter()
+            .enumerate()
+            .map(|(i, bit)| bit.parse::<usize>().unwrap() << i)
+            .sum::<usize>();
+
+        // Only a deposit at index 0 or 1 can be replayed from the proof alone,
+        // as the sibling at level 0 is then the only leaf preceding it
+        assert!(
+            leaf_index <= 1,
+            "fixture deposit must be one of the first two leaves"
+        );
+        let preceding_leaves = strings("merkle_proof")
+            .iter()
+            .take(leaf_index)
+            .map(|s| field(s))
+            .collect();
+
+        Self {
+            vapor_addr: Pubkey::new_from_array(vapor_addr),
+            amount,
+            deposit_amount,
+            leaf_index,
+            preceding_leaves,
+            merkle_root,
+        }
+    }
+
+    /// The fixture proof and public witness
+    fn proof(&self) -> (Vec<u8>, Vec<u8>) {
+        (PROOF.to_vec(), PUBLIC_WITNESS.to_vec())
+    }
+
+    /// The verifying key of the trusted setup the fixture proof was made with
+    fn verifying_key(&self) -> VerifyingKey {
+        VerifyingKey::from_bytes(VERIFYING_KEY)
+    }
+}
+
+/// A verifying key split into the parts the registry takes
+#[derive(Clone)]
+struct VerifyingKey {
+    header: VerifyingKeyHeader,
+    k: Vec<[u8; 64]>,
+    commitment_keys: Vec<[u8; 256]>,
+}
+
+impl VerifyingKey {
+    /// Parse gnark's uncompressed encoding, skipping [β]1 and [δ]1 which the verifier does not use
+    fn from_bytes(mut bytes: &[u8]) -> Self {
+        fn take<const N: usize>(bytes: &mut &[u8]) -> [u8; N] {
+            let (head, tail) = bytes.split_at(N);
+            *bytes = tail;
+            head.try_into().unwrap()
+        }
+        fn len(bytes: &mut &[u8]) -> usize {
+            u32::from_be_bytes(take(bytes)) as usize
+        }
+
+        let alpha_g1 = take(&mut bytes);
+        take::<64>(&mut bytes);
+        let beta_g2 = take(&mut bytes);
+        let gamma_g2 = take(&mut bytes);
+        take::<64>(&mut bytes);
+        let delta_g2 = take(&mut bytes);
+        let k = (0..len(&mut bytes)).map(|_| take(&mut bytes)).collect();
+        for _ in 0..len(&mut bytes) {
+            assert_eq!(
+                len(&mut bytes),
+                0,
+                "commitments to public inputs are not supported"
+            );
+        }
+        let commitment_keys: Vec<_> = (0..len(&mut bytes)).map(|_| take(&mut bytes)).collect();
+        assert!(bytes.is_empty(), "trailing bytes in verifying key");
+
+        Self {
+            header: VerifyingKeyHeader {
+                alpha_g1,
+                beta_g2,
+                gamma_g2,
+                delta_g2,
+                nr_commitments: commitment_keys.len() as u8,
+            },
+            k,
+            commitment_keys,
+        }
+    }
+}
+
+/// Recipient encoded in the first two public inputs of a sunspot public witness
+fn recipient_from_witness(pub_witness: &[u8]) -> Pubkey {
+    let entries: Vec<[u8; 32]> = pub_witness[12..]
+        .chunks(32)
+        .map(|c| c.try_into().unwrap())
+        .collect();
+    Pubkey::new_from_array(
+        unpack_bytes_from_le_fields(&entries[0..2], 32)
+            .try_into()
+            .unwrap(),
+    )
+}
+
+fn program_so(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(format!("../../target/deploy/{name}.so"))
+}
+
+struct TestEnv {
+    svm: LiteSVM,
+    payer: Keypair,
+    mint: Pubkey,
+    payer_ata: Pubkey,
+}
+
+impl TestEnv {
+    /// Deploy both programs, create a Token-2022 mint using the transfer hook, mint `supply` to the payer
+    /// and hand the mint authority to the condenser
+    fn new(supply: u64) -> Self {
+        Self::with_transfer_fee(supply, None)
+    }
+
+    /// As [`TestEnv::new`], with the transfer fee extension charging `(basis_points, maximum_fee)`
+    fn with_transfer_fee(supply: u64, transfer_fee: Option<(u16, u64)>) -> Self {
+        let mut env = Self::without_tree(transfer_fee);
+        let payer_key = env.payer.pubkey();
+        let payer = env.payer.insecure_clone();
+        env.initialize_tree(&payer).unwrap();
+
+        let initialize_extra_account_meta_list = Instruction {
+            program_id: vaportoken_transfer_hook::ID,
+            accounts: vaportoken_transfer_hook::accounts::InitializeExtraAccountMetaList {
+                payer: payer_key,
+                extra_account_meta_list: env.extra_account_meta_list(),
+                mint: env.mint,
+                token_program: spl_token_2022::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+                system_program: anchor_lang::system_program::ID,
+            }
+            .to_account_metas(None),
+            data: vaportoken_transfer_hook::instruction::InitializeExtraAccountMetaList {}.data(),
+        };
+        env.send(&[initialize_extra_account_meta_list], &[])
+            .unwrap();
+
+        let fund_payer = [
+            create_associated_token_account_idempotent(
+                &payer_key,
+                &payer_key,
+                &env.mint,
+                &spl_token_2022::ID,
+            ),
+            spl_token_2022::instruction::mint_to(
+                &spl_token_2022::ID,
+                &env.mint,
+                &env.payer_ata,
+                &payer_key,
+                &[],
+                supply,
+            )
+            .unwrap(),
+            spl_token_2022::instruction::set_authority(
+                &spl_token_2022::ID,
+                &env.mint,
+                Some(&env.mint_authority()),
+                AuthorityType::MintTokens,
+                &payer_key,
+                &[],
+            )
+            .unwrap(),
+        ];
+        env.send(&fund_payer, &[]).unwrap();
+
+        env
+    }
+
+    /// Deploy both programs and create a Token-2022 mint using the transfer hook, with the payer as its
+    /// transfer hook authority, but no transfer tree
+    fn without_tree(transfer_fee: Option<(u16, u64)>) -> Self {
+        let mut svm = LiteSVM::new();
+        svm.add_program_from_file(
+            vaportoken_transfer_hook::ID,
+            program_so("vaportoken_transfer_hook"),
+        )
+        .expect("transfer hook program not found, run `anchor build` first");
+        svm.add_program_from_file(vaportoken_condenser::ID, program_so("vaportoken_condenser"))
+            .expect("condenser program not found, run `anchor build` first");
+
+        let payer = Keypair::new();
+        svm.airdrop(&payer.pubkey(), 100_000_000_000).unwrap();
+
+        let mint = Keypair::new();
+        let mut extensions = vec![ExtensionType::TransferHook];
+        if transfer_fee.is_some() {
+            extensions.push(ExtensionType::TransferFeeConfig);
+        }
+        let mint_len = ExtensionType::try_calculate_account_len::<Mint>(&extensions).unwrap();
+
+        let mut env = Self {
+            payer_ata: get_associated_token_address_with_program_id(
+                &payer.pubkey(),
+                &mint.pubkey(),
+                &spl_token_2022::ID,
+            ),
+            svm,
+            payer,
+            mint: mint.pubkey(),
+        };
+        let payer_key = env.payer.pubkey();
+
+        let mut create_mint = vec![
+            solana_system_interface::instruction::create_account(
+                &payer_key,
+                &env.mint,
+                env.svm.minimum_balance_for_rent_exemption(mint_len),
+                mint_len as u64,
+                &spl_token_2022::ID,
+            ),
+            transfer_hook::instruction::initialize(
+                &spl_token_2022::ID,
+                &env.mint,
+                Some(payer_key),
+                Some(vaportoken_transfer_hook::ID),
+            )
+            .unwrap(),
+        ];
+        if let Some((basis_points, maximum_fee)) = transfer_fee {
+            create_mint.push(
+                transfer_fee::instruction::initialize_transfer_fee_config(
+                    &spl_token_2022::ID,
+                    &env.mint,
+                    Some(&payer_key),
+                    Some(&payer_key),
+                    basis_points,
+                    maximum_fee,
+                )
+                .unwrap(),
+            );
+        }
+        create_mint.push(
+            spl_token_2022::instruction::initialize_mint2(
+                &spl_token_2022::ID,
+                &env.mint,
+                &payer_key,
+                None,
+                DECIMALS,
+            )
+            .unwrap(),
+        );
+        env.send(&create_mint, &[&mint]).unwrap();
+
+        env
+    }
+
+    fn send(&mut self, ixs: &[Instruction], signers: &[&Keypair]) -> Result<(), TransactionError> {
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            ixs,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            self.svm.latest_blockhash(),
+        );
+        let result = self.svm.send_transaction(tx).map(|_| ()).map_err(|e| {
+            println!("{}", e.meta.logs.join("\n"));
+            e.err
+        });
+        self.svm.expire_blockhash();
+        result
+    }
+
+    /// Create the mint's transfer tree with `authority` as its authority
+    fn initialize_tree(&mut self, authority: &Keypair) -> Result<(), TransactionError> {
+        let initialize = Instruction {
+            program_id: vaportoken_transfer_hook::ID,
+            accounts: vaportoken_transfer_hook::accounts::Initialize {
+                mint: self.mint,
+                tree_account: self.tree_account(),
+                authority: authority.pubkey(),
+                system_program: anchor_lang::system_program::ID,
+            }
+            .to_account_metas(None),
+            data: vaportoken_transfer_hook::instruction::Initialize {}.data(),
+        };
+        self.send(&[initialize], &[authority])
+    }
+
+    fn tree_account(&self) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"merkle_tree", self.mint.as_ref()],
+            &vaportoken_transfer_hook::ID,
+        )
+        .0
+    }
+
+    fn extra_account_meta_list(&self) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"extra-account-metas", self.mint.as_ref()],
+            &vaportoken_transfer_hook::ID,
+        )
+        .0
+    }
+
+    fn mint_authority(&self) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"mint_authority", self.mint.as_ref()],
+            &vaportoken_condenser::ID,
+        )
+        .0
+    }
+
+    fn withdrawn(&self, recipient: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"withdrawn", self.mint.as_ref(), recipient.as_ref()],
+            &vaportoken_condenser::ID,
+        )
+        .0
+    }
+
+    fn verifying_key_account(&self, version: u32) -> Pubkey {
+        self.registered_key_account(Circuit::Condenser, version)
+    }
+
+    fn registered_key_account(&self, circuit: Circuit, version: u32) -> Pubkey {
+        Pubkey::find_program_address(
+            &[circuit.seed(), self.mint.as_ref(), &version.to_le_bytes()],
+            &vaportoken_condenser::ID,
+        )
+        .0
+    }
+
+    fn ata(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, &self.mint, &spl_token_2022::ID)
+    }
+
+    /// Transfer from the payer to the ATA of `to`, resolving the hook's extra accounts
+    fn transfer(&mut self, to: &Pubkey, amount: u64) -> Result<(), TransactionError> {
+        let payer_key = self.payer.pubkey();
+        let mut transfer = spl_token_2022::instruction::transfer_checked(
+            &spl_token_2022::ID,
+            &self.payer_ata,
+            &self.mint,
+            &self.ata(to),
+            &payer_key,
+            &[],
+            amount,
+            DECIMALS,
+        )
+        .unwrap();
+        transfer.accounts.extend([
+            AccountMeta::new(self.tree_account(), false),
+            AccountMeta::new_readonly(vaportoken_transfer_hook::ID, false),
+            AccountMeta::new_readonly(self.extra_account_meta_list(), false),
+        ]);
+
+        let create_ata = create_associated_token_account_idempotent(
+            &payer_key,
+            to,
+            &self.mint,
+            &spl_token_2022::ID,
+        );
+        self.send(&[create_ata, transfer], &[])
+    }
+
+    fn set_admission_policy(
+        &mut self,
+        authority: &Keypair,
+        policy: AdmissionPolicy,
+    ) -> Result<(), TransactionError> {
+        let set_policy = Instruction {
+            program_id: vaportoken_transfer_hook::ID,
+            accounts: vaportoken_transfer_hook::accounts::SetAdmissionPolicy {
+                mint: self.mint,
+                tree_account: self.tree_account(),
+                authority: authority.pubkey(),
+            }
+            .to_account_metas(None),
+            data: vaportoken_transfer_hook::instruction::SetAdmissionPolicy { policy }.data(),
+        };
+        self.send(&[set_policy], &[authority])
+    }
+
+    fn condense(
+        &mut self,
+        recipient: Pubkey,
+        proof_bytes: Vec<u8>,
+        pub_witness_bytes: Vec<u8>,
+    ) -> Result<(), TransactionError> {
+        self.condense_to(&recipient, recipient, proof_bytes, pub_witness_bytes)
+    }
+
+    /// Condense to the ATA of `owner`, which is the recipient's unless testing someone else's attempt
+    fn condense_to(
+        &mut self,
+        owner: &Pubkey,
+        recipient: Pubkey,
+        proof_bytes: Vec<u8>,
+        pub_witness_bytes: Vec<u8>,
+    ) -> Result<(), TransactionError> {
+        let payer_key = self.payer.pubkey();
+        let condense = Instruction {
+            program_id: vaportoken_condenser::ID,
+            accounts: vaportoken_condenser::accounts::Condense {
+                mint: self.mint,
+                to: self.ata(owner),
+                mint_authority: self.mint_authority(),
+                token_program: spl_token_2022::ID,
+                tree_account: self.tree_account(),
+                verifying_key: self.verifying_key_account(VK_VERSION),
+                withdrawn: self.withdrawn(&recipient),
+                payer: payer_key,
+                system_program: anchor_lang::system_program::ID,
+            }
+            .to_account_metas(None),
+            data: vaportoken_condenser::instruction::Condense {
+                recipient,
+                proof_bytes,
+                pub_witness_bytes,
+            }
+            .data(),
+        };
+        self.send(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(1_000_000),
+                create_associated_token_account_idempotent(
+                    &payer_key,
+                    owner,
+                    &self.mint,
+                    &spl_token_2022::ID,
+                ),
+                condense,
+            ],
+            &[],
+        )
+    }
+
+    /// Register `vk` under `version`, signed by `authority`, writing K and the commitment keys separately
+    fn register_verifying_key(
+        &mut self,
+        authority: &Keypair,
+        version: u32,
+        vk: &VerifyingKey,
+    ) -> Result<(), TransactionError> {
+        let register = Instruction {
+            program_id: vaportoken_condenser::ID,
+            accounts: vaportoken_condenser::accounts::RegisterVerifyingKey {
+                mint: self.mint,
+                verifying_key: self.verifying_key_account(version),
+                authority: authority.pubkey(),
+                system_program: anchor_lang::system_program::ID,
+            }
+            .to_account_metas(None),
+            data: vaportoken_condenser::instruction::RegisterVerifyingKey {
+                version,
+                header: vk.header.clone(),
+            }
+            .data(),
+        };
+        self.send(&[register], &[authority])?;
+        self.write_verifying_key(authority, version, vk.k.clone(), vec![])?;
+        self.write_verifying_key(authority, version, vec![], vk.commitment_keys.clone())
+    }
+
+    fn write_verifying_key(
+        &mut self,
+        authority: &Keypair,
+        version: u32,
+        k: Vec<[u8; 64]>,
+        commitment_keys: Vec<[u8; 256]>,
+    ) -> Result<(), TransactionError> {
+        let write = Instruction {
+            program_id: vaportoken_condenser::ID,
+            accounts: self.update_verifying_key_accounts(authority, version),
+            data: vaportoken_condenser::instruction::WriteVerifyingKey { k, commitment_keys }
+                .data(),
+        };
+        self.send(&[write], &[authority])
+    }
+
+    /// Register `vk` as the disclosure key under `version` and write all of its points in one go
+    fn register_disclosure_key(
+        &mut self,
+        authority: &Keypair,
+        version: u32,
+        vk: &VerifyingKey,
+    ) -> Result<(), TransactionError> {
+        let verifying_key = self.registered_key_account(Circuit::Disclosure, version);
+        let register = Instruction {
+            program_id: vaportoken_condenser::ID,
+            accounts: vaportoken_condenser::accounts::RegisterDisclosureKey {
+                mint: self.mint,
+                verifying_key,
+                authority: authority.pubkey(),
+                system_program: anchor_lang::system_program::ID,
+            }
+            .to_account_metas(None),
+            data: vaportoken_condenser::instruction::RegisterDisclosureKey {
+                version,
+                header: vk.header.clone(),
+            }
+            .data(),
+        };
+        let write = Instruction {
+            program_id: vaportoken_condenser::ID,
+            accounts: vaportoken_condenser::accounts::UpdateVerifyingKey {
+                mint: self.mint,
+                verifying_key,
+                authority: authority.pubkey(),
+            }
+            .to_account_metas(None),
+            data: vaportoken_condenser::instruction::WriteVerifyingKey {
+                k: vk.k.clone(),
+                commitment_keys: vk.commitment_keys.clone(),
+            }
+            .data(),
+        };
+        self.send(&[register, write], &[authority])
+    }
+
+    fn retire_verifying_key(
+        &mut self,
+        authority: &Keypair,
+        version: u32,
+    ) -> Result<(), TransactionError> {
+        let retire = Instruction {
+            program_id: vaportoken_condenser::ID,
+            accounts: self.update_verifying_key_accounts(authority, version),
+            data: vaportoken_condenser::instruction::RetireVerifyingKey {}.data(),
+        };
+        self.send(&[retire], &[authority])
+    }
+
+    fn update_verifying_key_accounts(&self, authority: &Keypair, version: u32) -> Vec<AccountMeta> {
+        vaportoken_condenser::accounts::UpdateVerifyingKey {
+            mint: self.mint,
+            verifying_key: self.verifying_key_account(version),
+            authority: authority.pubkey(),
+        }
+        .to_account_metas(None)
+    }
+
+    fn verifying_key(&self, version: u32) -> VerifyingKeyAccount {
+        self.registered_key(Circuit::Condenser, version)
+    }
+
+    fn registered_key(&self, circuit: Circuit, version: u32) -> VerifyingKeyAccount {
+        let account = self
+            .svm
+            .get_account(&self.registered_key_account(circuit, version))
+            .unwrap();
+        VerifyingKeyAccount::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    fn tree(&self) -> MerkleTreeAccount {
+        let account = self.svm.get_account(&self.tree_account()).unwrap();
+        bytemuck::pod_read_unaligned(&account.data[8..8 + std::mem::size_of::<MerkleTreeAccount>()])
+    }
+
+    /// Overwrite the on-chain tree so that it appears to already contain the given leaves.
+    /// These stand in for transfers made by others that the fixture proof commits to
+    fn seed_leaves(&mut self, leaves: &[[u8; 32]]) {
+        let mut tree = self.tree();
+        for leaf in leaves {
+            MerkleTree::append::<Poseidon>(*leaf, &mut tree).unwrap();
+        }
+
+        let mut account = self.svm.get_account(&self.tree_account()).unwrap();
+        account.data[8..8 + std::mem::size_of::<MerkleTreeAccount>()]
+            .copy_from_slice(bytemuck::bytes_of(&tree));
+        self.svm.set_account(self.tree_account(), account).unwrap();
+    }
+
+    fn token_balance(&self, owner: &Pubkey) -> u64 {
+        let account = self.svm.get_account(&self.ata(owner)).unwrap();
+        StateWithExtensions::<TokenAccount>::unpack(&account.data)
+            .unwrap()
+            .base
+            .amount
+    }
+
+    fn total_withdrawn(&self, recipient: &Pubkey) -> u64 {
+        let account = self.svm.get_account(&self.withdrawn(recipient)).unwrap();
+        WithdrawnTracker::try_deserialize(&mut account.data.as_slice())
+            .unwrap()
+            .total_withdrawn
+    }
+
+    /// Overwrite the recipient's withdrawn tracker as if earlier condenses had withdrawn `total`
+    fn set_total_withdrawn(&mut self, recipient: &Pubkey, total: u64) {
+        let mut data = WithdrawnTracker::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&total.to_le_bytes());
+        data.resize(8 + 64, 0);
+        let withdrawn = Account {
+            lamports: self.svm.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner: vaportoken_condenser::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.svm
+            .set_account(self.withdrawn(recipient), withdrawn)
+            .unwrap();
+    }
+}
+
+fn assert_custom_error(result: Result<(), TransactionError>, code: u32) {
+    match result {
+        Err(TransactionError::InstructionError(_, InstructionError::Custom(c))) => {
+            assert_eq!(c, code)
+        }
+        other => panic!("expected custom error {code}, got {other:?}"),
+    }
+}
+
+/// Register the fixture verifying key, replay the fixture deposit and check the on-chain tree agrees
+/// with one rebuilt by `transfer-tree`
+fn deposit_fixture(env: &mut TestEnv, fixture: &Fixture) {
+    let authority = env.payer.insecure_clone();
+    env.register_verifying_key(&authority, VK_VERSION, &fixture.verifying_key())
+        .unwrap();
+
+    env.seed_leaves(&fixture.preceding_leaves);
+    env.transfer(&fixture.vapor_addr, fixture.deposit_amount)
+        .unwrap();
+
+    let mut client_tree = TransferTree::<TREE_HEIGHT>::new();
+    for leaf in &fixture.preceding_leaves {
+        client_tree.append(*leaf).unwrap();
+    }
+    client_tree
+        .append_transfer(fixture.vapor_addr.to_bytes(), fixture.deposit_amount)
+        .unwrap();
+
+    let tree = env.tree();
+    assert_eq!(tree.next_index as usize, fixture.leaf_index + 1);
+    assert_eq!(tree.root, client_tree.root());
+    assert_eq!(tree.root, fixture.merkle_root);
+}
+
+#[test]
+fn test_transfers_are_recorded_in_tree() {
+    let mut env = TestEnv::new(10_000);
+    let mut client_tree = TransferTree::<TREE_HEIGHT>::new();
+    assert_eq!(env.tree().root, client_tree.root());
+
+    for i in 1..=5u64 {
+        let vapor_addr = Pubkey::new_unique();
+        env.transfer(&vapor_addr, i * 100).unwrap();
+        client_tree
+            .append_transfer(vapor_addr.to_bytes(), i * 100)
+            .unwrap();
+
+        let tree = env.tree();
+        assert_eq!(tree.next_index, i);
+        assert_eq!(tree.root, client_tree.root());
+    }
+}
+
+#[test]
+fn test_transfer_fee_is_not_recorded() {
+    // 1% fee capped at 50
+    let mut env = TestEnv::with_transfer_fee(1_000_000, Some((100, 50)));
+    let mut client_tree = TransferTree::<TREE_HEIGHT>::new();
+
+    for (amount, fee) in [(1_000, 10), (100_000, 50)] {
+        let vapor_addr = Pubkey::new_unique();
+        env.transfer(&vapor_addr, amount).unwrap();
+        assert_eq!(env.token_balance(&vapor_addr), amount - fee);
+
+        // The leaf commits to what the vapor address received, which is all a condense can mint back
+        client_tree
+            .append_transfer(vapor_addr.to_bytes(), amount - fee)
+            .unwrap();
+        assert_eq!(env.tree().root, client_tree.root());
+    }
+}
+
+#[test]
+fn test_admission_policy_skips_transfers() {
+    let mut env = TestEnv::new(10_000);
+    let authority = env.payer.insecure_clone();
+    let policy = AdmissionPolicy {
+        skip_off_curve_owners: true,
+        skip_zero_amounts: true,
+        min_amount: 100,
+    };
+    env.set_admission_policy(&authority, policy).unwrap();
+    assert_eq!(env.tree().admission_policy(), policy);
+
+    // Vapor addresses are on the curve, PDAs never are
+    let on_curve = Keypair::new().pubkey();
+    let off_curve = Pubkey::find_program_address(&[b"owner"], &vaportoken_condenser::ID).0;
+    for (to, amount) in [(on_curve, 0), (on_curve, 99), (off_curve, 500)] {
+        env.transfer(&to, amount).unwrap();
+    }
+    // Skipped transfers still move the tokens, they just take no leaf
+    assert_eq!(env.token_balance(&on_curve), 99);
+    assert_eq!(env.token_balance(&off_curve), 500);
+    assert_eq!(env.tree().next_index, 0);
+
+    env.transfer(&on_curve, 100).unwrap();
+    let mut client_tree = TransferTree::<TREE_HEIGHT>::new();
+    client_tree
+        .append_transfer(on_curve.to_bytes(), 100)
+        .unwrap();
+    assert_eq!(env.tree().next_index, 1);
+    assert_eq!(env.tree().root, client_tree.root());
+}
+
+#[test]
+fn test_admission_policy_requires_transfer_hook_authority() {
+    let mut env = TestEnv::new(10_000);
+    let other = Keypair::new();
+    env.svm.airdrop(&other.pubkey(), 1_000_000_000).unwrap();
+
+    let policy = AdmissionPolicy {
+        skip_zero_amounts: true,
+        ..Default::default()
+    };
+    assert_custom_error(
+        env.set_admission_policy(&other, policy),
+        HookError::Unauthorized.into(),
+    );
+    assert_eq!(env.tree().admission_policy(), AdmissionPolicy::default());
+}
+
+#[test]
+fn test_condense() {
+    let fixture = Fixture::load();
+    let (proof, pub_witness) = fixture.proof();
+    let recipient = recipient_from_witness(&pub_witness);
+
+    let mut env = TestEnv::new(fixture.deposit_amount);
+    deposit_fixture(&mut env, &fixture);
+
+    env.condense(recipient, proof, pub_witness).unwrap();
+    assert_eq!(env.token_balance(&recipient), fixture.amount);
+    assert_eq!(env.total_withdrawn(&recipient), fixture.amount);
+}
+
+#[test]
+fn test_condense_remainder_after_partial_withdrawal() {
+    let fixture = Fixture::load();
+    let (proof, pub_witness) = fixture.proof();
+    let recipient = recipient_from_witness(&pub_witness);
+    assert!(
+        fixture.amount < fixture.deposit_amount,
+        "fixture should withdraw part of its deposit"
+    );
+
+    let mut env = TestEnv::new(fixture.deposit_amount);
+    deposit_fixture(&mut env, &fixture);
+
+    // An earlier condense already withdrew some of the deposit, only the difference is minted
+    let earlier = fixture.amount / 3;
+    env.set_total_withdrawn(&recipient, earlier);
+
+    env.condense(recipient, proof, pub_witness).unwrap();
+    assert_eq!(env.token_balance(&recipient), fixture.amount - earlier);
+    assert_eq!(env.total_withdrawn(&recipient), fixture.amount);
+}
+
+#[test]
+fn test_condense_unknown_root() {
+    let fixture = Fixture::load();
+    let (proof, pub_witness) = fixture.proof();
+    let recipient = recipient_from_witness(&pub_witness);
+
+    // A different deposit amount gives a tree that never had the proven root
+    let mut env = TestEnv::new(fixture.deposit_amount);
+    let authority = env.payer.insecure_clone();
+    env.register_verifying_key(&authority, VK_VERSION, &fixture.verifying_key())
+        .unwrap();
+    env.seed_leaves(&fixture.preceding_leaves);
+    env.transfer(&fixture.vapor_addr, fixture.deposit_amount - 1)
+        .unwrap();
+
+    assert_custom_error(
+        env.condense(recipient, proof, pub_witness),
+        CondenserError::MerkleRootNotInHistory.into(),
+    );
+}
+
+#[test]
+fn test_condense_wrong_recipient() {
+    let fixture = Fixture::load();
+    let (proof, pub_witness) = fixture.proof();
+
+    let mut env = TestEnv::new(fixture.deposit_amount);
+    deposit_fixture(&mut env, &fixture);
+
+    assert_custom_error(
+        env.condense(Pubkey::new_unique(), proof, pub_witness),
+        CondenserError::RecipientMismatch.into(),
+    );
+}
+
+#[test]
+fn test_condense_front_run_to_other_account() {
+    let fixture = Fixture::load();
+    let (proof, pub_witness) = fixture.proof();
+    let recipient = recipient_from_witness(&pub_witness);
+
+    let mut env = TestEnv::new(fixture.deposit_amount);
+    deposit_fixture(&mut env, &fixture);
+
+    // Someone who sees the condense in flight replays its proof and witness, minting to their own account
+    let attacker = Pubkey::new_unique();
+    assert_custom_error(
+        env.condense_to(&attacker, recipient, proof.clone(), pub_witness.clone()),
+        CondenserError::DestinationNotOwnedByRecipient.into(),
+    );
+    assert_eq!(env.token_balance(&attacker), 0);
+
+    // The recipient's own condense still goes through
+    env.condense(recipient, proof, pub_witness).unwrap();
+    assert_eq!(env.token_balance(&recipient), fixture.amount);
+}
+
+#[test]
+fn test_condense_already_withdrawn() {
+    let fixture = Fixture::load();
+    let (proof, pub_witness) = fixture.proof();
+    let recipient = recipient_from_witness(&pub_witness);
+
+    let mut env = TestEnv::new(fixture.deposit_amount);
+    deposit_fixture(&mut env, &fixture);
+
+    // Pretend the recipient has already withdrawn more than this proof covers
+    env.set_total_withdrawn(&recipient, fixture.amount + 1);
+
+    assert_custom_error(
+        env.condense(recipient, proof, pub_witness),
+        CondenserError::BadAmount.into(),
+    );
+}
+
+#[test]
+fn test_register_verifying_key() {
+    let fixture = Fixture::load();
+    let vk = fixture.verifying_key();
+    let mut env = TestEnv::new(10_000);
+    let authority = env.payer.insecure_clone();
+
+    env.register_verifying_key(&authority, 3, &vk).unwrap();
+    let registered = env.verifying_key(3);
+    assert_eq!(registered.version, 3);
+    assert!(registered.is_complete());
+    assert!(!registered.retired);
+    assert_eq!(registered.header, vk.header);
+    assert_eq!(registered.k, vk.k);
+    assert_eq!(registered.commitment_keys, vk.commitment_keys);
+
+    // A version can only be registered once
+    assert!(env.register_verifying_key(&authority, 3, &vk).is_err());
+
+    // Writing past the size declared when registering is rejected
+    assert_custom_error(
+        env.write_verifying_key(&authority, 3, vec![vk.k[0]], vec![]),
+        CondenserError::VerifyingKeyOverflow.into(),
+    );
+}
+
+#[test]
+fn test_register_disclosure_key() {
+    let fixture = Fixture::load();
+    let condenser_vk = fixture.verifying_key();
+    let mut env = TestEnv::new(10_000);
+    let authority = env.payer.insecure_clone();
+
+    // A disclosure key has a point for each of its 34 public inputs
+    let mut vk = condenser_vk.clone();
+    vk.k = vec![condenser_vk.k[0]; 35 + vk.commitment_keys.len()];
+    env.register_disclosure_key(&authority, VK_VERSION, &vk)
+        .unwrap();
+    let registered = env.registered_key(Circuit::Disclosure, VK_VERSION);
+    assert_eq!(registered.circuit, Circuit::Disclosure);
+    assert!(registered.is_complete());
+
+    // It lives apart from the condenser key of the same version, and is only complete with all 35 points
+    env.register_verifying_key(&authority, VK_VERSION, &condenser_vk)
+        .unwrap();
+    assert_eq!(env.verifying_key(VK_VERSION).circuit, Circuit::Condenser);
+    assert!(env
+        .register_disclosure_key(&authority, 2, &condenser_vk)
+        .is_ok());
+    assert!(!env.registered_key(Circuit::Disclosure, 2).is_complete());
+}
+
+#[test]
+fn test_initialize_requires_transfer_hook_authority() {
+    // Nobody but the mint's transfer hook authority can claim the transfer tree of a new mint
+    let mut env = TestEnv::without_tree(None);
+    let other = Keypair::new();
+    env.svm.airdrop(&other.pubkey(), 1_000_000_000).unwrap();
+    assert_custom_error(env.initialize_tree(&other), HookError::Unauthorized.into());
+
+    let authority = env.payer.insecure_clone();
+    env.initialize_tree(&authority).unwrap();
+    assert_eq!(
+        env.tree().authority.to_bytes(),
+        authority.pubkey().to_bytes()
+    );
+}
+
+#[test]
+fn test_register_verifying_key_requires_transfer_hook_authority() {
+    let fixture = Fixture::load();
+    let vk = fixture.verifying_key();
+    let mut env = TestEnv::new(10_000);
+    let authority = env.payer.insecure_clone();
+    env.register_verifying_key(&authority, VK_VERSION, &vk)
+        .unwrap();
+
+    let other = Keypair::new();
+    env.svm.airdrop(&other.pubkey(), 1_000_000_000).unwrap();
+    assert_custom_error(
+        env.register_verifying_key(&other, 2, &vk),
+        CondenserError::Unauthorized.into(),
+    );
+    assert_custom_error(
+        env.write_verifying_key(&other, VK_VERSION, vec![], vec![]),
+        CondenserError::Unauthorized.into(),
+    );
+    assert_custom_error(
+        env.retire_verifying_key(&other, VK_VERSION),
+        CondenserError::Unauthorized.into(),
+    );
+    assert!(!env.verifying_key(VK_VERSION).retired);
+}
+
+#[test]
+fn test_condense_retired_verifying_key() {
+    let fixture = Fixture::load();
+    let (proof, pub_witness) = fixture.proof();
+    let recipient = recipient_from_witness(&pub_witness);
+
+    let mut env = TestEnv::new(fixture.deposit_amount);
+    deposit_fixture(&mut env, &fixture);
+
+    let authority = env.payer.insecure_clone();
+    env.retire_verifying_key(&authority, VK_VERSION).unwrap();
+    assert_custom_error(
+        env.condense(recipient, proof, pub_witness),
+        CondenserError::VerifyingKeyRetired.into(),
+    );
+    assert_eq!(env.total_withdrawn(&recipient), 0);
+}
+
+#[test]
+fn test_condense_incomplete_verifying_key() {
+    let fixture = Fixture::load();
+    let (proof, pub_witness) = fixture.proof();
+    let recipient = recipient_from_witness(&pub_witness);
+
+    // The commitment keys are never written
+    let mut vk = fixture.verifying_key();
+    vk.commitment_keys.clear();
+    let mut env = TestEnv::new(fixture.deposit_amount);
+    let authority = env.payer.insecure_clone();
+    env.register_verifying_key(&authority, VK_VERSION, &vk)
+        .unwrap();
+    env.seed_leaves(&fixture.preceding_leaves);
+    env.transfer(&fixture.vapor_addr, fixture.deposit_amount)
+        .unwrap();
+
+    assert_custom_error(
+        env.condense(recipient, proof, pub_witness),
+        CondenserError::VerifyingKeyIncomplete.into(),
+    );
+}
+
+#[test]
+fn test_hook_rejects_direct_invocation() {
+    let mut env = TestEnv::new(10_000);
+    let vapor_addr = Pubkey::new_unique();
+    env.transfer(&vapor_addr, 100).unwrap();
+
+    let payer_key = env.payer.pubkey();
+    let execute = Instruction {
+        program_id: vaportoken_transfer_hook::ID,
+        accounts: vaportoken_transfer_hook::accounts::TransferHook {
+            source_token: env.payer_ata,
+            mint: env.mint,
+            destination_token: env.ata(&vapor_addr),
+            owner: payer_key,
+            extra_account_meta_list: env.extra_account_meta_list(),
+            tree_account: env.tree_account(),
+        }
+        .to_account_metas(None),
+        data: vaportoken_transfer_hook::instruction::TransferHook { amount: 100 }.data(),
+    };
+
+    assert_custom_error(
+        env.send(&[execute], &[]),
+        HookError::IsNotCurrentlyTransferring.into(),
+    );
+    assert_eq!(env.tree().next_index, 1);
+}
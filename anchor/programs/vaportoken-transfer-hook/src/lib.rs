@@ -7,17 +7,18 @@ use anchor_spl::{
     token_2022::{
         spl_token_2022::{
             extension::{
-                transfer_hook::TransferHookAccount, BaseStateWithExtensionsMut,
+                transfer_fee::TransferFeeConfig,
+                transfer_hook::{TransferHook as TransferHookExtension, TransferHookAccount},
+                BaseStateWithExtensions, BaseStateWithExtensionsMut, PodStateWithExtensions,
                 PodStateWithExtensionsMut,
             },
-            pod::PodAccount,
+            pod::{PodAccount, PodMint},
         },
         Token2022,
     },
     token_interface::{Mint, TokenAccount},
 };
-use light_hasher::{Hasher, Poseidon};
-pub use merkle_tree::{MerkleTree, MerkleTreeAccount};
+pub use merkle_tree::{AdmissionPolicy, MerkleTree, MerkleTreeAccount};
 use spl_discriminator::SplDiscriminate;
 use spl_tlv_account_resolution::{
     account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList,
@@ -25,7 +26,7 @@ use spl_tlv_account_resolution::{
 use spl_transfer_hook_interface::instruction::{
     ExecuteInstruction, InitializeExtraAccountMetaListInstruction,
 };
-use utils::pack_bytes;
+use transfer_tree::{transfer_leaf, Poseidon};
 
 mod merkle_tree;
 
@@ -33,8 +34,6 @@ declare_id!("4pY5QvuVwh2Ktd6LAiAGhuhFvVFqx6GCioh6iThmLT8y");
 
 #[program]
 pub mod transfer_hook {
-    use utils::{fr_to_be_32, u64_to_be_32};
-
     use super::*;
 
     pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
@@ -43,7 +42,6 @@ pub mod transfer_hook {
         tree_account.next_index = 0;
         tree_account.root_index = 0;
         tree_account.bump = ctx.bumps.tree_account;
-        tree_account.max_deposit_amount = 1_000_000_000_000; // 1000 SOL default limit
         tree_account.height = MERKLE_TREE_HEIGHT; // Hardcoded height
         tree_account.root_history_size = ROOT_HISTORY as u8; // Hardcoded root history size
 
@@ -71,27 +69,43 @@ pub mod transfer_hook {
         // Fail this instruction if it is not called from within a transfer hook
         check_is_transferring(&ctx)?;
 
-        let destination = pack_bytes(&ctx.accounts.destination_token.owner.as_ref())
-            .iter()
-            .map(fr_to_be_32)
-            .collect::<Vec<[u8; 32]>>();
-
-        let amount_bytes = u64_to_be_32(amount);
-        let leaf = Poseidon::hashv(&[&destination[0], &destination[1], &amount_bytes]).unwrap();
+        // Record what the destination actually received so a condense can't mint back the fee
+        let amount = net_amount(
+            &ctx.accounts.mint.to_account_info().try_borrow_data()?,
+            Clock::get()?.epoch,
+            amount,
+        )?;
+        let to = ctx.accounts.destination_token.owner;
 
-        // Insert the leaf into the merkle tree for the transfer
+        // Insert the leaf into the merkle tree for the transfer,
+        // unless the admission policy keeps it from using up a slot
         let tree_account = &mut ctx.accounts.tree_account.load_mut()?;
-        MerkleTree::append::<Poseidon>(leaf, tree_account)?;
+        let appended = tree_account.admits(&to, amount);
+        if appended {
+            let leaf = transfer_leaf(&to.to_bytes(), amount);
+            MerkleTree::append::<Poseidon>(leaf, tree_account)?;
+        }
 
         // Emit a transfer log which will be used by the wallet to
         // reconstruct the merkle tree corresponding to the accumulator
         emit!(Transfer {
-            to: ctx.accounts.destination_token.owner.key(),
+            to,
             amount,
+            appended,
         });
 
         Ok(())
     }
+
+    /// Set which transfers are appended to the tree. Only the mint's transfer hook authority can change the policy
+    pub fn set_admission_policy(
+        ctx: Context<SetAdmissionPolicy>,
+        policy: AdmissionPolicy,
+    ) -> Result<()> {
+        let tree_account = &mut ctx.accounts.tree_account.load_mut()?;
+        tree_account.set_admission_policy(&policy);
+        Ok(())
+    }
 }
 
 fn check_is_transferring(ctx: &Context<TransferHook>) -> Result<()> {
@@ -107,6 +121,30 @@ fn check_is_transferring(ctx: &Context<TransferHook>) -> Result<()> {
     Ok(())
 }
 
+/// Authority of the mint's transfer hook extension, set by the creator of the mint in the transaction that creates it.
+/// It administers the mint's transfer tree and condenser verifying keys, so they can't be claimed by whoever gets there first
+pub fn transfer_hook_authority(mint: &AccountInfo) -> Result<Option<Pubkey>> {
+    let mint_data = mint.try_borrow_data()?;
+    let mint = PodStateWithExtensions::<PodMint>::unpack(&mint_data)?;
+    let extension = mint.get_extension::<TransferHookExtension>()?;
+    Ok(extension.authority.into())
+}
+
+/// Amount of a transfer of `amount` in `epoch` that reaches the destination, given the data of the mint.
+/// On mints with the transfer fee extension Token-2022 withholds the fee for the epoch in the destination account
+pub fn net_amount(mint_data: &[u8], epoch: u64, amount: u64) -> Result<u64> {
+    let mint = PodStateWithExtensions::<PodMint>::unpack(mint_data)?;
+    let fee = match mint.get_extension::<TransferFeeConfig>() {
+        Ok(transfer_fee_config) => transfer_fee_config
+            .calculate_epoch_fee(epoch, amount)
+            .ok_or(ErrorCode::ArithmeticOverflow)?,
+        Err(_) => 0,
+    };
+    Ok(amount
+        .checked_sub(fee)
+        .ok_or(ErrorCode::ArithmeticOverflow)?)
+}
+
 #[derive(Accounts)]
 pub struct Initialize<'info> {
     pub mint: InterfaceAccount<'info, Mint>,
@@ -120,12 +158,34 @@ pub struct Initialize<'info> {
     )]
     pub tree_account: AccountLoader<'info, MerkleTreeAccount>,
 
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = transfer_hook_authority(&mint.to_account_info())? == Some(authority.key())
+            @ ErrorCode::Unauthorized
+    )]
     pub authority: Signer<'info>,
 
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct SetAdmissionPolicy<'info> {
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"merkle_tree", mint.key().as_ref()],
+        bump = tree_account.load()?.bump
+    )]
+    pub tree_account: AccountLoader<'info, MerkleTreeAccount>,
+
+    #[account(
+        constraint = transfer_hook_authority(&mint.to_account_info())? == Some(authority.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct InitializeExtraAccountMetaList<'info> {
     #[account(mut)]
@@ -192,7 +252,10 @@ pub struct TransferHook<'info> {
 #[event]
 pub struct Transfer {
     to: Pubkey,
+    /// Amount received by the destination, net of any transfer fee
     amount: u64,
+    /// Whether a leaf was appended to the tree, transfers skipped by the admission policy are not
+    appended: bool,
 }
 
 #[error_code]
@@ -203,4 +266,6 @@ pub enum ErrorCode {
     MerkleTreeFull,
     #[msg("The token is not currently transferring")]
     IsNotCurrentlyTransferring,
+    #[msg("Signer is not the transfer hook authority of the mint")]
+    Unauthorized,
 }
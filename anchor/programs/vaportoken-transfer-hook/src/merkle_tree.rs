@@ -1,7 +1,9 @@
 // Adapted from https://github.com/Privacy-Cash/privacy-cash/blob/main/anchor/programs/zkcash/src/merkle_tree.rs
+// The frontier itself is maintained by the `transfer-tree` crate, which clients use to rebuild the same tree.
 use crate::ErrorCode;
 use anchor_lang::prelude::*;
-use light_hasher::Hasher;
+use solana_curve25519::edwards::{validate_edwards, PodEdwardsPoint};
+use transfer_tree::Hasher;
 
 pub const MERKLE_TREE_HEIGHT: u8 = 26;
 pub const ROOT_HISTORY: usize = 100;
@@ -12,60 +14,25 @@ impl MerkleTree {
     pub fn initialize<H: Hasher>(tree_account: &mut MerkleTreeAccount) -> Result<()> {
         let height = tree_account.height as usize;
 
-        // Initialize empty subtrees
-        let zero_bytes = H::zero_bytes();
-        for i in 0..height {
-            tree_account.subtrees[i] = zero_bytes[i];
-        }
-
-        // Set initial root
-        let initial_root = H::zero_bytes()[height];
+        let initial_root = transfer_tree::init::<H>(&mut tree_account.subtrees[..height]);
         tree_account.root = initial_root;
         tree_account.root_history[0] = initial_root;
 
         Ok(())
     }
 
-    pub fn append<H: Hasher>(
-        leaf: [u8; 32],
-        tree_account: &mut MerkleTreeAccount,
-    ) -> Result<Vec<[u8; 32]>> {
+    pub fn append<H: Hasher>(leaf: [u8; 32], tree_account: &mut MerkleTreeAccount) -> Result<()> {
         let height = tree_account.height as usize;
         let root_history_size = tree_account.root_history_size as usize;
 
-        // Check if tree is full before appending
-        // Maximum capacity is 2^height leaves
-        let max_capacity = 1u64 << height; // 2^height
-        require!(
-            tree_account.next_index < max_capacity,
-            ErrorCode::MerkleTreeFull
-        );
-
-        let mut current_index = tree_account.next_index as usize;
-        let mut current_level_hash = leaf;
-        let mut left;
-        let mut right;
-        let mut proof: Vec<[u8; 32]> = vec![[0u8; 32]; height];
-
-        for i in 0..height {
-            let subtree = &mut tree_account.subtrees[i];
-            let zero_byte = H::zero_bytes()[i];
-
-            if current_index % 2 == 0 {
-                left = current_level_hash;
-                right = zero_byte;
-                *subtree = current_level_hash;
-                proof[i] = right;
-            } else {
-                left = *subtree;
-                right = current_level_hash;
-                proof[i] = left;
-            }
-            current_level_hash = H::hashv(&[&left, &right]).unwrap();
-            current_index /= 2;
-        }
+        let root = transfer_tree::append::<H>(
+            &mut tree_account.subtrees[..height],
+            tree_account.next_index,
+            leaf,
+        )
+        .map_err(|_| ErrorCode::MerkleTreeFull)?;
 
-        tree_account.root = current_level_hash;
+        tree_account.root = root;
         tree_account.next_index = tree_account
             .next_index
             .checked_add(1)
@@ -76,9 +43,9 @@ impl MerkleTree {
             .ok_or(ErrorCode::ArithmeticOverflow)?
             % root_history_size;
         tree_account.root_index = new_root_index as u64;
-        tree_account.root_history[new_root_index] = current_level_hash;
+        tree_account.root_history[new_root_index] = root;
 
-        Ok(proof)
+        Ok(())
     }
 
     pub fn is_known_root(tree_account: &MerkleTreeAccount, root: [u8; 32]) -> bool {
@@ -110,6 +77,16 @@ impl MerkleTree {
     }
 }
 
+/// Which transfers get a leaf in the tree. Transfers that are skipped still succeed but can never be condensed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AdmissionPolicy {
+    /// Skip transfers to owners that are not ed25519 points, such as PDAs. Vapor addresses are always on the curve
+    pub skip_off_curve_owners: bool,
+    pub skip_zero_amounts: bool,
+    /// Skip transfers of less than this amount, 0 for no minimum
+    pub min_amount: u64,
+}
+
 #[account(zero_copy)]
 pub struct MerkleTreeAccount {
     pub authority: Pubkey,
@@ -118,10 +95,170 @@ pub struct MerkleTreeAccount {
     pub root: [u8; 32],
     pub root_history: [[u8; 32]; ROOT_HISTORY],
     pub root_index: u64,
-    pub max_deposit_amount: u64,
+    /// Formerly the unused `max_deposit_amount`, which trees created before the admission policy hold
+    /// a non-zero value in. Only enforced while `enforce_min_amount` is set
+    pub min_deposit_amount: u64,
     pub height: u8,
     pub root_history_size: u8,
     pub bump: u8,
-    // The pub _padding: [u8; 5] is needed because of the #[account(zero_copy)] attribute.
-    pub _padding: [u8; 5],
+    // Admission flags are stored as 0 or 1 as bool is not Pod. They take what was padding so
+    // existing tree accounts keep their size and offsets, with every flag unset
+    pub skip_off_curve_owners: u8,
+    pub skip_zero_amounts: u8,
+    pub enforce_min_amount: u8,
+    // The pub _padding: [u8; 2] is needed because of the #[account(zero_copy)] attribute.
+    pub _padding: [u8; 2],
+}
+
+impl MerkleTreeAccount {
+    pub fn admission_policy(&self) -> AdmissionPolicy {
+        AdmissionPolicy {
+            skip_off_curve_owners: self.skip_off_curve_owners != 0,
+            skip_zero_amounts: self.skip_zero_amounts != 0,
+            min_amount: self.min_amount(),
+        }
+    }
+
+    pub fn set_admission_policy(&mut self, policy: &AdmissionPolicy) {
+        self.skip_off_curve_owners = policy.skip_off_curve_owners as u8;
+        self.skip_zero_amounts = policy.skip_zero_amounts as u8;
+        self.min_deposit_amount = policy.min_amount;
+        self.enforce_min_amount = (policy.min_amount != 0) as u8;
+    }
+
+    fn min_amount(&self) -> u64 {
+        if self.enforce_min_amount != 0 {
+            self.min_deposit_amount
+        } else {
+            0
+        }
+    }
+
+    /// Whether a transfer of `amount` to a token account owned by `owner` is appended to the tree
+    pub fn admits(&self, owner: &Pubkey, amount: u64) -> bool {
+        if self.skip_zero_amounts != 0 && amount == 0 {
+            return false;
+        }
+        if amount < self.min_amount() {
+            return false;
+        }
+        // Checked last as it is the only expensive rule
+        if self.skip_off_curve_owners != 0 && !validate_edwards(&PodEdwardsPoint(owner.to_bytes()))
+        {
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use transfer_tree::{transfer_leaf, Poseidon, TransferTree};
+
+    fn new_tree_account(height: u8) -> Box<MerkleTreeAccount> {
+        let mut tree_account: Box<MerkleTreeAccount> = Box::new(bytemuck::Zeroable::zeroed());
+        tree_account.height = height;
+        tree_account.root_history_size = ROOT_HISTORY as u8;
+        MerkleTree::initialize::<Poseidon>(&mut tree_account).unwrap();
+        tree_account
+    }
+
+    #[test]
+    fn test_full_tree_is_rejected() {
+        let mut tree_account = new_tree_account(2);
+        for i in 0..4 {
+            MerkleTree::append::<Poseidon>(transfer_leaf(&[1; 32], i), &mut tree_account).unwrap();
+        }
+        assert!(
+            MerkleTree::append::<Poseidon>(transfer_leaf(&[1; 32], 4), &mut tree_account).is_err()
+        );
+        assert_eq!(tree_account.next_index, 4);
+    }
+
+    #[test]
+    fn test_admission_policy() {
+        let mut tree_account = new_tree_account(2);
+        // The ed25519 base point
+        let mut on_curve = [0x66; 32];
+        on_curve[0] = 0x58;
+        let on_curve = Pubkey::new_from_array(on_curve);
+        let off_curve = Pubkey::find_program_address(&[b"owner"], &crate::ID).0;
+
+        // Everything is admitted by default
+        assert!(tree_account.admits(&off_curve, 0));
+
+        tree_account.set_admission_policy(&AdmissionPolicy {
+            skip_off_curve_owners: true,
+            skip_zero_amounts: true,
+            min_amount: 0,
+        });
+        assert!(!tree_account.admits(&off_curve, 10));
+        assert!(!tree_account.admits(&on_curve, 0));
+        assert!(tree_account.admits(&on_curve, 1));
+
+        tree_account.set_admission_policy(&AdmissionPolicy {
+            min_amount: 100,
+            ..Default::default()
+        });
+        assert!(!tree_account.admits(&on_curve, 99));
+        assert!(tree_account.admits(&off_curve, 100));
+    }
+
+    #[test]
+    fn test_legacy_layout() {
+        // authority | next_index | subtrees | root | root_history | root_index | max_deposit_amount
+        // | height | root_history_size | bump | padding[5], as written before the admission policy
+        const LEGACY_SIZE: usize =
+            32 + 8 + 32 * MERKLE_TREE_HEIGHT as usize + 32 + 32 * ROOT_HISTORY + 8 + 8 + 3 + 5;
+        assert_eq!(std::mem::size_of::<MerkleTreeAccount>(), LEGACY_SIZE);
+
+        let tree_account = new_tree_account(MERKLE_TREE_HEIGHT);
+        let mut legacy = bytemuck::bytes_of(&*tree_account).to_vec();
+        let root_index_offset = LEGACY_SIZE - 8 - 8 - 8;
+        legacy[root_index_offset + 8..root_index_offset + 16]
+            .copy_from_slice(&1_000_000_000_000u64.to_le_bytes());
+        legacy[root_index_offset + 16] = MERKLE_TREE_HEIGHT;
+        legacy[root_index_offset + 17] = ROOT_HISTORY as u8;
+        legacy[root_index_offset + 18] = 255;
+        legacy[root_index_offset + 19..].fill(0);
+
+        let mut tree_account: Box<MerkleTreeAccount> =
+            Box::new(bytemuck::pod_read_unaligned(&legacy));
+        assert_eq!(tree_account.height, MERKLE_TREE_HEIGHT);
+        assert_eq!(tree_account.root_history_size, ROOT_HISTORY as u8);
+        assert_eq!(tree_account.bump, 255);
+        // The old deposit limit is not mistaken for a minimum
+        assert_eq!(tree_account.admission_policy(), AdmissionPolicy::default());
+        let off_curve = Pubkey::find_program_address(&[b"owner"], &crate::ID).0;
+        assert!(tree_account.admits(&off_curve, 0));
+
+        MerkleTree::append::<Poseidon>(transfer_leaf(&[1; 32], 5), &mut tree_account).unwrap();
+        assert_eq!(tree_account.next_index, 1);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        /// The on-chain tree and the client tree agree on every root for any sequence of transfers
+        #[test]
+        fn prop_account_matches_client_tree(
+            transfers in prop::collection::vec((any::<[u8; 32]>(), any::<u64>()), 0..16)
+        ) {
+            let mut tree_account = new_tree_account(MERKLE_TREE_HEIGHT);
+            let mut client_tree = TransferTree::<{ MERKLE_TREE_HEIGHT as usize }>::new();
+            prop_assert_eq!(tree_account.root, client_tree.root());
+
+            for (destination, amount) in transfers {
+                MerkleTree::append::<Poseidon>(transfer_leaf(&destination, amount), &mut tree_account)
+                    .unwrap();
+                client_tree.append_transfer(destination, amount).unwrap();
+
+                prop_assert_eq!(tree_account.root, client_tree.root());
+                prop_assert_eq!(tree_account.next_index, client_tree.next_index());
+                prop_assert!(MerkleTree::is_known_root(&tree_account, client_tree.root()));
+            }
+        }
+    }
 }
@@ -28,6 +28,50 @@ import {
 import { Condenser } from "../target/types/condenser";
 import { TransferHook } from "../target/types/transfer_hook";
 
+/**
+ * Parse gnark's uncompressed verifying key encoding into the parts the registry takes,
+ * skipping [β]1 and [δ]1 which the verifier does not use
+ */
+function parseVerifyingKey(bytes: Buffer) {
+  let offset = 0;
+  const take = (n: number) => {
+    const point = Array.from(bytes.subarray(offset, offset + n));
+    offset += n;
+    return point;
+  };
+  const len = () => {
+    const n = bytes.readUInt32BE(offset);
+    offset += 4;
+    return n;
+  };
+
+  const alphaG1 = take(64);
+  take(64);
+  const betaG2 = take(128);
+  const gammaG2 = take(128);
+  take(64);
+  const deltaG2 = take(128);
+  const k = Array.from({ length: len() }, () => take(64));
+  const nrCommitted = len();
+  for (let i = 0; i < nrCommitted; i++) {
+    assert.equal(len(), 0, "commitments to public inputs are not supported");
+  }
+  const commitmentKeys = Array.from({ length: len() }, () => take(256));
+  assert.equal(offset, bytes.length, "trailing bytes in verifying key");
+
+  return {
+    header: {
+      alphaG1,
+      betaG2,
+      gammaG2,
+      deltaG2,
+      nrCommitments: commitmentKeys.length,
+    },
+    k,
+    commitmentKeys,
+  };
+}
+
 describe("vapor-tokens", () => {
   const provider = anchor.AnchorProvider.env();
   anchor.setProvider(provider);
@@ -56,7 +100,7 @@ describe("vapor-tokens", () => {
       transferHookProgram.programId
     );
     const [treeAccount] = PublicKey.findProgramAddressSync(
-      [Buffer.from("merkle_tree")],
+      [Buffer.from("merkle_tree"), mint.publicKey.toBuffer()],
       transferHookProgram.programId
     );
 
@@ -138,6 +182,12 @@ describe("vapor-tokens", () => {
       [Buffer.from("withdrawn"), mint.publicKey.toBuffer(), recipientOwner.toBuffer()],
       condenserProgram.programId
     );
+    const vkVersion = Buffer.alloc(4);
+    vkVersion.writeUInt32LE(1);
+    const [verifyingKey] = PublicKey.findProgramAddressSync(
+      [Buffer.from("verifying_key"), mint.publicKey.toBuffer(), vkVersion],
+      condenserProgram.programId
+    );
 
     const createRecipientAtaTx = new Transaction().add(
       createAssociatedTokenAccountInstruction(
@@ -203,6 +253,43 @@ describe("vapor-tokens", () => {
     );
     await provider.sendAndConfirm(new Transaction().add(transferIx), []);
 
+    // The payer is the transfer hook authority of the mint so it manages the verifying keys
+    const vkPath = path.resolve(
+      __dirname,
+      "..",
+      "..",
+      "circuits",
+      "condenser",
+      "target",
+      "condenser.vk"
+    );
+    const vk = parseVerifyingKey(fs.readFileSync(vkPath));
+    await condenserProgram.methods
+      .registerVerifyingKey(1, vk.header)
+      .accountsStrict({
+        mint: mint.publicKey,
+        verifyingKey,
+        authority: payer,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    await condenserProgram.methods
+      .writeVerifyingKey(vk.k, [])
+      .accountsStrict({
+        mint: mint.publicKey,
+        verifyingKey,
+        authority: payer,
+      })
+      .rpc();
+    await condenserProgram.methods
+      .writeVerifyingKey([], vk.commitmentKeys)
+      .accountsStrict({
+        mint: mint.publicKey,
+        verifyingKey,
+        authority: payer,
+      })
+      .rpc();
+
     const treeState =
       await transferHookProgram.account.merkleTreeAccount.fetch(treeAccount);
     const rootBytes = Buffer.from(treeState.root as number[]);
@@ -241,6 +328,7 @@ describe("vapor-tokens", () => {
         mintAuthority,
         tokenProgram: TOKEN_2022_PROGRAM_ID,
         treeAccount,
+        verifyingKey,
         withdrawn: withdrawnAccount,
         payer,
         systemProgram: SystemProgram.programId,
@@ -0,0 +1,63 @@
+use serde::Serialize;
+use vapor_wallet::anonymity::AnonymitySet;
+use vapor_wallet::{Deposit, SyncReport, Wallet, parse_address};
+
+use crate::output::{Output, Synced};
+
+#[derive(Serialize)]
+struct DepositAnonymity {
+    #[serde(flatten)]
+    deposit: Deposit,
+    anonymity_set: AnonymitySet,
+    recommendations: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct AnonymitySetOutput {
+    deposits: Vec<DepositAnonymity>,
+}
+
+/// Print the anonymity set of each deposit to a vapor address
+pub(crate) fn anonymity_set(
+    wallet: &Wallet,
+    output: Output,
+    sync: SyncReport,
+    vapor_addr: &str,
+) -> anyhow::Result<()> {
+    let deposits = wallet
+        .deposits(parse_address("vapor_addr", vapor_addr)?)?
+        .into_iter()
+        .map(|deposit| {
+            let set = wallet.anonymity_set(deposit.leaf_index)?;
+            Ok(DepositAnonymity {
+                deposit,
+                recommendations: set.recommendations(),
+                anonymity_set: set,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let result = Synced {
+        sync,
+        result: AnonymitySetOutput { deposits },
+    };
+    output.result(&result, || {
+        if result.result.deposits.is_empty() {
+            println!("No deposits to this address have been synced");
+        }
+        for deposit in &result.result.deposits {
+            println!(
+                "Deposit of {} (leaf {})",
+                deposit.anonymity_set.amount, deposit.deposit.leaf_index
+            );
+            println!("  {}", deposit.anonymity_set);
+            if deposit.recommendations.is_empty() {
+                println!("  Looks safe to condense");
+            }
+            for line in &deposit.recommendations {
+                println!("  - {}", line);
+            }
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,55 @@
+use std::str::FromStr;
+
+use serde::Serialize;
+use solana_sdk::signature::Signature;
+use vapor_wallet::disclosure::DisclosedDeposit;
+use vapor_wallet::{SyncReport, Wallet, parse_address};
+
+use crate::output::{Output, Synced};
+
+#[derive(Serialize)]
+struct DiscloseOutput<'a> {
+    out: &'a str,
+    deposits: &'a [DisclosedDeposit],
+    condenses: &'a [String],
+}
+
+/// Create a disclosure artifact revealing deposits to a vapor address and proving that it commits to its recipient,
+/// bound to the condense transactions the deposits account for.
+/// If no slots are given all deposits to the address are disclosed
+pub(crate) fn disclose(
+    wallet: &Wallet,
+    output: Output,
+    sync: SyncReport,
+    vapor_addr: &str,
+    slots: &[u64],
+    condenses: &[String],
+    out: &str,
+) -> anyhow::Result<()> {
+    let condenses = condenses
+        .iter()
+        .map(|s| Signature::from_str(s))
+        .collect::<Result<Vec<_>, _>>()?;
+    output.status("Generating disclosure proof...");
+    let disclosure =
+        wallet.disclose(parse_address("vapor_addr", vapor_addr)?, slots, &condenses)?;
+    std::fs::write(out, serde_json::to_string_pretty(&disclosure)?)?;
+
+    let result = Synced {
+        sync,
+        result: DiscloseOutput {
+            out,
+            deposits: &disclosure.deposits,
+            condenses: &disclosure.condenses,
+        },
+    };
+    output.result(&result, || {
+        println!(
+            "Disclosure of {} deposit(s) and {} condense(s) written to {}",
+            disclosure.deposits.len(),
+            disclosure.condenses.len(),
+            out
+        );
+        Ok(())
+    })
+}
@@ -1,35 +1,59 @@
-use vaporize_addresses::generate_vaporize_address;
-
-use crate::{VAP_ADDR, VaporAddressRecord};
-
-pub(crate) fn gen_vapor_address(db: &redb::Database, recipient: &str) -> anyhow::Result<()> {
-    let recipient: [u8; 32] = bs58::decode(recipient)
-        .into_vec()?
-        .try_into()
-        .expect("recipient must be 32 bytes");
-
-    let mut rng = rand::thread_rng();
-    let (addr, secret) = generate_vaporize_address(&mut rng, recipient);
-    let address = bs58::encode(addr).into_string();
-
-    println!("Generated vaporize address: {}", address);
-    qr2term::print_qr(address)?;
-    println!("");
-    println!("Spend secret: {}", secret.to_string());
-
-    let record = VaporAddressRecord {
-        addr,
-        recipient,
-        secret: secret.to_string(),
+use std::io::Write;
+
+use vapor_wallet::{Wallet, parse_address};
+use vaporize_addresses::prefix_difficulty;
+
+use crate::output::{AddressOutput, Output};
+
+pub(crate) fn gen_vapor_address(
+    wallet: &Wallet,
+    output: Output,
+    recipient: &str,
+    prefix: Option<&str>,
+    threads: usize,
+    include_secret: bool,
+) -> anyhow::Result<()> {
+    let recipient = parse_address("recipient", recipient)?;
+
+    let record = match prefix {
+        Some(prefix) => {
+            let difficulty = prefix_difficulty(prefix)?;
+            if difficulty.is_infinite() {
+                anyhow::bail!("no 32 byte address starts with {:?}", prefix);
+            }
+            output.status(format!(
+                "Searching for an address starting with {:?} on {} threads, expecting ~{:.0} attempts",
+                prefix, threads, difficulty
+            ));
+            let record = wallet.new_vanity_address(recipient, prefix, threads, |progress| {
+                if output.is_json() {
+                    return;
+                }
+                let rate = progress.rate();
+                let remaining = (difficulty - progress.attempts as f64).max(0.0) / rate;
+                print!(
+                    "\r{} attempts ({:.0}/s), ~{:.0}s remaining on average   ",
+                    progress.attempts, rate, remaining
+                );
+                std::io::stdout().flush().ok();
+            })?;
+            if !output.is_json() {
+                println!();
+            }
+            record
+        }
+        None => wallet.new_address(recipient)?,
     };
 
-    let write_txn = db.begin_write()?;
-    {
-        let mut table = write_txn.open_table(VAP_ADDR)?;
-
-        table.insert(&record.addr, &record)?;
-    }
-    write_txn.commit()?;
-
-    Ok(())
+    output.result(&AddressOutput::new(&record, include_secret), || {
+        let address = bs58::encode(record.addr).into_string();
+        println!("Generated vaporize address: {}", address);
+        qr2term::print_qr(address)?;
+        println!("");
+        println!(
+            "Spend secret: {}",
+            record.secret.to_decimal_string().as_str()
+        );
+        Ok(())
+    })
 }
@@ -1,35 +1,42 @@
-use redb::{ReadableDatabase, ReadableTable};
+use serde::Serialize;
+use vapor_wallet::{SyncReport, Wallet};
 
-use crate::{TRANSFERS, VAP_ADDR};
+use crate::output::{AddressOutput, Output, Synced};
 
-pub(crate) fn list(db: &redb::Database) -> anyhow::Result<()> {
-    let read_txn = db.begin_read()?;
-    {
-        let addresses = read_txn.open_table(VAP_ADDR)?;
-        let transfers = read_txn.open_table(TRANSFERS)?;
+#[derive(Serialize)]
+struct ListOutput {
+    addresses: Vec<AddressOutput>,
+}
 
-        for result in addresses.iter()? {
-            let (key, record) = result?;
-            let address = bs58::encode(key.value()).into_string();
-            let recipient = bs58::encode(record.value().recipient).into_string();
-            let secret = record.value().secret;
+pub(crate) fn list(
+    wallet: &Wallet,
+    output: Output,
+    sync: SyncReport,
+    include_secrets: bool,
+) -> anyhow::Result<()> {
+    let records = wallet.addresses()?;
+    let mut addresses = Vec::new();
+    for record in &records {
+        let mut address = AddressOutput::new(record, include_secrets);
+        address.deposits = Some(wallet.deposits(record.addr)?);
+        addresses.push(address);
+    }
 
-            println!("Vaporize Address: {}", address);
-            println!("  Recipient: {}", recipient);
-            println!("  Secret: {}", secret);
+    let result = Synced {
+        sync,
+        result: ListOutput { addresses },
+    };
+    output.result(&result, || {
+        for (record, address) in records.iter().zip(&result.result.addresses) {
+            println!("Vaporize Address: {}", address.vapor_addr);
+            println!("  Recipient: {}", address.recipient);
+            println!("  Secret: {}", record.secret.to_decimal_string().as_str());
             println!("  Deposits:");
-            for result in transfers.iter()? {
-                let (slot_key, transfer) = result?;
-                if transfer.value().to == key.value() {
-                    println!(
-                        "    Received {} in slot {}",
-                        transfer.value().amount,
-                        slot_key.value()
-                    );
-                }
+            for deposit in address.deposits.iter().flatten() {
+                println!("    Received {} in slot {}", deposit.amount, deposit.slot);
             }
             println!();
         }
-    }
-    Ok(())
+        Ok(())
+    })
 }
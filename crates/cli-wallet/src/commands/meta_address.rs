@@ -0,0 +1,30 @@
+use serde::Serialize;
+use vapor_wallet::{Wallet, parse_address};
+
+use crate::output::Output;
+
+#[derive(Serialize)]
+struct MetaAddressOutput {
+    recipient: String,
+    meta_address: String,
+}
+
+pub(crate) fn meta_address(wallet: &Wallet, output: Output, recipient: &str) -> anyhow::Result<()> {
+    let meta = wallet
+        .meta_address(parse_address("recipient", recipient)?)?
+        .to_string();
+
+    let result = MetaAddressOutput {
+        recipient: recipient.to_string(),
+        meta_address: meta.clone(),
+    };
+    output.result(&result, || {
+        println!("Stealth meta-address: {}", meta);
+        qr2term::print_qr(&meta)?;
+        println!();
+        println!(
+            "Payments to this meta-address are found when the wallet syncs. Keep this wallet file safe, it holds the scan key"
+        );
+        Ok(())
+    })
+}
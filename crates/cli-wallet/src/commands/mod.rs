@@ -1,7 +1,21 @@
+mod anonymity_set;
 mod condense;
+mod disclose;
 mod gen_address;
 mod list;
+mod meta_address;
+mod schedule;
+mod send;
+mod verify_disclosure;
+mod watch;
 
-pub(crate) use condense::condense;
+pub(crate) use anonymity_set::anonymity_set;
+pub(crate) use condense::{Selection, condense};
+pub(crate) use disclose::disclose;
 pub(crate) use gen_address::gen_vapor_address;
 pub(crate) use list::list;
+pub(crate) use meta_address::meta_address;
+pub(crate) use schedule::{Window, cancel_job, jobs, schedule_condense};
+pub(crate) use send::send;
+pub(crate) use verify_disclosure::verify_disclosure;
+pub(crate) use watch::{WatchOptions, watch};
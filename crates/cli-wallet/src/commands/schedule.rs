@@ -0,0 +1,95 @@
+use serde::Serialize;
+use vapor_wallet::{SyncReport, Wallet, parse_address};
+
+use super::condense::{Selection, select_deposit};
+use crate::output::{JobOutput, Output, Synced};
+
+/// When a scheduled condense may run
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Window {
+    /// Unix time of the start of the window
+    pub start: u64,
+    /// Length of the window in seconds
+    pub length: u64,
+}
+
+#[derive(Serialize)]
+struct ScheduledOutput {
+    id: u64,
+    vapor_addr: String,
+    leaf_index: u64,
+    amount: Option<u64>,
+    /// The job runs at a random time in this range, which is not shown
+    earliest: u64,
+    latest: u64,
+}
+
+#[derive(Serialize)]
+struct CancelledOutput {
+    cancelled: u64,
+}
+
+/// Queue a condense of one of the deposits to a vapor address to be run by `watch` at a random time in `window`
+pub(crate) fn schedule_condense(
+    wallet: &Wallet,
+    output: Output,
+    sync: SyncReport,
+    vapor_addr: &str,
+    selection: Selection,
+    amount: Option<u64>,
+    window: Window,
+) -> anyhow::Result<()> {
+    let vapor_addr = parse_address("vapor_addr", vapor_addr)?;
+
+    let Some(leaf_index) = select_deposit(wallet, output, vapor_addr, selection)? else {
+        return Ok(());
+    };
+
+    let end = window.start.saturating_add(window.length);
+    let id = wallet.schedule_condense(vapor_addr, leaf_index, amount, window.start, end)?;
+
+    let result = Synced {
+        sync,
+        result: ScheduledOutput {
+            id,
+            vapor_addr: bs58::encode(vapor_addr).into_string(),
+            leaf_index,
+            amount,
+            earliest: window.start,
+            latest: end,
+        },
+    };
+    output.result(&result, || {
+        println!(
+            "Scheduled job {}. Keep `watch` running so it is condensed at a random time between {} and {}",
+            id, window.start, end
+        );
+        Ok(())
+    })
+}
+
+/// List scheduled condenses and their results
+pub(crate) fn jobs(wallet: &Wallet, output: Output) -> anyhow::Result<()> {
+    let jobs = wallet.jobs()?;
+    let result: Vec<JobOutput> = jobs
+        .iter()
+        .map(|(id, job)| JobOutput::new(*id, job))
+        .collect();
+    output.result(&result, || {
+        if jobs.is_empty() {
+            println!("No condenses are scheduled");
+        }
+        for (id, job) in &jobs {
+            println!("[{}] {}", id, job);
+        }
+        Ok(())
+    })
+}
+
+pub(crate) fn cancel_job(wallet: &Wallet, output: Output, id: u64) -> anyhow::Result<()> {
+    wallet.cancel_job(id)?;
+    output.result(&CancelledOutput { cancelled: id }, || {
+        println!("Cancelled job {}", id);
+        Ok(())
+    })
+}
@@ -0,0 +1,38 @@
+use serde::Serialize;
+use solana_sdk::signature::Keypair;
+use vapor_wallet::{Destination, Wallet};
+
+use crate::output::Output;
+
+#[derive(Serialize)]
+struct SendOutput {
+    to: String,
+    amount: u64,
+    signature: String,
+}
+
+/// Transfer tokens from the keypair's token account to a destination wallet.
+/// A stealth meta-address is paid at a fresh vapor address derived for the recipient to find
+pub(crate) fn send(
+    wallet: &Wallet,
+    output: Output,
+    payer: Keypair,
+    destination: Destination,
+    amount: u64,
+) -> anyhow::Result<()> {
+    let transfer = wallet.send(&payer, &destination, amount)?;
+
+    let result = SendOutput {
+        to: transfer.to.to_string(),
+        amount: transfer.amount,
+        signature: transfer.signature.to_string(),
+    };
+    output.result(&result, || {
+        println!("Sent {} to {}", result.amount, result.to);
+        println!(
+            "Transaction accepted https://solscan.io/tx/{}?cluster=devnet",
+            result.signature
+        );
+        Ok(())
+    })
+}
@@ -0,0 +1,57 @@
+use serde::Serialize;
+use vapor_wallet::disclosure::{DisclosedDeposit, Disclosure};
+use vapor_wallet::{SyncReport, Wallet};
+
+use crate::output::{Output, Synced};
+
+#[derive(Serialize)]
+struct VerifiedOutput<'a> {
+    verified: bool,
+    recipient: &'a str,
+    vapor_addr: &'a str,
+    deposits: &'a [DisclosedDeposit],
+    condenses: &'a [String],
+    vk_version: u32,
+}
+
+/// Check a disclosure artifact against the synced transfers and its condense transactions, and verify its proof.
+/// Fails if any part of the disclosure does not hold
+pub(crate) fn verify_disclosure(
+    wallet: &Wallet,
+    output: Output,
+    sync: SyncReport,
+    path: &str,
+) -> anyhow::Result<()> {
+    let disclosure: Disclosure = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    wallet.verify_disclosure(&disclosure)?;
+
+    let result = Synced {
+        sync,
+        result: VerifiedOutput {
+            verified: true,
+            recipient: &disclosure.recipient,
+            vapor_addr: &disclosure.vapor_addr,
+            deposits: &disclosure.deposits,
+            condenses: &disclosure.condenses,
+            vk_version: disclosure.vk_version,
+        },
+    };
+    output.result(&result, || {
+        println!("Disclosure verified");
+        println!("  Recipient: {}", disclosure.recipient);
+        println!("  Vaporize Address: {}", disclosure.vapor_addr);
+        println!("  Deposits:");
+        for deposit in &disclosure.deposits {
+            println!(
+                "    Received {} in slot {} (leaf {})",
+                deposit.amount, deposit.slot, deposit.leaf_index
+            );
+        }
+        println!("  Condenses:");
+        for signature in &disclosure.condenses {
+            println!("    {}", signature);
+        }
+        println!("  Disclosure key: version {}", disclosure.vk_version);
+        Ok(())
+    })
+}
@@ -0,0 +1,35 @@
+use std::thread::sleep;
+use std::time::Duration;
+
+use solana_sdk::signature::Keypair;
+use vapor_wallet::schedule::RetryPolicy;
+use vapor_wallet::{SubmitOptions, Wallet};
+
+use crate::output::{JobOutput, Output};
+
+pub(crate) struct WatchOptions {
+    pub poll_interval: Duration,
+    pub retry: RetryPolicy,
+    pub submit: SubmitOptions,
+}
+
+/// Run scheduled condenses as they come due, forever.
+/// At most one job runs per poll so jobs due at the same time are not submitted together.
+/// In JSON mode each job that runs is printed as one line
+pub(crate) fn watch(
+    wallet: &Wallet,
+    output: Output,
+    payer: Keypair,
+    options: &WatchOptions,
+) -> anyhow::Result<()> {
+    output.status("Watching for scheduled condenses");
+    loop {
+        if let Some((id, job)) = wallet.run_next_job(&payer, &options.retry, &options.submit)? {
+            output.result(&JobOutput::new(id, &job), || {
+                println!("[{}] {}", id, job);
+                Ok(())
+            })?;
+        }
+        sleep(options.poll_interval);
+    }
+}
@@ -1,27 +1,17 @@
 use std::str::FromStr;
+use std::time::Duration;
 
-use crate::borsh_record::BorshRecord;
-use borsh::{BorshDeserialize, BorshSerialize};
 use clap::Parser;
-use redb::TableDefinition;
-use solana_sdk::{pubkey::Pubkey, signature::read_keypair_file};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, read_keypair_file};
+use vapor_wallet::schedule::{self, RetryPolicy};
+use vapor_wallet::{SubmitOptions, SyncReport, Wallet, WalletConfig};
 
-mod borsh_record;
-mod build_merkle_proof;
-mod commands;
-mod prove;
-mod sync;
-
-const TREE_HEIGHT: usize = 26;
-
-/// Table for crated vaporize address metadata indexed by vaporize address.
-const VAP_ADDR: TableDefinition<[u8; 32], BorshRecord<VaporAddressRecord>> =
-    TableDefinition::new("vapor-addresses");
+use crate::output::{ErrorOutput, Output, OutputFormat};
 
-/// Table for recorded spends index by slot.
-/// This is a little bit fraught -- it assumes only one spend per slot which may not hold in reality
-const TRANSFERS: TableDefinition<u64, BorshRecord<TransferEvent>> =
-    TableDefinition::new("transfers");
+mod commands;
+mod output;
+mod tui;
 
 #[derive(clap::Parser)]
 #[clap(version, about = "CLI wallet for Solana Vapor Tokens", long_about = None)]
@@ -40,6 +30,22 @@ struct Args {
     /// Path to the wallet database file
     #[clap(long, env = "WALLET_PATH", default_value = "wallet.redb")]
     wallet_file: String,
+
+    /// Sync transfers from a vapor-indexer at this URL instead of scanning the mint's history over RPC
+    #[clap(long, env = "VAPOR_INDEXER")]
+    indexer_url: Option<String>,
+
+    /// Version of the condenser verifying key to prove against, matching the circuit of the prover
+    #[clap(long, env = "VK_VERSION", default_value = "1")]
+    vk_version: u32,
+
+    /// Version of the disclosure verifying key to prove disclosures against, matching the circuit of the prover
+    #[clap(long, env = "DISCLOSURE_VK_VERSION", default_value = "1")]
+    disclosure_vk_version: u32,
+
+    /// Print results as text or as JSON for scripts. In JSON mode errors are printed as `{"error": ...}`
+    #[clap(long, global = true, value_enum, default_value = "text")]
+    output: OutputFormat,
 }
 
 #[derive(Clone, clap::Subcommand)]
@@ -53,9 +59,38 @@ enum Command {
     GenAddress {
         #[clap()]
         recipient: String,
+
+        /// Grind for an address whose base58 encoding starts with this prefix.
+        /// Each extra character makes this ~58 times slower
+        #[clap(long)]
+        prefix: Option<String>,
+
+        /// Number of threads to use when searching for a prefix. Defaults to the number of CPUs
+        #[clap(long)]
+        threads: Option<usize>,
+
+        /// Include the spend secret in JSON output. Text output always shows it
+        #[clap(long)]
+        include_secrets: bool,
+    },
+    /// Print a stealth meta-address for a recipient, generating its scan key if needed
+    /// Senders derive a fresh vaporize address from it for every payment. This wallet finds those payments when it syncs
+    MetaAddress {
+        #[clap()]
+        recipient: String,
     },
     /// List all vaporize addresses in this wallet along with any deposits made to them
-    List,
+    List {
+        /// Include spend secrets in JSON output. Text output always shows them
+        #[clap(long)]
+        include_secrets: bool,
+    },
+    /// Estimate how well each deposit to a vaporize address hides among the other transfers
+    /// A condense reveals the amount and recipient, so a rare amount or a condense soon after the deposit links the two
+    AnonymitySet {
+        #[clap()]
+        vapor_addr: String,
+    },
     /// Condense (mint) vaporized tokens to their destination account
     /// This requires the secret associated with the vaporize address to authorize the mint but this, along with which deposit is being condensed
     /// is hidden within the zero-knowledge proof before being submitted publicly
@@ -65,49 +100,370 @@ enum Command {
 
         #[clap()]
         vapor_addr: String,
+
+        /// Leaf index of the deposit to condense. Asked for if not given
+        #[clap(long)]
+        leaf_index: Option<u64>,
+
+        /// Do not ask for confirmation when the deposit has a small anonymity set
+        #[clap(long)]
+        yes: bool,
+
+        /// Amount to condense now, in base units. The rest of the deposit can be condensed later.
+        /// Defaults to everything not yet withdrawn to the recipient
+        #[clap(long)]
+        amount: Option<u64>,
+
+        /// Compute unit price in micro-lamports. Defaults to the median of recent fees for the accounts involved
+        #[clap(long)]
+        priority_fee: Option<u64>,
+
+        /// Number of times to resend with a fresh blockhash if the transaction expires before confirming
+        #[clap(long, default_value = "3")]
+        max_retries: usize,
+    },
+    /// Schedule a condense to run at a random time within a window, rather than straight after the deposit
+    /// Scheduled condenses are run by `watch`, which must be running when they come due
+    ScheduleCondense {
+        #[clap()]
+        vapor_addr: String,
+
+        /// Leaf index of the deposit to condense. Asked for if not given
+        #[clap(long)]
+        leaf_index: Option<u64>,
+
+        /// Do not ask for confirmation when the deposit has a small anonymity set
+        #[clap(long)]
+        yes: bool,
+
+        /// Amount to condense, in base units. Defaults to everything not yet withdrawn to the recipient when the job runs
+        #[clap(long)]
+        amount: Option<u64>,
+
+        /// Earliest time to condense as a delay from now, e.g. 30m, 12h or 2d
+        #[clap(long, default_value = "1h", conflicts_with = "at")]
+        after: String,
+
+        /// Earliest time to condense as a unix timestamp
+        #[clap(long)]
+        at: Option<u64>,
+
+        /// Length of the window after the earliest time that the condense is randomly placed in
+        #[clap(long, default_value = "1d")]
+        within: String,
+    },
+    /// List scheduled condenses and their results
+    Jobs,
+    /// Remove a scheduled condense that has not run yet
+    CancelJob {
+        #[clap()]
+        id: u64,
+    },
+    /// Run scheduled condenses as they come due until stopped
+    /// Each job syncs, proves and submits. A job that fails, for example because its root became stale, is retried later
+    Watch {
+        #[clap(long, default_value = "~/.config/solana/id.json")]
+        keypair: String,
+
+        /// Seconds between checks for due jobs
+        #[clap(long, default_value = "60")]
+        poll_interval: u64,
+
+        /// Number of attempts before giving up on a job
+        #[clap(long, default_value = "5")]
+        max_attempts: u32,
+
+        /// Delay before retrying a failed job, doubled after each further failure and randomized up to twice as long
+        #[clap(long, default_value = "10m")]
+        retry_delay: String,
+
+        /// Compute unit price in micro-lamports. Defaults to the median of recent fees for the accounts involved
+        #[clap(long)]
+        priority_fee: Option<u64>,
+
+        /// Number of times to resend with a fresh blockhash if the transaction expires before confirming
+        #[clap(long, default_value = "3")]
+        max_retries: usize,
+    },
+    /// Send tokens from the keypair's token account to a destination wallet address
+    /// The destination's token account is created if it does not exist
+    Send {
+        #[clap(long, default_value = "~/.config/solana/id.json")]
+        keypair: String,
+
+        /// Destination wallet address (not the associated token address)
+        /// or a stealth meta-address, in which case a fresh vaporize address is derived for it
+        #[clap()]
+        destination: String,
+
+        /// Amount to send in base units
+        #[clap()]
+        amount: u64,
+    },
+    /// Create a disclosure for an auditor showing that deposits to a vapor address were made to you
+    /// This reveals the chosen deposits and proves the vapor address commits to its recipient without revealing the secret
+    Disclose {
+        #[clap()]
+        vapor_addr: String,
+
+        /// Slots of the deposits to disclose. Defaults to all deposits made to the address
+        #[clap(long, multiple_values = true)]
+        slots: Vec<u64>,
+
+        /// Signatures of the condense transactions the deposits account for
+        #[clap(long, multiple_values = true, required = true)]
+        condenses: Vec<String>,
+
+        /// File to write the disclosure to
+        #[clap(long, default_value = "disclosure.json")]
+        out: String,
+    },
+    /// Verify a disclosure against the synced transfer tree
+    VerifyDisclosure {
+        #[clap()]
+        path: String,
+    },
+    /// Browse addresses and deposits, generate addresses and condense from an interactive terminal UI
+    /// Condensing is disabled if the keypair cannot be read
+    Tui {
+        #[clap(long, default_value = "~/.config/solana/id.json")]
+        keypair: String,
+
+        /// Seconds between background syncs
+        #[clap(long, default_value = "30")]
+        sync_interval: u64,
+
+        /// Compute unit price in micro-lamports. Defaults to the median of recent fees for the accounts involved
+        #[clap(long)]
+        priority_fee: Option<u64>,
+
+        /// Number of times to resend with a fresh blockhash if the transaction expires before confirming
+        #[clap(long, default_value = "3")]
+        max_retries: usize,
     },
 }
 
-#[derive(Clone, Debug, BorshDeserialize, BorshSerialize, PartialEq)]
-struct VaporAddressRecord {
-    addr: [u8; 32],
-    recipient: [u8; 32],
-    secret: String,
+/// Sync the wallet and report anything the user should know about
+fn sync(wallet: &Wallet, output: Output) -> anyhow::Result<SyncReport> {
+    let report = wallet.sync()?;
+    if let Some(onchain) = report.indexer_behind {
+        output.status(format!(
+            "Indexer is behind: wallet has {} of {} transfers",
+            report.transfers, onchain
+        ));
+    }
+    if report.stealth_payments > 0 {
+        output.status(format!(
+            "Found {} new stealth payment(s)",
+            report.stealth_payments
+        ));
+    }
+    Ok(report)
 }
 
-#[derive(Debug, BorshDeserialize, BorshSerialize, PartialEq)]
-pub struct TransferEvent {
-    pub to: [u8; 32],
-    pub amount: u64,
+fn read_keypair(path: &str) -> anyhow::Result<Keypair> {
+    let path = shellexpand::tilde(path).to_string();
+    read_keypair_file(&path).map_err(|e| anyhow::anyhow!("cannot read keypair {}: {}", path, e))
 }
 
 fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt::init();
+    // Logs go to stderr so that stdout only holds results
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
     let args = Args::parse();
+    let output = Output {
+        format: args.output,
+    };
+
+    match run(args, output) {
+        Err(e) if output.is_json() => {
+            println!(
+                "{}",
+                serde_json::to_string(&ErrorOutput {
+                    error: format!("{:#}", e)
+                })?
+            );
+            std::process::exit(1);
+        }
+        result => result,
+    }
+}
 
-    let db = redb::Database::create(&args.wallet_file)?;
+fn run(args: Args, output: Output) -> anyhow::Result<()> {
+    let wallet = Wallet::open(
+        &args.wallet_file,
+        WalletConfig {
+            rpc_url: args.rpc_url.clone(),
+            mint: Pubkey::from_str(&args.mint)?,
+            indexer_url: args.indexer_url.clone(),
+            show_prover_output: !matches!(args.cmd, Command::Tui { .. }),
+            vk_version: args.vk_version,
+            disclosure_vk_version: args.disclosure_vk_version,
+        },
+    )?;
 
     match args.cmd {
-        Command::GenAddress { recipient } => {
-            commands::gen_vapor_address(&db, &recipient)?;
+        Command::GenAddress {
+            recipient,
+            prefix,
+            threads,
+            include_secrets,
+        } => {
+            let threads = match threads {
+                Some(threads) => threads,
+                None => std::thread::available_parallelism()?.get(),
+            };
+            commands::gen_vapor_address(
+                &wallet,
+                output,
+                &recipient,
+                prefix.as_deref(),
+                threads,
+                include_secrets,
+            )?;
         }
-        Command::List => {
-            sync::sync(&db, &args.rpc_url, &args.mint)?;
-            commands::list(&db)?;
+        Command::MetaAddress { recipient } => {
+            commands::meta_address(&wallet, output, &recipient)?;
+        }
+        Command::List { include_secrets } => {
+            let report = sync(&wallet, output)?;
+            commands::list(&wallet, output, report, include_secrets)?;
+        }
+        Command::AnonymitySet { vapor_addr } => {
+            let report = sync(&wallet, output)?;
+            commands::anonymity_set(&wallet, output, report, &vapor_addr)?;
         }
         Command::Condense {
             vapor_addr,
             keypair,
+            leaf_index,
+            yes,
+            amount,
+            priority_fee,
+            max_retries,
+        } => {
+            let signer = read_keypair(&keypair)?;
+            let report = sync(&wallet, output)?;
+            commands::condense(
+                &wallet,
+                output,
+                report,
+                signer,
+                &vapor_addr,
+                commands::Selection { leaf_index, yes },
+                amount,
+                &SubmitOptions {
+                    priority_fee,
+                    max_retries,
+                },
+            )?;
+        }
+        Command::ScheduleCondense {
+            vapor_addr,
+            leaf_index,
+            yes,
+            amount,
+            after,
+            at,
+            within,
+        } => {
+            let report = sync(&wallet, output)?;
+            let start = match at {
+                Some(at) => at,
+                None => schedule::unix_now() + schedule::parse_duration(&after)?,
+            };
+            commands::schedule_condense(
+                &wallet,
+                output,
+                report,
+                &vapor_addr,
+                commands::Selection { leaf_index, yes },
+                amount,
+                commands::Window {
+                    start,
+                    length: schedule::parse_duration(&within)?,
+                },
+            )?;
+        }
+        Command::Jobs => {
+            commands::jobs(&wallet, output)?;
+        }
+        Command::CancelJob { id } => {
+            commands::cancel_job(&wallet, output, id)?;
+        }
+        Command::Watch {
+            keypair,
+            poll_interval,
+            max_attempts,
+            retry_delay,
+            priority_fee,
+            max_retries,
         } => {
-            let keypair = shellexpand::tilde(&keypair).to_string();
-            let signer = read_keypair_file(keypair).unwrap();
-            sync::sync(&db, &args.rpc_url, &args.mint)?;
-            commands::condense::<TREE_HEIGHT>(
-                &db,
-                &args.rpc_url,
+            let signer = read_keypair(&keypair)?;
+            commands::watch(
+                &wallet,
+                output,
                 signer,
-                Pubkey::from_str(&args.mint)?,
+                &commands::WatchOptions {
+                    poll_interval: Duration::from_secs(poll_interval),
+                    retry: RetryPolicy {
+                        max_attempts,
+                        delay: schedule::parse_duration(&retry_delay)?,
+                    },
+                    submit: SubmitOptions {
+                        priority_fee,
+                        max_retries,
+                    },
+                },
+            )?;
+        }
+        Command::Send {
+            keypair,
+            destination,
+            amount,
+        } => {
+            let signer = read_keypair(&keypair)?;
+            commands::send(&wallet, output, signer, destination.parse()?, amount)?;
+        }
+        Command::Disclose {
+            vapor_addr,
+            slots,
+            condenses,
+            out,
+        } => {
+            let report = sync(&wallet, output)?;
+            commands::disclose(
+                &wallet,
+                output,
+                report,
                 &vapor_addr,
+                &slots,
+                &condenses,
+                &out,
+            )?;
+        }
+        Command::VerifyDisclosure { path } => {
+            let report = sync(&wallet, output)?;
+            commands::verify_disclosure(&wallet, output, report, &path)?;
+        }
+        Command::Tui {
+            keypair,
+            sync_interval,
+            priority_fee,
+            max_retries,
+        } => {
+            tui::tui(
+                wallet,
+                read_keypair(&keypair).ok(),
+                tui::TuiOptions {
+                    sync_interval: Duration::from_secs(sync_interval),
+                    submit: SubmitOptions {
+                        priority_fee,
+                        max_retries,
+                    },
+                },
             )?;
         }
     };
@@ -0,0 +1,142 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+use vapor_wallet::schedule::{CondenseJob, JobStatus};
+use vapor_wallet::{Deposit, SyncReport, VaporAddressRecord};
+
+/// How command results are printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum OutputFormat {
+    /// Human readable text
+    Text,
+    /// A single JSON document on stdout per result. Progress and prompts go to stderr
+    Json,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Output {
+    pub format: OutputFormat,
+}
+
+impl Output {
+    pub fn is_json(&self) -> bool {
+        self.format == OutputFormat::Json
+    }
+
+    /// Print a progress message or prompt, kept off stdout in JSON mode so it only holds results
+    pub fn status(&self, message: impl Display) {
+        match self.format {
+            OutputFormat::Text => println!("{}", message),
+            OutputFormat::Json => eprintln!("{}", message),
+        }
+    }
+
+    /// Print a result in JSON mode, or run `text` to print it for humans
+    pub fn result<T: Serialize>(
+        &self,
+        result: &T,
+        text: impl FnOnce() -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        match self.format {
+            OutputFormat::Text => text()?,
+            OutputFormat::Json => println!("{}", serde_json::to_string(result)?),
+        }
+        Ok(())
+    }
+}
+
+/// Printed in place of a result when a command fails in JSON mode
+#[derive(Serialize)]
+pub(crate) struct ErrorOutput {
+    pub error: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct AddressOutput {
+    pub vapor_addr: String,
+    pub recipient: String,
+    /// Only set when secrets are explicitly requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deposits: Option<Vec<Deposit>>,
+}
+
+impl AddressOutput {
+    pub fn new(record: &VaporAddressRecord, include_secret: bool) -> Self {
+        Self {
+            vapor_addr: bs58::encode(record.addr).into_string(),
+            recipient: bs58::encode(record.recipient).into_string(),
+            secret: include_secret.then(|| record.secret.to_decimal_string().as_str().to_string()),
+            deposits: None,
+        }
+    }
+}
+
+/// A result along with the sync that ran before the command
+#[derive(Serialize)]
+pub(crate) struct Synced<T> {
+    pub sync: SyncReport,
+    #[serde(flatten)]
+    pub result: T,
+}
+
+#[derive(Serialize)]
+pub(crate) struct JobOutput {
+    pub id: u64,
+    pub vapor_addr: String,
+    pub leaf_index: u64,
+    /// The recipient's total withdrawn once the job has run
+    pub total: u64,
+    pub run_at: u64,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    /// One of `pending`, `done`, `failed` or `reached`
+    pub status: &'static str,
+    pub signature: Option<String>,
+}
+
+impl JobOutput {
+    pub fn new(id: u64, job: &CondenseJob) -> Self {
+        let (status, signature) = match &job.status {
+            JobStatus::Pending => ("pending", None),
+            JobStatus::Done { signature } => ("done", Some(signature.clone())),
+            JobStatus::Failed => ("failed", None),
+            JobStatus::Reached => ("reached", None),
+        };
+        Self {
+            id,
+            vapor_addr: bs58::encode(job.vapor_addr).into_string(),
+            leaf_index: job.leaf_index,
+            total: job.total,
+            run_at: job.run_at,
+            attempts: job.attempts,
+            last_error: job.last_error.clone(),
+            status,
+            signature,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_only_when_requested() {
+        let (addr, secret) =
+            vaporize_addresses::generate_vaporize_address(&mut rand::thread_rng(), [1u8; 32]);
+        let record = VaporAddressRecord {
+            addr,
+            recipient: [1u8; 32],
+            secret,
+        };
+
+        let json = serde_json::to_value(AddressOutput::new(&record, false)).unwrap();
+        assert_eq!(json["vapor_addr"], bs58::encode(addr).into_string());
+        assert!(json.get("secret").is_none());
+
+        let json = serde_json::to_value(AddressOutput::new(&record, true)).unwrap();
+        assert_eq!(json["secret"], record.secret.to_decimal_string().as_str());
+    }
+}
@@ -0,0 +1,536 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use vapor_wallet::anonymity::AnonymitySet;
+use vapor_wallet::{Deposit, SyncReport, VaporAddressRecord, parse_address};
+
+/// Wallet contents loaded after each sync
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Snapshot {
+    pub report: SyncReport,
+    pub addresses: Vec<AddressView>,
+    /// Total condensed so far by recipient
+    pub withdrawn: HashMap<[u8; 32], u64>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct AddressView {
+    pub record: VaporAddressRecord,
+    pub deposits: Vec<DepositView>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DepositView {
+    pub deposit: Deposit,
+    pub anonymity: AnonymitySet,
+}
+
+/// Condensed and still condensable amounts for a recipient
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Balance {
+    pub condensed: u64,
+    /// What the next condense can mint. The recipient's running total is capped by the deposit being condensed,
+    /// so this is set by their largest deposit
+    pub pending: u64,
+}
+
+impl Snapshot {
+    pub fn balance(&self, recipient: [u8; 32]) -> Balance {
+        let condensed = self.withdrawn.get(&recipient).copied().unwrap_or(0);
+        let largest = self
+            .addresses
+            .iter()
+            .filter(|address| address.record.recipient == recipient)
+            .flat_map(|address| &address.deposits)
+            .map(|deposit| deposit.deposit.amount)
+            .max()
+            .unwrap_or(0);
+        Balance {
+            condensed,
+            pending: largest.saturating_sub(condensed),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum SyncStatus {
+    Syncing,
+    Synced { at: Instant },
+    Failed(String),
+}
+
+/// Steps of a condense started from the TUI, in order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Stage {
+    Syncing,
+    Planning,
+    Proving,
+    Submitting,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [
+        Stage::Syncing,
+        Stage::Planning,
+        Stage::Proving,
+        Stage::Submitting,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Stage::Syncing => "Sync the transfer tree",
+            Stage::Planning => "Build the Merkle proof and witness",
+            Stage::Proving => "Generate and verify the proof",
+            Stage::Submitting => "Submit the condense transaction",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Progress {
+    pub stage: Stage,
+    pub started: Instant,
+    pub stage_started: Instant,
+    /// Signature or error once the condense has finished
+    pub result: Option<Result<String, String>>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Popup {
+    NewAddress {
+        input: String,
+    },
+    Qr {
+        address: String,
+    },
+    Condense {
+        vapor_addr: [u8; 32],
+        deposit: Deposit,
+        anonymity: AnonymitySet,
+        amount: String,
+    },
+    Progress(Progress),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Focus {
+    Addresses,
+    Deposits,
+}
+
+/// Results sent back by the worker threads
+#[derive(Debug)]
+pub(crate) enum WorkerEvent {
+    Synced(Result<Snapshot, String>),
+    AddressCreated(Result<VaporAddressRecord, String>),
+    Stage(Stage),
+    Condensed(Result<String, String>),
+}
+
+/// Work the event loop should start in response to a key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Action {
+    Quit,
+    Sync,
+    NewAddress {
+        recipient: [u8; 32],
+    },
+    Condense {
+        vapor_addr: [u8; 32],
+        leaf_index: u64,
+        amount: Option<u64>,
+    },
+}
+
+pub(crate) struct App {
+    pub mint: String,
+    pub snapshot: Snapshot,
+    pub sync: SyncStatus,
+    pub focus: Focus,
+    pub selected_address: usize,
+    pub selected_deposit: usize,
+    pub popup: Option<Popup>,
+    /// Last message for the status line
+    pub message: Option<String>,
+    /// Recipient offered when generating an address, the payer's address if there is one
+    pub default_recipient: Option<String>,
+}
+
+impl App {
+    pub fn new(mint: String, default_recipient: Option<String>) -> Self {
+        Self {
+            mint,
+            snapshot: Snapshot::default(),
+            sync: SyncStatus::Syncing,
+            focus: Focus::Addresses,
+            selected_address: 0,
+            selected_deposit: 0,
+            popup: None,
+            message: None,
+            default_recipient,
+        }
+    }
+
+    pub fn address(&self) -> Option<&AddressView> {
+        self.snapshot.addresses.get(self.selected_address)
+    }
+
+    pub fn deposit(&self) -> Option<&DepositView> {
+        self.address()?.deposits.get(self.selected_deposit)
+    }
+
+    /// A condense is running, so another must not be started
+    pub fn condensing(&self) -> bool {
+        matches!(&self.popup, Some(Popup::Progress(progress)) if progress.result.is_none())
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Some(Action::Quit);
+        }
+        match self.popup.take() {
+            None => self.handle_main_key(key),
+            Some(popup) => self.handle_popup_key(popup, key),
+        }
+    }
+
+    fn handle_main_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Some(Action::Quit),
+            KeyCode::Char('s') => return Some(Action::Sync),
+            KeyCode::Char('n') => {
+                self.popup = Some(Popup::NewAddress {
+                    input: self.default_recipient.clone().unwrap_or_default(),
+                })
+            }
+            KeyCode::Tab | KeyCode::Left | KeyCode::Right => {
+                self.focus = match self.focus {
+                    Focus::Addresses => Focus::Deposits,
+                    Focus::Deposits => Focus::Addresses,
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Char('r') => self.show_qr(),
+            KeyCode::Char('c') => self.start_condense(),
+            KeyCode::Enter => match self.focus {
+                Focus::Addresses => self.show_qr(),
+                Focus::Deposits => self.start_condense(),
+            },
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_popup_key(&mut self, popup: Popup, key: KeyEvent) -> Option<Action> {
+        match popup {
+            Popup::NewAddress { mut input } => match key.code {
+                KeyCode::Esc => {}
+                KeyCode::Enter => match parse_address("recipient", input.trim()) {
+                    Ok(recipient) => return Some(Action::NewAddress { recipient }),
+                    Err(e) => {
+                        self.message = Some(e.to_string());
+                        self.popup = Some(Popup::NewAddress { input });
+                    }
+                },
+                _ => {
+                    edit(&mut input, key.code);
+                    self.popup = Some(Popup::NewAddress { input });
+                }
+            },
+            Popup::Qr { .. } => {}
+            Popup::Condense {
+                vapor_addr,
+                deposit,
+                anonymity,
+                mut amount,
+            } => match key.code {
+                KeyCode::Esc => {}
+                KeyCode::Enter => {
+                    let parsed = match amount.trim() {
+                        "" => Ok(None),
+                        amount => amount.parse::<u64>().map(Some),
+                    };
+                    match parsed {
+                        Ok(amount) => {
+                            return Some(Action::Condense {
+                                vapor_addr,
+                                leaf_index: deposit.leaf_index,
+                                amount,
+                            });
+                        }
+                        Err(e) => {
+                            self.message = Some(format!("invalid amount: {}", e));
+                        }
+                    }
+                    self.popup = Some(Popup::Condense {
+                        vapor_addr,
+                        deposit,
+                        anonymity,
+                        amount,
+                    });
+                }
+                code => {
+                    if matches!(code, KeyCode::Char(c) if !c.is_ascii_digit()) {
+                        // Only digits make up an amount
+                    } else {
+                        edit(&mut amount, code);
+                    }
+                    self.popup = Some(Popup::Condense {
+                        vapor_addr,
+                        deposit,
+                        anonymity,
+                        amount,
+                    });
+                }
+            },
+            Popup::Progress(progress) => {
+                // Stays up until the condense has finished
+                if progress.result.is_none() || key.code != KeyCode::Esc {
+                    self.popup = Some(Popup::Progress(progress));
+                }
+            }
+        }
+        None
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let (selected, len) = match self.focus {
+            Focus::Addresses => (&mut self.selected_address, self.snapshot.addresses.len()),
+            Focus::Deposits => (
+                &mut self.selected_deposit,
+                self.snapshot
+                    .addresses
+                    .get(self.selected_address)
+                    .map_or(0, |address| address.deposits.len()),
+            ),
+        };
+        if len == 0 {
+            return;
+        }
+        *selected = selected.saturating_add_signed(delta).min(len - 1);
+        if self.focus == Focus::Addresses {
+            self.selected_deposit = 0;
+        }
+    }
+
+    fn show_qr(&mut self) {
+        if let Some(address) = self.address() {
+            self.popup = Some(Popup::Qr {
+                address: bs58::encode(address.record.addr).into_string(),
+            });
+        }
+    }
+
+    fn start_condense(&mut self) {
+        if self.condensing() {
+            return;
+        }
+        let Some(address) = self.address() else {
+            return;
+        };
+        let vapor_addr = address.record.addr;
+        match self.deposit() {
+            Some(deposit) => {
+                self.popup = Some(Popup::Condense {
+                    vapor_addr,
+                    deposit: deposit.deposit,
+                    anonymity: deposit.anonymity.clone(),
+                    amount: String::new(),
+                })
+            }
+            None => self.message = Some("No deposit selected".to_string()),
+        }
+    }
+
+    /// Show a condense as started, before the worker reports its first stage
+    pub fn condense_started(&mut self) {
+        let now = Instant::now();
+        self.popup = Some(Popup::Progress(Progress {
+            stage: Stage::Syncing,
+            started: now,
+            stage_started: now,
+            result: None,
+        }));
+    }
+
+    pub fn apply(&mut self, event: WorkerEvent) {
+        match event {
+            WorkerEvent::Synced(Ok(snapshot)) => {
+                self.snapshot = snapshot;
+                self.selected_address = self
+                    .selected_address
+                    .min(self.snapshot.addresses.len().saturating_sub(1));
+                let deposits = self.address().map_or(0, |address| address.deposits.len());
+                self.selected_deposit = self.selected_deposit.min(deposits.saturating_sub(1));
+                self.sync = SyncStatus::Synced { at: Instant::now() };
+            }
+            WorkerEvent::Synced(Err(e)) => self.sync = SyncStatus::Failed(e),
+            WorkerEvent::AddressCreated(Ok(record)) => {
+                let address = bs58::encode(record.addr).into_string();
+                self.message = Some(format!("Generated vaporize address {}", address));
+                self.snapshot.addresses.push(AddressView {
+                    record,
+                    deposits: Vec::new(),
+                });
+                self.selected_address = self.snapshot.addresses.len() - 1;
+                self.selected_deposit = 0;
+                self.focus = Focus::Addresses;
+                self.popup = Some(Popup::Qr { address });
+            }
+            WorkerEvent::AddressCreated(Err(e)) => self.message = Some(e),
+            WorkerEvent::Stage(stage) => {
+                if let Some(Popup::Progress(progress)) = &mut self.popup {
+                    progress.stage = stage;
+                    progress.stage_started = Instant::now();
+                }
+            }
+            WorkerEvent::Condensed(result) => {
+                self.message = Some(match &result {
+                    Ok(signature) => format!("Condensed in {}", signature),
+                    Err(e) => format!("Condense failed: {}", e),
+                });
+                if let Some(Popup::Progress(progress)) = &mut self.popup {
+                    progress.result = Some(result);
+                }
+            }
+        }
+    }
+}
+
+/// Apply a key to a single line text input
+fn edit(input: &mut String, code: KeyCode) {
+    match code {
+        KeyCode::Char(c) => input.push(c),
+        KeyCode::Backspace => {
+            input.pop();
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn address(addr: u8, recipient: u8, amounts: &[u64]) -> AddressView {
+        let (_, secret) =
+            vaporize_addresses::generate_vaporize_address(&mut rand::thread_rng(), [recipient; 32]);
+        AddressView {
+            record: VaporAddressRecord {
+                addr: [addr; 32],
+                recipient: [recipient; 32],
+                secret,
+            },
+            deposits: amounts
+                .iter()
+                .enumerate()
+                .map(|(i, amount)| DepositView {
+                    deposit: Deposit {
+                        leaf_index: i as u64,
+                        slot: 100 + i as u64,
+                        amount: *amount,
+                    },
+                    anonymity: AnonymitySet {
+                        amount: *amount,
+                        same_amount: 1,
+                        leaves_since: 0,
+                        common_amounts: Vec::new(),
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    fn app() -> App {
+        let mut app = App::new("mint".to_string(), None);
+        app.apply(WorkerEvent::Synced(Ok(Snapshot {
+            report: SyncReport::default(),
+            addresses: vec![address(1, 9, &[100, 40]), address(2, 9, &[70])],
+            withdrawn: HashMap::from([([9; 32], 30)]),
+        })));
+        app
+    }
+
+    #[test]
+    fn test_balance_is_capped_by_the_largest_deposit() {
+        let app = app();
+        assert_eq!(
+            app.snapshot.balance([9; 32]),
+            Balance {
+                condensed: 30,
+                pending: 70
+            }
+        );
+        assert_eq!(app.snapshot.balance([8; 32]), Balance::default());
+    }
+
+    #[test]
+    fn test_navigation_stays_in_bounds() {
+        let mut app = app();
+        app.handle_key(key(KeyCode::Up));
+        assert_eq!(app.selected_address, 0);
+        app.handle_key(key(KeyCode::Down));
+        app.handle_key(key(KeyCode::Down));
+        assert_eq!(app.selected_address, 1);
+
+        app.handle_key(key(KeyCode::Up));
+        app.handle_key(key(KeyCode::Tab));
+        app.handle_key(key(KeyCode::Down));
+        app.handle_key(key(KeyCode::Down));
+        assert_eq!(app.deposit().unwrap().deposit.amount, 40);
+    }
+
+    #[test]
+    fn test_condense_from_selected_deposit() {
+        let mut app = app();
+        app.handle_key(key(KeyCode::Tab));
+        app.handle_key(key(KeyCode::Down));
+        assert_eq!(app.handle_key(key(KeyCode::Enter)), None);
+        assert!(matches!(app.popup, Some(Popup::Condense { .. })));
+
+        // Letters are ignored in the amount
+        for c in "2x5".chars() {
+            app.handle_key(key(KeyCode::Char(c)));
+        }
+        assert_eq!(
+            app.handle_key(key(KeyCode::Enter)),
+            Some(Action::Condense {
+                vapor_addr: [1; 32],
+                leaf_index: 1,
+                amount: Some(25),
+            })
+        );
+
+        // The progress popup can't be closed while the condense runs
+        app.condense_started();
+        app.handle_key(key(KeyCode::Esc));
+        assert!(app.condensing());
+        app.apply(WorkerEvent::Condensed(Ok("sig".to_string())));
+        app.handle_key(key(KeyCode::Esc));
+        assert!(app.popup.is_none());
+    }
+
+    #[test]
+    fn test_new_address_requires_a_valid_recipient() {
+        let mut app = app();
+        app.handle_key(key(KeyCode::Char('n')));
+        app.handle_key(key(KeyCode::Char('x')));
+        assert_eq!(app.handle_key(key(KeyCode::Enter)), None);
+        assert!(matches!(app.popup, Some(Popup::NewAddress { .. })));
+
+        app.handle_key(key(KeyCode::Backspace));
+        for c in bs58::encode([3u8; 32]).into_string().chars() {
+            app.handle_key(key(KeyCode::Char(c)));
+        }
+        assert_eq!(
+            app.handle_key(key(KeyCode::Enter)),
+            Some(Action::NewAddress { recipient: [3; 32] })
+        );
+    }
+}
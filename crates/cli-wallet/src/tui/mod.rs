@@ -0,0 +1,213 @@
+//! Terminal UI for day-to-day use of the wallet.
+//! Syncing, address generation and condenses run on worker threads so the screen stays live,
+//! and report back to the event loop over a channel.
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::Arc;
+use std::sync::mpsc::{Sender, channel};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyEventKind};
+use ratatui::DefaultTerminal;
+use solana_sdk::signature::{Keypair, Signer};
+use vapor_wallet::anonymity::AnonymitySet;
+use vapor_wallet::{SubmitOptions, Wallet};
+
+use self::app::{Action, AddressView, App, DepositView, Snapshot, Stage, SyncStatus, WorkerEvent};
+
+mod app;
+mod ui;
+
+pub(crate) struct TuiOptions {
+    /// Time between background syncs
+    pub sync_interval: Duration,
+    pub submit: SubmitOptions,
+}
+
+/// Run the TUI until the user quits
+pub(crate) fn tui(
+    wallet: Wallet,
+    payer: Option<Keypair>,
+    options: TuiOptions,
+) -> anyhow::Result<()> {
+    let wallet = Arc::new(wallet);
+    let payer = payer.map(Arc::new);
+    let default_recipient = payer.as_ref().map(|payer| payer.pubkey().to_string());
+    let mut app = App::new(wallet.mint().to_string(), default_recipient);
+    if payer.is_none() {
+        app.message = Some("No keypair loaded, condensing is disabled".to_string());
+    }
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut app, wallet, payer, options);
+    ratatui::restore();
+    result
+}
+
+fn run(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    wallet: Arc<Wallet>,
+    payer: Option<Arc<Keypair>>,
+    options: TuiOptions,
+) -> anyhow::Result<()> {
+    let (tx, rx) = channel();
+    let mut workers = Workers {
+        wallet,
+        tx,
+        syncing: false,
+        last_sync: Instant::now(),
+    };
+    // Sync straight away, then again whenever asked to or the interval passes
+    let mut sync_requested = true;
+
+    loop {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+
+        if event::poll(Duration::from_millis(250))?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+            && let Some(action) = app.handle_key(key)
+        {
+            match action {
+                Action::Quit => return Ok(()),
+                Action::Sync => sync_requested = true,
+                Action::NewAddress { recipient } => workers.new_address(recipient),
+                Action::Condense {
+                    vapor_addr,
+                    leaf_index,
+                    amount,
+                } => match &payer {
+                    Some(payer) => {
+                        app.condense_started();
+                        workers.condense(
+                            payer,
+                            vapor_addr,
+                            leaf_index,
+                            amount,
+                            options.submit.clone(),
+                        );
+                    }
+                    None => {
+                        app.message = Some("Load a keypair with --keypair to condense".to_string())
+                    }
+                },
+            }
+        }
+
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                WorkerEvent::Synced(_) => workers.syncing = false,
+                // Pick up a finished condense in the balances
+                WorkerEvent::Condensed(Ok(_)) => sync_requested = true,
+                _ => {}
+            }
+            app.apply(event);
+        }
+
+        let due = !app.condensing() && workers.last_sync.elapsed() >= options.sync_interval;
+        if (sync_requested || due) && !workers.syncing {
+            sync_requested = false;
+            app.sync = SyncStatus::Syncing;
+            workers.sync();
+        }
+    }
+}
+
+/// Starts work on background threads, which report back over `tx`
+struct Workers {
+    wallet: Arc<Wallet>,
+    tx: Sender<WorkerEvent>,
+    /// A sync is running, only one runs at a time
+    syncing: bool,
+    last_sync: Instant,
+}
+
+impl Workers {
+    /// Sync and reload the wallet
+    fn sync(&mut self) {
+        self.syncing = true;
+        self.last_sync = Instant::now();
+
+        let wallet = self.wallet.clone();
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let result = load(&wallet).map_err(|e| format!("{:#}", e));
+            tx.send(WorkerEvent::Synced(result)).ok();
+        });
+    }
+
+    fn new_address(&self, recipient: [u8; 32]) {
+        let wallet = self.wallet.clone();
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let result = wallet.new_address(recipient).map_err(|e| e.to_string());
+            tx.send(WorkerEvent::AddressCreated(result)).ok();
+        });
+    }
+
+    fn condense(
+        &self,
+        payer: &Arc<Keypair>,
+        vapor_addr: [u8; 32],
+        leaf_index: u64,
+        amount: Option<u64>,
+        options: SubmitOptions,
+    ) {
+        let wallet = self.wallet.clone();
+        let payer = payer.clone();
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let stage = |stage| {
+                tx.send(WorkerEvent::Stage(stage)).ok();
+            };
+            let result = (|| {
+                // Sync right before proving so the proof is against a recent root
+                stage(Stage::Syncing);
+                wallet.sync()?;
+                stage(Stage::Planning);
+                let plan = wallet.plan_condense(vapor_addr, leaf_index, amount)?;
+                stage(Stage::Proving);
+                let proof = wallet.prove_condense(&plan)?;
+                stage(Stage::Submitting);
+                wallet.submit_condense(&payer, &proof, &options)
+            })();
+            tx.send(WorkerEvent::Condensed(
+                result
+                    .map(|signature| signature.to_string())
+                    .map_err(|e: vapor_wallet::WalletError| e.to_string()),
+            ))
+            .ok();
+        });
+    }
+}
+
+/// Sync then read everything the TUI shows
+fn load(wallet: &Wallet) -> anyhow::Result<Snapshot> {
+    let report = wallet.sync()?;
+    let transfers = wallet.transfers()?;
+
+    let mut addresses = Vec::new();
+    let mut withdrawn = HashMap::new();
+    for record in wallet.addresses()? {
+        if let Entry::Vacant(entry) = withdrawn.entry(record.recipient) {
+            entry.insert(wallet.total_withdrawn(record.recipient)?);
+        }
+        let deposits = wallet
+            .deposits(record.addr)?
+            .into_iter()
+            .map(|deposit| DepositView {
+                deposit,
+                anonymity: AnonymitySet::analyze(&transfers, deposit.leaf_index as usize),
+            })
+            .collect();
+        addresses.push(AddressView { record, deposits });
+    }
+
+    Ok(Snapshot {
+        report,
+        addresses,
+        withdrawn,
+    })
+}
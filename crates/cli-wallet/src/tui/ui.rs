@@ -0,0 +1,278 @@
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table, TableState, Wrap,
+};
+
+use super::app::{App, Focus, Popup, Progress, Stage, SyncStatus};
+
+const HELP: &str = "↑↓ move  tab switch  enter qr/condense  n new address  s sync  q quit";
+
+pub(crate) fn draw(frame: &mut Frame, app: &App) {
+    let [header, body, footer] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(8),
+        Constraint::Length(2),
+    ])
+    .areas(frame.area());
+
+    draw_header(frame, app, header);
+
+    let [addresses, right] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(body);
+    let [deposits, details] =
+        Layout::vertical([Constraint::Min(5), Constraint::Length(9)]).areas(right);
+    draw_addresses(frame, app, addresses);
+    draw_deposits(frame, app, deposits);
+    draw_details(frame, app, details);
+
+    let message = app.message.as_deref().unwrap_or("");
+    frame.render_widget(
+        Paragraph::new(vec![Line::from(message), Line::from(HELP.dark_gray())]),
+        footer,
+    );
+
+    match &app.popup {
+        None => {}
+        Some(Popup::NewAddress { input }) => draw_new_address(frame, input),
+        Some(Popup::Qr { address }) => draw_qr(frame, address),
+        Some(Popup::Condense {
+            deposit,
+            anonymity,
+            amount,
+            ..
+        }) => {
+            let mut lines = vec![
+                Line::from(format!(
+                    "Deposit of {} (leaf {}, slot {})",
+                    deposit.amount, deposit.leaf_index, deposit.slot
+                )),
+                Line::from(format!("Anonymity set: {}", anonymity)),
+            ];
+            for advice in anonymity.recommendations() {
+                lines.push(Line::from(format!("  - {}", advice)).yellow());
+            }
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                Span::raw("Amount (empty for all that remains): "),
+                Span::raw(amount.as_str()).bold(),
+                Span::raw("█"),
+            ]));
+            lines.push(Line::from("enter condense  esc cancel".dark_gray()));
+            draw_popup(frame, "Condense", lines, 70, 14);
+        }
+        Some(Popup::Progress(progress)) => draw_progress(frame, progress),
+    }
+}
+
+fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
+    let sync = match &app.sync {
+        SyncStatus::Syncing => Span::raw("syncing...").yellow(),
+        SyncStatus::Synced { at } => Span::raw(format!(
+            "{} transfers, synced {}s ago",
+            app.snapshot.report.transfers,
+            at.elapsed().as_secs()
+        ))
+        .green(),
+        SyncStatus::Failed(e) => Span::raw(format!("sync failed: {}", e)).red(),
+    };
+    let mut line = vec![Span::raw(format!("Mint {}  ", app.mint)), sync];
+    if let Some(onchain) = app.snapshot.report.indexer_behind {
+        line.push(Span::raw(format!("  indexer behind, {} on chain", onchain)).yellow());
+    }
+    frame.render_widget(
+        Paragraph::new(Line::from(line)).block(Block::bordered().title(" Vapor Wallet ")),
+        area,
+    );
+}
+
+fn focused_block(title: &str, focused: bool) -> Block<'_> {
+    let block = Block::new().borders(Borders::ALL).title(title);
+    if focused {
+        block.border_style(Style::new().fg(Color::Cyan))
+    } else {
+        block
+    }
+}
+
+fn draw_addresses(frame: &mut Frame, app: &App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .snapshot
+        .addresses
+        .iter()
+        .map(|address| {
+            ListItem::new(vec![
+                Line::from(bs58::encode(address.record.addr).into_string()),
+                Line::from(format!(
+                    "  to {}, {} deposit(s)",
+                    short(&bs58::encode(address.record.recipient).into_string()),
+                    address.deposits.len()
+                ))
+                .dark_gray(),
+            ])
+        })
+        .collect();
+    let list = List::new(items)
+        .block(focused_block(" Addresses ", app.focus == Focus::Addresses))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default()
+        .with_selected((!app.snapshot.addresses.is_empty()).then_some(app.selected_address));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_deposits(frame: &mut Frame, app: &App, area: Rect) {
+    let deposits = app.address().map_or(&[][..], |address| &address.deposits);
+    let rows = deposits.iter().map(|deposit| {
+        Row::new(vec![
+            Cell::from(deposit.deposit.leaf_index.to_string()),
+            Cell::from(deposit.deposit.slot.to_string()),
+            Cell::from(deposit.deposit.amount.to_string()),
+            Cell::from(deposit.anonymity.same_amount.to_string()),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(10),
+            Constraint::Length(12),
+            Constraint::Min(12),
+            Constraint::Length(10),
+        ],
+    )
+    .header(Row::new(vec!["Leaf", "Slot", "Amount", "Same amt"]).bold())
+    .block(focused_block(" Deposits ", app.focus == Focus::Deposits))
+    .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    let mut state =
+        TableState::default().with_selected((!deposits.is_empty()).then_some(app.selected_deposit));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn draw_details(frame: &mut Frame, app: &App, area: Rect) {
+    let mut lines = Vec::new();
+    if let Some(address) = app.address() {
+        let recipient = address.record.recipient;
+        let balance = app.snapshot.balance(recipient);
+        lines.push(Line::from(format!(
+            "Recipient {}",
+            bs58::encode(recipient).into_string()
+        )));
+        lines.push(Line::from(format!(
+            "Condensed {}  Pending {}",
+            balance.condensed, balance.pending
+        )));
+    }
+    if let Some(deposit) = app.deposit() {
+        lines.push(Line::from(format!("Anonymity set: {}", deposit.anonymity)));
+        let advice = deposit.anonymity.recommendations();
+        if advice.is_empty() {
+            lines.push(Line::from("Looks safe to condense").green());
+        }
+        for line in advice {
+            lines.push(Line::from(format!("  - {}", line)).yellow());
+        }
+    }
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(" Details ")),
+        area,
+    );
+}
+
+fn draw_new_address(frame: &mut Frame, input: &str) {
+    let lines = vec![
+        Line::from("Recipient Solana address (not the associated token address)"),
+        Line::from(vec![Span::raw(input).bold(), Span::raw("█")]),
+        Line::from(""),
+        Line::from("enter generate  esc cancel".dark_gray()),
+    ];
+    draw_popup(frame, "New vaporize address", lines, 60, 6);
+}
+
+fn draw_qr(frame: &mut Frame, address: &str) {
+    let mut lines: Vec<Line> = match qrcode::QrCode::new(address) {
+        Ok(code) => code
+            .render::<qrcode::render::unicode::Dense1x2>()
+            .quiet_zone(true)
+            .build()
+            .lines()
+            .map(|line| Line::from(line.to_string()))
+            .collect(),
+        Err(e) => vec![Line::from(format!("cannot render QR code: {}", e))],
+    };
+    lines.push(Line::from(address.to_string()).bold());
+    lines.push(Line::from("any key to close".dark_gray()));
+    let height = lines.len() as u16 + 2;
+    draw_popup(frame, "Vaporize address", lines, 60, height);
+}
+
+fn draw_progress(frame: &mut Frame, progress: &Progress) {
+    let mut lines = Vec::new();
+    for stage in Stage::ALL {
+        let line = if progress.result.is_some() || stage < progress.stage {
+            match (&progress.result, stage == progress.stage) {
+                (Some(Err(_)), true) => Line::from(format!("✗ {}", stage.label())).red(),
+                _ => Line::from(format!("✓ {}", stage.label())).green(),
+            }
+        } else if stage == progress.stage {
+            Line::from(format!(
+                "… {} ({}s)",
+                stage.label(),
+                progress.stage_started.elapsed().as_secs()
+            ))
+            .yellow()
+        } else {
+            Line::from(format!("  {}", stage.label())).dark_gray()
+        };
+        lines.push(line);
+    }
+    lines.push(Line::from(""));
+    match &progress.result {
+        None => lines.push(Line::from(format!(
+            "Running for {}s",
+            progress.started.elapsed().as_secs()
+        ))),
+        Some(Ok(signature)) => {
+            lines.push(Line::from("Transaction accepted").green());
+            lines.push(Line::from(signature.clone()));
+            lines.push(Line::from("esc to close".dark_gray()));
+        }
+        Some(Err(e)) => {
+            lines.push(Line::from(e.clone()).red());
+            lines.push(Line::from("esc to close".dark_gray()));
+        }
+    }
+    draw_popup(frame, "Condensing", lines, 70, 12);
+}
+
+fn draw_popup(frame: &mut Frame, title: &str, lines: Vec<Line>, width: u16, height: u16) {
+    let area = centered(frame.area(), width, height);
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(format!(" {} ", title))),
+        area,
+    );
+}
+
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Shorten a base58 address for display
+fn short(address: &str) -> String {
+    if address.len() <= 12 {
+        return address.to_string();
+    }
+    format!("{}…{}", &address[..6], &address[address.len() - 6..])
+}
@@ -0,0 +1,39 @@
+use ark_bn254::Fr as NoirField;
+use serde::{Deserialize, Serialize};
+use utils::{SpendSecret, pack_bytes};
+use zeroize::Zeroizing;
+
+use crate::encoding;
+
+/// Inputs for the `disclosure` circuit which proves a vapor address commits to a recipient
+/// without revealing the secret
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisclosureWitness {
+    #[serde(with = "encoding::fields")]
+    pub recipient: [NoirField; 2],
+    #[serde(with = "encoding::bytes")]
+    pub vapor_addr: [u8; 32],
+    #[serde(with = "encoding::secret")]
+    pub secret: SpendSecret,
+}
+
+impl DisclosureWitness {
+    pub fn new(recipient: [u8; 32], vapor_addr: [u8; 32], secret: SpendSecret) -> Self {
+        Self {
+            recipient: pack_bytes(&recipient)
+                .try_into()
+                .expect("recipient must be 2 field elements"),
+            vapor_addr,
+            secret,
+        }
+    }
+
+    /// Prover.toml contents. Contains the secret so is wiped when dropped
+    pub fn to_toml(&self) -> Zeroizing<String> {
+        Zeroizing::new(toml::to_string(self).expect("witness is always representable as TOML"))
+    }
+
+    pub fn from_toml(s: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(s)
+    }
+}
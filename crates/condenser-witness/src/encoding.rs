@@ -0,0 +1,263 @@
+//! Serde encodings for circuit inputs.
+//!
+//! Human readable formats (TOML, JSON) use the same encoding as a nargo `Prover.toml`:
+//! every value, including bytes, is written as a decimal string. Hex strings (`0x..`) and
+//! plain integers are also accepted when parsing since nargo accepts them too.
+//!
+//! Binary formats encode field elements as 32 big-endian bytes and bytes as themselves.
+
+use std::fmt;
+
+use ark_bn254::Fr as NoirField;
+use ark_ff::{BigInteger, PrimeField};
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A single value as written in a Prover.toml. Either a string or an integer
+enum Scalar {
+    Str(String),
+    Int(u64),
+}
+
+impl<'de> Deserialize<'de> for Scalar {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ScalarVisitor;
+
+        impl Visitor<'_> for ScalarVisitor {
+            type Value = Scalar;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a decimal or hex string or an integer")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Scalar, E> {
+                Ok(Scalar::Str(v.to_string()))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Scalar, E> {
+                Ok(Scalar::Int(v))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Scalar, E> {
+                u64::try_from(v)
+                    .map(Scalar::Int)
+                    .map_err(|_| E::custom("negative values are not supported"))
+            }
+        }
+
+        deserializer.deserialize_any(ScalarVisitor)
+    }
+}
+
+impl Scalar {
+    fn into_field<E: de::Error>(self) -> Result<NoirField, E> {
+        match self {
+            Scalar::Int(v) => Ok(NoirField::from(v)),
+            Scalar::Str(s) => {
+                parse_field(&s).ok_or_else(|| E::custom(format!("invalid field element {:?}", s)))
+            }
+        }
+    }
+
+    fn into_byte<E: de::Error>(self) -> Result<u8, E> {
+        let v = match self {
+            Scalar::Int(v) => v,
+            Scalar::Str(s) => match s.strip_prefix("0x") {
+                Some(hex) => u64::from_str_radix(hex, 16),
+                None => s.parse(),
+            }
+            .map_err(|_| E::custom(format!("invalid byte {:?}", s)))?,
+        };
+        u8::try_from(v).map_err(|_| E::custom(format!("byte out of range: {}", v)))
+    }
+}
+
+/// Parse a decimal or `0x` prefixed hex string into a field element, rejecting values not below the modulus
+fn parse_field(s: &str) -> Option<NoirField> {
+    match s.strip_prefix("0x") {
+        Some(hex) => {
+            let hex = if hex.len() % 2 == 1 {
+                format!("0{}", hex)
+            } else {
+                hex.to_string()
+            };
+            let bytes = hex::decode(hex).ok()?;
+            if bytes.len() > 32 {
+                return None;
+            }
+            let mut padded = [0u8; 32];
+            padded[32 - bytes.len()..].copy_from_slice(&bytes);
+            let field = NoirField::from_be_bytes_mod_order(&padded);
+            // from_be_bytes_mod_order silently reduces, so check the value round trips
+            (to_be_bytes(&field) == padded).then_some(field)
+        }
+        None => {
+            let field = s.parse::<NoirField>().ok()?;
+            // parsing also reduces, so reject anything that doesn't print back the same
+            let digits = s.trim_start_matches('0');
+            let digits = if digits.is_empty() { "0" } else { digits };
+            (field.to_string() == digits).then_some(field)
+        }
+    }
+}
+
+fn to_be_bytes(field: &NoirField) -> [u8; 32] {
+    field
+        .into_bigint()
+        .to_bytes_be()
+        .try_into()
+        .expect("bn254 field elements are 32 bytes")
+}
+
+pub(crate) mod field {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &NoirField, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&value.to_string())
+        } else {
+            to_be_bytes(value).serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NoirField, D::Error> {
+        if deserializer.is_human_readable() {
+            Scalar::deserialize(deserializer)?.into_field()
+        } else {
+            let bytes = <[u8; 32]>::deserialize(deserializer)?;
+            let field = NoirField::from_be_bytes_mod_order(&bytes);
+            if to_be_bytes(&field) != bytes {
+                return Err(de::Error::custom("field element is not below the modulus"));
+            }
+            Ok(field)
+        }
+    }
+}
+
+pub(crate) mod secret {
+    use super::*;
+    use utils::SpendSecret;
+
+    pub fn serialize<S: Serializer>(value: &SpendSecret, serializer: S) -> Result<S::Ok, S::Error> {
+        field::serialize(value.expose_secret(), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SpendSecret, D::Error> {
+        field::deserialize(deserializer).map(SpendSecret::new)
+    }
+}
+
+pub(crate) mod fields {
+    use super::*;
+
+    struct Wrapped(NoirField);
+
+    impl Serialize for Wrapped {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            field::serialize(&self.0, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Wrapped {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            field::deserialize(deserializer).map(Wrapped)
+        }
+    }
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        values: &[NoirField; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for value in values {
+            tuple.serialize_element(&Wrapped(*value))?;
+        }
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[NoirField; N], D::Error> {
+        deserializer
+            .deserialize_tuple(N, ArrayVisitor::<Wrapped, N>::new())
+            .map(|values| values.map(|Wrapped(f)| f))
+    }
+}
+
+pub(crate) mod bytes {
+    use super::*;
+
+    struct Wrapped(u8);
+
+    impl Serialize for Wrapped {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            if serializer.is_human_readable() {
+                serializer.serialize_str(&self.0.to_string())
+            } else {
+                serializer.serialize_u8(self.0)
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Wrapped {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            if deserializer.is_human_readable() {
+                Scalar::deserialize(deserializer)?.into_byte().map(Wrapped)
+            } else {
+                u8::deserialize(deserializer).map(Wrapped)
+            }
+        }
+    }
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        values: &[u8; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for value in values {
+            tuple.serialize_element(&Wrapped(*value))?;
+        }
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[u8; N], D::Error> {
+        deserializer
+            .deserialize_tuple(N, ArrayVisitor::<Wrapped, N>::new())
+            .map(|values| values.map(|Wrapped(b)| b))
+    }
+}
+
+/// Visits a fixed length sequence. Serde only implements arrays up to length 32
+struct ArrayVisitor<T, const N: usize>(std::marker::PhantomData<T>);
+
+impl<T, const N: usize> ArrayVisitor<T, N> {
+    fn new() -> Self {
+        Self(std::marker::PhantomData)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, const N: usize> Visitor<'de> for ArrayVisitor<T, N> {
+    type Value = [T; N];
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an array of length {}", N)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<[T; N], A::Error> {
+        let mut values = Vec::with_capacity(N);
+        while let Some(value) = seq.next_element()? {
+            if values.len() == N {
+                return Err(de::Error::invalid_length(N + 1, &self));
+            }
+            values.push(value);
+        }
+        values
+            .try_into()
+            .map_err(|values: Vec<T>| de::Error::invalid_length(values.len(), &self))
+    }
+}
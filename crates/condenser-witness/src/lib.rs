@@ -1,18 +1,38 @@
 use ark_bn254::Fr as NoirField;
 use ark_ff::PrimeField;
-use utils::pack_bytes;
+use utils::{SpendSecret, pack_bytes};
+use zeroize::Zeroizing;
 
 use bon::bon;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+mod disclosure;
+mod encoding;
+
+pub use disclosure::DisclosureWitness;
+
+/// Inputs for the `condenser` circuit.
+/// Serializes to the same layout as the circuit's Prover.toml so witnesses can be saved and handed to nargo directly
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CondenserWitness<const HEIGHT: usize> {
+    #[serde(with = "encoding::fields")]
     pub recipient: [NoirField; 2],
+    /// Total withdrawn to the recipient once this condense is applied, at most `deposit_amount`
+    #[serde(with = "encoding::field")]
     pub amount: NoirField,
+    #[serde(with = "encoding::field")]
     pub merkle_root: NoirField,
+    #[serde(with = "encoding::bytes")]
     pub vapor_addr: [u8; 32],
+    /// Amount of the transfer to `vapor_addr` that is in the tree
+    #[serde(with = "encoding::field")]
+    pub deposit_amount: NoirField,
+    #[serde(with = "encoding::fields")]
     pub merkle_proof: [NoirField; HEIGHT],
+    #[serde(with = "encoding::bytes")]
     pub merkle_proof_indices: [u8; HEIGHT],
-    pub secret: NoirField,
+    #[serde(with = "encoding::secret")]
+    pub secret: SpendSecret,
 }
 
 #[bon]
@@ -23,9 +43,10 @@ impl<const HEIGHT: usize> CondenserWitness<HEIGHT> {
         amount: u64,
         merkle_root: [u8; 32],
         vapor_addr: [u8; 32],
+        deposit_amount: u64,
         merkle_proof: [[u8; 32]; HEIGHT],
         merkle_proof_indices: [u8; HEIGHT],
-        secret: NoirField,
+        secret: SpendSecret,
     ) -> Self {
         Self {
             recipient: pack_bytes(&recipient)
@@ -34,74 +55,171 @@ impl<const HEIGHT: usize> CondenserWitness<HEIGHT> {
             amount: NoirField::from(amount),
             merkle_root: NoirField::from_be_bytes_mod_order(&merkle_root),
             vapor_addr,
+            deposit_amount: NoirField::from(deposit_amount),
             merkle_proof: merkle_proof.map(|node| NoirField::from_be_bytes_mod_order(&node)),
             merkle_proof_indices,
             secret,
         }
     }
 
-    pub fn to_toml(&self) -> String {
-        let mut toml_str = String::new();
-
-        toml_str.push_str(&format!(
-            "recipient = {:?}\n",
-            self.recipient
-                .iter()
-                .map(|b| b.to_string())
-                .collect::<Vec<_>>()
-        ));
-        toml_str.push_str(&format!("amount = \"{}\"\n", self.amount));
-
-        toml_str.push_str(&format!(
-            "merkle_root = \"{}\"\n",
-            self.merkle_root.to_string()
-        ));
-
-        toml_str.push_str(&format!(
-            "vapor_addr = {:?}\n",
-            self.vapor_addr
-                .iter()
-                .map(|b| b.to_string())
-                .collect::<Vec<_>>()
-        ));
-
-        toml_str.push_str(&format!(
-            "merkle_proof = {:?}\n",
-            self.merkle_proof
-                .iter()
-                .map(|node| node.to_string())
-                .collect::<Vec<_>>()
-        ));
-
-        toml_str.push_str(&format!(
-            "merkle_proof_indices = {:?}\n",
-            self.merkle_proof_indices
-                .iter()
-                .map(|b| b.to_string())
-                .collect::<Vec<_>>()
-        ));
-
-        toml_str.push_str(&format!("secret = {:?}\n", self.secret.to_string()));
-
-        toml_str
+    /// Prover.toml contents. Contains the secret so is wiped when dropped
+    pub fn to_toml(&self) -> Zeroizing<String> {
+        Zeroizing::new(toml::to_string(self).expect("witness is always representable as TOML"))
+    }
+
+    pub fn from_toml(s: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(s)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    #[test]
-    fn test_to_toml() {
-        let witness = CondenserWitness {
+
+    const PROVER_TOML: &str = include_str!("../../../circuits/condenser/Prover.toml");
+
+    fn witness() -> CondenserWitness<26> {
+        CondenserWitness {
             recipient: [NoirField::from(1u64); 2],
             amount: NoirField::from(42u64),
             merkle_root: NoirField::from(2u64),
             vapor_addr: [3u8; 32],
+            deposit_amount: NoirField::from(100u64),
             merkle_proof: [NoirField::from(0u64); 26],
             merkle_proof_indices: [0u8; 26],
-            secret: NoirField::from(4u64),
-        };
-        let toml_output = witness.to_toml();
-        println!("{}", toml_output);
+            secret: SpendSecret::new(NoirField::from(4u64)),
+        }
+    }
+
+    #[test]
+    fn test_toml_round_trip() {
+        let witness = witness();
+        assert_eq!(
+            CondenserWitness::<26>::from_toml(&witness.to_toml()).unwrap(),
+            witness
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let witness = witness();
+        let json = serde_json::to_string(&witness).unwrap();
+        assert_eq!(
+            serde_json::from_str::<CondenserWitness<26>>(&json).unwrap(),
+            witness
+        );
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let witness = witness();
+        let bytes = bincode::serialize(&witness).unwrap();
+        assert_eq!(
+            bincode::deserialize::<CondenserWitness<26>>(&bytes).unwrap(),
+            witness
+        );
+    }
+
+    #[test]
+    fn test_prover_toml_round_trip() {
+        let witness = CondenserWitness::<26>::from_toml(PROVER_TOML).unwrap();
+        assert_eq!(witness.amount, NoirField::from(600000000000u64));
+        assert_eq!(witness.deposit_amount, NoirField::from(666000000000u64));
+        assert_eq!(witness.merkle_proof_indices[0], 1);
+
+        // Same values and encoding as the file nargo executes
+        let expected: toml::Table = toml::from_str(PROVER_TOML).unwrap();
+        let actual: toml::Table = toml::from_str(&witness.to_toml()).unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_builder_matches_field_encoding() {
+        let witness = CondenserWitness::<26>::builder()
+            .recipient([7u8; 32])
+            .amount(42)
+            .merkle_root([0u8; 32])
+            .vapor_addr([3u8; 32])
+            .deposit_amount(100)
+            .merkle_proof([[0u8; 32]; 26])
+            .merkle_proof_indices([0u8; 26])
+            .secret(SpendSecret::new(NoirField::from(4u64)))
+            .build();
+        let toml: toml::Table = toml::from_str(&witness.to_toml()).unwrap();
+        assert_eq!(toml["amount"].as_str(), Some("42"));
+        assert_eq!(toml["deposit_amount"].as_str(), Some("100"));
+        assert_eq!(toml["merkle_root"].as_str(), Some("0"));
+        assert_eq!(toml["vapor_addr"][0].as_str(), Some("3"));
+    }
+
+    #[test]
+    fn test_accepts_nargo_integer_formats() {
+        let toml = witness()
+            .to_toml()
+            .replace("amount = \"42\"", "amount = \"0x2a\"")
+            .replace("merkle_root = \"2\"", "merkle_root = 2");
+        let witness = CondenserWitness::<26>::from_toml(&toml).unwrap();
+        assert_eq!(witness.amount, NoirField::from(42u64));
+        assert_eq!(witness.merkle_root, NoirField::from(2u64));
+    }
+
+    #[test]
+    fn test_rejects_invalid_values() {
+        let wrong_length = witness().to_toml().replace(
+            "merkle_proof_indices = [\"0\", ",
+            "merkle_proof_indices = [",
+        );
+        assert!(CondenserWitness::<26>::from_toml(&wrong_length).is_err());
+
+        // the bn254 scalar field modulus
+        let out_of_range = witness().to_toml().replace(
+            "amount = \"42\"",
+            "amount = \"21888242871839275222246405745257275088548364400416034343698204186575808495617\"",
+        );
+        assert!(CondenserWitness::<26>::from_toml(&out_of_range).is_err());
+
+        let bad_byte = witness()
+            .to_toml()
+            .replace("vapor_addr = [\"3\"", "vapor_addr = [\"256\"");
+        assert!(CondenserWitness::<26>::from_toml(&bad_byte).is_err());
+    }
+
+    /// Run `nargo execute` on generated inputs, written next to the circuit's Prover.toml under another name
+    fn nargo_execute(circuit: &str, toml: &str) {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../circuits")
+            .join(circuit);
+        let name = format!("Generated{}", std::process::id());
+        let input = dir.join(format!("{}.toml", name));
+        std::fs::write(&input, toml).unwrap();
+
+        let status = std::process::Command::new("nargo")
+            .arg("execute")
+            .arg("--prover-name")
+            .arg(&name)
+            .arg(&name)
+            .current_dir(&dir)
+            .status();
+        std::fs::remove_file(&input).unwrap();
+        let _ = std::fs::remove_file(dir.join("target").join(format!("{}.gz", name)));
+
+        assert!(
+            status.expect("failed to run nargo").success(),
+            "nargo rejected the generated {} inputs",
+            circuit
+        );
+    }
+
+    /// Needs nargo and the circuits' dependencies, run with `just test_witness`
+    #[test]
+    #[ignore = "runs nargo"]
+    fn test_nargo_executes_generated_toml() {
+        let witness = CondenserWitness::<26>::from_toml(PROVER_TOML).unwrap();
+        nargo_execute("condenser", &witness.to_toml());
+
+        let disclosure =
+            DisclosureWitness::from_toml(include_str!("../../../circuits/disclosure/Prover.toml"))
+                .unwrap();
+        nargo_execute("disclosure", &disclosure.to_toml());
     }
 }
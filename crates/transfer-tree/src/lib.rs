@@ -1,151 +1,316 @@
-use ark_ff::{BigInteger, PrimeField};
-use light_bounded_vec::BoundedVec;
-use light_concurrent_merkle_tree::{ConcurrentMerkleTree, errors::ConcurrentMerkleTreeError};
-use light_hasher::{Hasher, Poseidon};
-use utils::pack_bytes;
+//! The append-only Merkle tree of transfers.
+//!
+//! The tree is stored as a frontier: the most recently filled node on each level. That is enough
+//! to append a leaf and compute the new root, which is all the transfer hook needs on-chain.
+//! The free functions here operate on a borrowed frontier so the hook can use the one stored in its
+//! account, while [`TransferTree`] owns its frontier and additionally produces [`Witness`]es for
+//! clients that need inclusion proofs. Both go through the same code so the roots always agree.
+#![no_std]
 
-pub type TransferTree<const HEIGHT: usize> = ConcurrentMerkleTree<Poseidon, HEIGHT>;
+use core::fmt;
 
-pub trait TransferTreeExt<const HEIGHT: usize> {
-    fn new_empty() -> TransferTree<HEIGHT>;
+pub use light_hasher::{Hasher, Poseidon};
 
-    fn append_transfer(
-        &mut self,
-        destination: [u8; 32],
-        amount: u64,
-    ) -> Result<(usize, usize), ConcurrentMerkleTreeError>;
+/// Appending to a tree that already holds `2^height` leaves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeFull;
 
-    fn append_transfer_with_proof(
-        &mut self,
-        recipient: [u8; 32],
-        amount: u64,
-        proof: &mut BoundedVec<[u8; 32]>,
-    ) -> Result<(usize, usize), ConcurrentMerkleTreeError>;
+impl fmt::Display for TreeFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("transfer tree is full")
+    }
+}
 
-    fn validate_transfer_proof(
-        &self,
-        destination: [u8; 32],
-        amount: u64,
-        index: usize,
-        proof: &BoundedVec<[u8; 32]>,
-    ) -> Result<(), ConcurrentMerkleTreeError>;
+impl core::error::Error for TreeFull {}
+
+/// Leaf recorded for a transfer of `amount` to `destination`.
+///
+/// This is `Poseidon(pack_bytes(destination)[0], pack_bytes(destination)[1], amount)`, the same
+/// commitment the condenser circuit opens. The address is split into a 31 byte and a 1 byte
+/// little-endian chunk as `utils::pack_bytes` does, written out here so no allocation is needed.
+pub fn transfer_leaf(destination: &[u8; 32], amount: u64) -> [u8; 32] {
+    let mut low = [0u8; 32];
+    for (i, b) in destination[..31].iter().enumerate() {
+        low[31 - i] = *b;
+    }
+    let mut high = [0u8; 32];
+    high[31] = destination[31];
+    let mut amount_bytes = [0u8; 32];
+    amount_bytes[24..].copy_from_slice(&amount.to_be_bytes());
+
+    Poseidon::hashv(&[&low, &high, &amount_bytes]).expect("poseidon inputs are field elements")
+}
+
+/// Reset a frontier to the empty tree of height `frontier.len()` and return its root
+pub fn init<H: Hasher>(frontier: &mut [[u8; 32]]) -> [u8; 32] {
+    let zero_bytes = H::zero_bytes();
+    frontier.copy_from_slice(&zero_bytes[..frontier.len()]);
+    zero_bytes[frontier.len()]
+}
 
-    fn proof_indices(&self, index: usize) -> [u8; HEIGHT];
+/// Append `leaf` at position `index` of the tree with the given frontier and return the new root.
+/// `index` is the number of leaves already in the tree, tracking it is up to the caller.
+pub fn append<H: Hasher>(
+    frontier: &mut [[u8; 32]],
+    index: u64,
+    leaf: [u8; 32],
+) -> Result<[u8; 32], TreeFull> {
+    append_visiting::<H>(frontier, index, leaf, |_, _, _| {})
 }
 
-impl<const HEIGHT: usize> TransferTreeExt<HEIGHT> for TransferTree<HEIGHT> {
-    fn new_empty() -> TransferTree<HEIGHT> {
-        let mut tree = TransferTree::<HEIGHT>::new(HEIGHT, 2, 1, 0).unwrap();
-        tree.init().unwrap();
-        tree
+/// [`append`], calling `visit(level, node, sibling)` for each node on the path from the new leaf
+/// (level 0) up to, but excluding, the root
+fn append_visiting<H: Hasher>(
+    frontier: &mut [[u8; 32]],
+    index: u64,
+    leaf: [u8; 32],
+    mut visit: impl FnMut(usize, &[u8; 32], &[u8; 32]),
+) -> Result<[u8; 32], TreeFull> {
+    let height = frontier.len();
+    if index >= 1u64 << height {
+        return Err(TreeFull);
     }
 
-    fn append_transfer(
-        &mut self,
-        destination: [u8; 32],
-        amount: u64,
-    ) -> Result<(usize, usize), ConcurrentMerkleTreeError> {
-        let leaf = build_leaf(destination, amount);
-        self.append(&leaf)
+    let zero_bytes = H::zero_bytes();
+    let mut current_index = index;
+    let mut node = leaf;
+    for (level, subtree) in frontier.iter_mut().enumerate() {
+        let (left, right) = if current_index % 2 == 0 {
+            *subtree = node;
+            visit(level, &node, &zero_bytes[level]);
+            (node, zero_bytes[level])
+        } else {
+            visit(level, &node, subtree);
+            (*subtree, node)
+        };
+        node = H::hashv(&[&left, &right]).expect("tree nodes are field elements");
+        current_index /= 2;
     }
 
-    fn append_transfer_with_proof(
-        &mut self,
-        destination: [u8; 32],
-        amount: u64,
-        proof: &mut BoundedVec<[u8; 32]>,
-    ) -> Result<(usize, usize), ConcurrentMerkleTreeError> {
-        let leaf = build_leaf(destination, amount);
-        self.append_with_proof(&leaf, proof)
+    Ok(node)
+}
+
+/// Nodes touched by appending a leaf
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AppendPath<const HEIGHT: usize> {
+    pub index: u64,
+    /// Node on the path from the leaf at each level, with empty subtrees to its right
+    pub nodes: [[u8; 32]; HEIGHT],
+    /// Sibling of each node in `nodes`
+    pub siblings: [[u8; 32]; HEIGHT],
+}
+
+/// Inclusion proof for one leaf, kept current as further leaves are appended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Witness<const HEIGHT: usize> {
+    pub index: u64,
+    pub siblings: [[u8; 32]; HEIGHT],
+}
+
+impl<const HEIGHT: usize> Witness<HEIGHT> {
+    /// Start tracking the leaf that was just appended along `path`
+    pub fn new(path: &AppendPath<HEIGHT>) -> Self {
+        Self {
+            index: path.index,
+            siblings: path.siblings,
+        }
     }
 
-    fn validate_transfer_proof(
-        &self,
-        destination: [u8; 32],
-        amount: u64,
-        index: usize,
-        proof: &BoundedVec<[u8; 32]>,
-    ) -> Result<(), ConcurrentMerkleTreeError> {
-        let leaf = build_leaf(destination, amount);
-        self.validate_proof(&leaf, index, proof)
-    }
-
-    fn proof_indices(&self, index: usize) -> [u8; HEIGHT] {
-        let mut indices = [0u8; HEIGHT];
-        let mut idx = index;
-        for i in 0..HEIGHT {
-            indices[i] = (idx & 1) as u8;
-            idx >>= 1;
+    /// Account for a leaf appended after this one.
+    /// Only the sibling subtree the new leaf falls into changes
+    pub fn update(&mut self, path: &AppendPath<HEIGHT>) {
+        for level in 0..HEIGHT {
+            if path.index >> level == (self.index >> level) ^ 1 {
+                self.siblings[level] = path.nodes[level];
+            }
         }
-        indices
+    }
+
+    /// Position of the node at each level relative to its sibling, 0 for left and 1 for right
+    pub fn path_indices(&self) -> [u8; HEIGHT] {
+        core::array::from_fn(|level| ((self.index >> level) & 1) as u8)
+    }
+
+    /// Root of the tree this witness proves `leaf` is in
+    pub fn root<H: Hasher>(&self, leaf: [u8; 32]) -> [u8; 32] {
+        let mut node = leaf;
+        for (level, sibling) in self.siblings.iter().enumerate() {
+            node = if (self.index >> level) & 1 == 0 {
+                H::hashv(&[&node, sibling])
+            } else {
+                H::hashv(&[sibling, &node])
+            }
+            .expect("tree nodes are field elements");
+        }
+        node
+    }
+}
+
+/// A transfer tree held in memory, as the hook's account would hold it after the same appends
+#[derive(Debug, Clone)]
+pub struct TransferTree<const HEIGHT: usize> {
+    frontier: [[u8; 32]; HEIGHT],
+    next_index: u64,
+    root: [u8; 32],
+}
+
+impl<const HEIGHT: usize> Default for TransferTree<HEIGHT> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-fn build_leaf(destination: [u8; 32], amount: u64) -> [u8; 32] {
-    let destination_bytes = pack_bytes(&destination)
-        .iter()
-        .map(|f| f.into_bigint().to_bytes_be())
-        .collect::<Vec<Vec<u8>>>();
-
-    Poseidon::hashv(&[
-        &destination_bytes[0],
-        &destination_bytes[1],
-        &amount.to_be_bytes(),
-    ])
-    .unwrap()
+impl<const HEIGHT: usize> TransferTree<HEIGHT> {
+    pub fn new() -> Self {
+        let mut frontier = [[0u8; 32]; HEIGHT];
+        let root = init::<Poseidon>(&mut frontier);
+        Self {
+            frontier,
+            next_index: 0,
+            root,
+        }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.root
+    }
+
+    /// Number of leaves in the tree
+    pub fn next_index(&self) -> u64 {
+        self.next_index
+    }
+
+    /// Most recently filled node on each level, starting from the leaves
+    pub fn frontier(&self) -> &[[u8; 32]; HEIGHT] {
+        &self.frontier
+    }
+
+    pub fn append(&mut self, leaf: [u8; 32]) -> Result<AppendPath<HEIGHT>, TreeFull> {
+        let mut path = AppendPath {
+            index: self.next_index,
+            nodes: [[0u8; 32]; HEIGHT],
+            siblings: [[0u8; 32]; HEIGHT],
+        };
+        self.root = append_visiting::<Poseidon>(
+            &mut self.frontier,
+            self.next_index,
+            leaf,
+            |level, node, sibling| {
+                path.nodes[level] = *node;
+                path.siblings[level] = *sibling;
+            },
+        )?;
+        self.next_index += 1;
+        Ok(path)
+    }
+
+    pub fn append_transfer(
+        &mut self,
+        destination: [u8; 32],
+        amount: u64,
+    ) -> Result<AppendPath<HEIGHT>, TreeFull> {
+        self.append(transfer_leaf(&destination, amount))
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use proptest::prelude::*;
+    use utils::{fr_to_be_32, pack_bytes, u64_to_be_32};
+
     use super::*;
-    use num_bigint::BigUint;
+
+    /// Root of a tree holding `leaves`, computed a level at a time from the full list of leaves
+    fn naive_root(leaves: &[[u8; 32]], height: usize) -> [u8; 32] {
+        let zero_bytes = Poseidon::zero_bytes();
+        let mut level: Vec<[u8; 32]> = leaves.to_vec();
+        for zero in zero_bytes.iter().take(height) {
+            level = level
+                .chunks(2)
+                .map(|pair| Poseidon::hashv(&[&pair[0], pair.get(1).unwrap_or(zero)]).unwrap())
+                .collect();
+        }
+        level.first().copied().unwrap_or(zero_bytes[height])
+    }
+
+    fn leaf(seed: u64) -> [u8; 32] {
+        u64_to_be_32(seed)
+    }
+
+    #[test]
+    fn test_transfer_leaf_matches_packed_fields() {
+        let destination: [u8; 32] = core::array::from_fn(|i| (i as u8).wrapping_mul(97) ^ 0xa5);
+        let packed: Vec<[u8; 32]> = pack_bytes(&destination).iter().map(fr_to_be_32).collect();
+        let expected =
+            Poseidon::hashv(&[&packed[0], &packed[1], &u64_to_be_32(1_000_000)]).unwrap();
+        assert_eq!(transfer_leaf(&destination, 1_000_000), expected);
+    }
+
+    #[test]
+    fn test_empty_root() {
+        assert_eq!(TransferTree::<26>::new().root(), Poseidon::zero_bytes()[26]);
+        assert_eq!(TransferTree::<26>::new().root(), naive_root(&[], 26));
+    }
 
     #[test]
-    fn test_append_transfer() {
-        let mut tree = TransferTree::<26>::new_empty();
-
-        let old_root = tree.root();
-        println!("Old root: {:?}", old_root);
-
-        let mut proof = BoundedVec::with_capacity(26);
-        let leaf_index = tree.next_index();
-        let (mut changelog_index, _sequence_number) = tree
-            .append_transfer_with_proof(
-                [
-                    24, 190, 156, 60, 238, 7, 189, 235, 169, 222, 217, 179, 62, 139, 220, 233, 237,
-                    241, 21, 36, 93, 52, 137, 195, 1, 43, 97, 163, 221, 73, 181, 190,
-                ],
-                2,
-                &mut proof,
-            )
-            .unwrap();
-
-        // append a new transfer and update prior proof
-        tree.append_transfer(
-            [
-                24, 190, 156, 60, 238, 7, 189, 235, 169, 222, 217, 179, 62, 139, 220, 233, 237,
-                241, 21, 36, 93, 52, 137, 195, 1, 43, 97, 163, 221, 73, 181, 190,
-            ],
-            10,
-        )
-        .unwrap();
-        if changelog_index != tree.changelog_index() {
-            tree.update_proof_from_changelog(changelog_index, leaf_index, &mut proof)
-                .unwrap();
-            changelog_index = tree.changelog_index();
+    fn test_full_tree() {
+        let mut tree = TransferTree::<3>::new();
+        for i in 0..8 {
+            tree.append(leaf(i)).unwrap();
         }
+        assert_eq!(tree.append(leaf(8)), Err(TreeFull));
+        assert_eq!(tree.next_index(), 8);
+    }
+
+    #[test]
+    fn test_borrowed_frontier_matches_tree() {
+        let mut tree = TransferTree::<26>::new();
+        let mut frontier = [[0u8; 32]; 26];
+        let mut root = init::<Poseidon>(&mut frontier);
+        for i in 0..5 {
+            tree.append(leaf(i)).unwrap();
+            root = append::<Poseidon>(&mut frontier, i, leaf(i)).unwrap();
+        }
+        assert_eq!(root, tree.root());
+        assert_eq!(root, naive_root(&(0..5).map(leaf).collect::<Vec<_>>(), 26));
+    }
 
-        let new_root = tree.root();
-        println!("New root: {:?}", new_root);
-        assert_ne!(old_root, new_root);
-
-        println!(
-            "Proof: {:?}",
-            proof
-                .iter()
-                .map(|b| BigUint::from_bytes_be(b).to_string())
-                .collect::<Vec<_>>()
-        );
-        println!("Proof indices: {:?}", tree.proof_indices(0));
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn prop_root_matches_naive(seeds in prop::collection::vec(any::<u64>(), 0..24)) {
+            let leaves: Vec<[u8; 32]> = seeds.iter().copied().map(leaf).collect();
+            let mut tree = TransferTree::<8>::new();
+            for (i, l) in leaves.iter().enumerate() {
+                tree.append(*l).unwrap();
+                prop_assert_eq!(tree.root(), naive_root(&leaves[..=i], 8));
+            }
+        }
+
+        #[test]
+        fn prop_witness_tracks_root(
+            seeds in prop::collection::vec(any::<u64>(), 1..24),
+            tracked in any::<prop::sample::Index>(),
+        ) {
+            let tracked = tracked.index(seeds.len());
+            let mut tree = TransferTree::<8>::new();
+            let mut witness = None;
+            for (i, seed) in seeds.iter().enumerate() {
+                let path = tree.append(leaf(*seed)).unwrap();
+                if i == tracked {
+                    witness = Some(Witness::new(&path));
+                } else if let Some(w) = &mut witness {
+                    w.update(&path);
+                }
+                if let Some(w) = &witness {
+                    prop_assert_eq!(w.root::<Poseidon>(leaf(seeds[tracked])), tree.root());
+                }
+            }
+        }
     }
 }
@@ -1,6 +1,10 @@
 use ark_bn254::Fr as NoirField;
 use ark_ff::{BigInt, BigInteger, PrimeField};
 
+mod secret;
+
+pub use secret::{InvalidSecret, SpendSecret};
+
 #[inline]
 fn fr_from_31_le_bytes(chunk: &[u8; 31]) -> NoirField {
     // 31 bytes = 248 bits, fits comfortably below NoirField modulus, so no reduction issues.
@@ -54,3 +58,30 @@ pub fn u64_to_be_32(x: u64) -> [u8; 32] {
     out[24..].copy_from_slice(&x.to_be_bytes());
     out
 }
+
+/// Public inputs of the condenser circuit, `recipient: [Field; 2], amount: Field, merkle_root: Field`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CondenserPublicInputs {
+    pub recipient: [u8; 32],
+    pub amount: u64,
+    pub merkle_root: [u8; 32],
+}
+
+impl CondenserPublicInputs {
+    /// Decode from the big-endian public witness entries.
+    /// Only the low 8 bytes of the amount are read, this is the decoding the condenser program relies on
+    pub fn from_entries(entries: &[[u8; 32]]) -> Option<Self> {
+        if entries.len() != 4 {
+            return None;
+        }
+        let recipient = unpack_bytes_from_le_fields(&entries[0..2], 32)
+            .try_into()
+            .ok()?;
+        let amount = u64::from_be_bytes(entries[2][24..].try_into().ok()?);
+        Some(Self {
+            recipient,
+            amount,
+            merkle_root: entries[3],
+        })
+    }
+}
@@ -0,0 +1,85 @@
+use std::fmt;
+use std::str::FromStr;
+
+use ark_bn254::Fr as NoirField;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// The secret that, together with the recipient, derives a vapor address and authorizes condensing its deposits.
+/// It is wiped from memory when dropped and never printed by `Debug` or `Display`.
+/// Use [`SpendSecret::expose_secret`] where the value itself is needed.
+#[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct SpendSecret(NoirField);
+
+impl SpendSecret {
+    pub fn new(secret: NoirField) -> Self {
+        Self(secret)
+    }
+
+    pub fn expose_secret(&self) -> &NoirField {
+        &self.0
+    }
+
+    /// Decimal encoding as stored in the wallet and written to Prover.toml
+    pub fn to_decimal_string(&self) -> Zeroizing<String> {
+        Zeroizing::new(self.0.to_string())
+    }
+}
+
+impl FromStr for SpendSecret {
+    type Err = InvalidSecret;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NoirField::from_str(s).map(Self).map_err(|_| InvalidSecret)
+    }
+}
+
+impl fmt::Debug for SpendSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SpendSecret(<redacted>)")
+    }
+}
+
+impl fmt::Display for SpendSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidSecret;
+
+impl fmt::Display for InvalidSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid spend secret")
+    }
+}
+
+impl std::error::Error for InvalidSecret {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::UniformRand;
+
+    #[test]
+    fn test_secret_is_redacted() {
+        let secret = SpendSecret::new(NoirField::from(1234567u64));
+        assert_eq!(format!("{:?}", secret), "SpendSecret(<redacted>)");
+        assert_eq!(format!("{}", secret), "<redacted>");
+        assert_eq!(secret.to_decimal_string().as_str(), "1234567");
+    }
+
+    #[test]
+    fn test_secret_round_trips_through_decimal() {
+        let secret = SpendSecret::new(NoirField::rand(&mut rand::thread_rng()));
+        let parsed: SpendSecret = secret.to_decimal_string().parse().unwrap();
+        assert_eq!(parsed, secret);
+    }
+
+    #[test]
+    fn test_zeroize() {
+        let mut secret = SpendSecret::new(NoirField::from(42u64));
+        secret.zeroize();
+        assert_eq!(*secret.expose_secret(), NoirField::from(0u64));
+    }
+}
@@ -0,0 +1,331 @@
+use std::fmt;
+
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use spl_token_2022::extension::{ExtensionType, metadata_pointer, transfer_fee, transfer_hook};
+use spl_token_2022::instruction::AuthorityType;
+use spl_token_2022::state::Mint;
+use spl_token_metadata_interface::state::TokenMetadata;
+use vapor_client::registry::RegistryEntry;
+use vapor_client::{Circuit, TOKEN_2022_PROGRAM_ID, TRANSFER_HOOK_PROGRAM_ID, instructions, pda};
+
+#[derive(Clone, clap::Args)]
+pub(crate) struct DeployArgs {
+    #[clap(long, env = "TOKEN_NAME")]
+    name: String,
+
+    #[clap(long, env = "TOKEN_SYMBOL")]
+    symbol: String,
+
+    #[clap(long, env = "TOKEN_URI")]
+    uri: String,
+
+    /// Initial supply in whole tokens
+    #[clap(long, env = "TOKEN_SUPPLY")]
+    supply: u64,
+
+    #[clap(long, env = "TOKEN_DECIMALS", default_value = "9")]
+    decimals: u8,
+
+    /// Account that receives the entire initial supply. Must be a regular (on-curve) address
+    #[clap(long, env = "MINT_RECIPIENT")]
+    recipient: String,
+
+    /// Transfer fee in basis points. Deposits record the amount received net of the fee
+    #[clap(long, env = "TOKEN_TRANSFER_FEE_BPS", requires = "max_transfer_fee")]
+    transfer_fee_bps: Option<u16>,
+
+    /// Most fee charged on a single transfer, in base units
+    #[clap(long, env = "TOKEN_MAX_TRANSFER_FEE", requires = "transfer_fee_bps")]
+    max_transfer_fee: Option<u64>,
+
+    /// Verifying key registry entry written by `cargo xtask emit-vk` to register for the mint, e.g.
+    /// circuits/condenser/target/condenser.v1.json. Repeat for the disclosure key.
+    /// Nothing can be condensed until a condenser key is registered
+    #[clap(long = "vk-entry")]
+    vk_entries: Vec<String>,
+}
+
+/// Addresses of a deployed vapor token
+pub(crate) struct DeployConfig {
+    mint: Pubkey,
+    mint_authority: Pubkey,
+    /// Keeps the transfer hook, metadata and transfer fee authorities of the mint.
+    /// As the transfer hook authority it administers the transfer tree and the condenser verifying keys
+    admin: Pubkey,
+    transfer_fee: bool,
+    recipient: Pubkey,
+    recipient_ata: Pubkey,
+    extra_account_metas: Pubkey,
+    merkle_tree: Pubkey,
+    /// Circuit and version of each verifying key registered for the mint
+    verifying_keys: Vec<(Circuit, u32)>,
+}
+
+impl fmt::Display for DeployConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Mint (token address): {}", self.mint)?;
+        writeln!(f, "Mint authority PDA: {}", self.mint_authority)?;
+        writeln!(f, "Transfer hook authority: {}", self.admin)?;
+        writeln!(f, "Metadata pointer and update authority: {}", self.admin)?;
+        if self.transfer_fee {
+            writeln!(
+                f,
+                "Transfer fee config and withdraw withheld authority: {}",
+                self.admin
+            )?;
+        }
+        writeln!(f, "Recipient owner: {}", self.recipient)?;
+        writeln!(f, "Recipient ATA: {}", self.recipient_ata)?;
+        writeln!(f, "Transfer hook program: {}", TRANSFER_HOOK_PROGRAM_ID)?;
+        writeln!(f, "ExtraAccountMetaList: {}", self.extra_account_metas)?;
+        writeln!(f, "MerkleTree: {}", self.merkle_tree)?;
+        for (circuit, version) in &self.verifying_keys {
+            writeln!(f, "{:?} verifying key: version {}", circuit, version)?;
+        }
+        if !self
+            .verifying_keys
+            .iter()
+            .any(|(circuit, _)| *circuit == Circuit::Condenser)
+        {
+            writeln!(f)?;
+            writeln!(
+                f,
+                "# Register the verifying keys of your trusted setup before condensing"
+            )?;
+            writeln!(f, "just emit_vk 1")?;
+            writeln!(f, "just upload_vk {} 1", self.mint)?;
+            writeln!(f, "just upload_disclosure_vk {} 1", self.mint)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "# Wallet configuration")?;
+        write!(f, "MINT={}", self.mint)
+    }
+}
+
+/// Create and initialize a new vapor token mint, in the same steps as `anchor/migrations/deploy.ts`.
+/// The mint is created in the same transaction as its transfer tree so there is no window in which
+/// it exists without one. The payer keeps every authority of the mint except minting
+pub(crate) fn deploy(
+    rpc_url: &str,
+    payer: &Keypair,
+    args: DeployArgs,
+) -> anyhow::Result<DeployConfig> {
+    let client = RpcClient::new(rpc_url.to_string());
+
+    let recipient: Pubkey = args.recipient.parse()?;
+    if CompressedEdwardsY(recipient.to_bytes())
+        .decompress()
+        .is_none()
+    {
+        anyhow::bail!("MINT_RECIPIENT must be an on-curve address");
+    }
+    let supply = 10u64
+        .checked_pow(args.decimals as u32)
+        .and_then(|unit| args.supply.checked_mul(unit))
+        .ok_or_else(|| {
+            anyhow::anyhow!("TOKEN_SUPPLY is too large for {} decimals", args.decimals)
+        })?;
+
+    // Read before deploying so a bad entry doesn't leave a mint behind without its keys
+    let vk_entries = args
+        .vk_entries
+        .iter()
+        .map(|path| -> anyhow::Result<RegistryEntry> {
+            let entry: RegistryEntry = serde_json::from_slice(
+                &std::fs::read(path)
+                    .map_err(|e| anyhow::anyhow!("failed to read {}: {}", path, e))?,
+            )?;
+            entry.circuit()?;
+            Ok(entry)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mint = Keypair::new();
+    let mut config = DeployConfig {
+        mint: mint.pubkey(),
+        mint_authority: pda::mint_authority(&mint.pubkey()).0,
+        admin: payer.pubkey(),
+        transfer_fee: args.transfer_fee_bps.is_some(),
+        recipient,
+        recipient_ata: pda::associated_token_account(&recipient, &mint.pubkey()),
+        extra_account_metas: pda::extra_account_metas(&mint.pubkey()).0,
+        merkle_tree: pda::merkle_tree(&mint.pubkey()).0,
+        verifying_keys: Vec::new(),
+    };
+
+    let send = |ixs: &[Instruction], signers: &[&Keypair], step: &str| -> anyhow::Result<()> {
+        let mut all_signers = vec![payer];
+        all_signers.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            ixs,
+            Some(&payer.pubkey()),
+            &all_signers,
+            client.get_latest_blockhash()?,
+        );
+        let sig = client.send_and_confirm_transaction(&tx)?;
+        println!("{}: {}", step, sig);
+        Ok(())
+    };
+
+    // The mint account is created with room for the fixed size extensions.
+    // Token metadata is variable length and reallocated into, so only its rent is paid up front
+    let mut extensions = vec![ExtensionType::MetadataPointer, ExtensionType::TransferHook];
+    if args.transfer_fee_bps.is_some() {
+        extensions.push(ExtensionType::TransferFeeConfig);
+    }
+    let mint_len = ExtensionType::try_calculate_account_len::<Mint>(&extensions)?;
+    let metadata_len = TokenMetadata {
+        name: args.name.clone(),
+        symbol: args.symbol.clone(),
+        uri: args.uri.clone(),
+        ..Default::default()
+    }
+    .tlv_size_of()?;
+    let lamports = client.get_minimum_balance_for_rent_exemption(mint_len + metadata_len)?;
+
+    let mint_key = v2(&config.mint);
+    let payer_key = v2(&payer.pubkey());
+    let token_program = v2(&TOKEN_2022_PROGRAM_ID);
+
+    let mut create_mint = vec![
+        solana_system_interface::instruction::create_account(
+            &payer.pubkey(),
+            &config.mint,
+            lamports,
+            mint_len as u64,
+            &TOKEN_2022_PROGRAM_ID,
+        ),
+        v3(metadata_pointer::instruction::initialize(
+            &token_program,
+            &mint_key,
+            Some(payer_key),
+            Some(mint_key),
+        )?),
+        v3(transfer_hook::instruction::initialize(
+            &token_program,
+            &mint_key,
+            Some(payer_key),
+            Some(v2(&TRANSFER_HOOK_PROGRAM_ID)),
+        )?),
+    ];
+    if let (Some(basis_points), Some(maximum_fee)) = (args.transfer_fee_bps, args.max_transfer_fee)
+    {
+        create_mint.push(v3(
+            transfer_fee::instruction::initialize_transfer_fee_config(
+                &token_program,
+                &mint_key,
+                Some(&payer_key),
+                Some(&payer_key),
+                basis_points,
+                maximum_fee,
+            )?,
+        ));
+    }
+    create_mint.extend([
+        v3(spl_token_2022::instruction::initialize_mint2(
+            &token_program,
+            &mint_key,
+            &payer_key,
+            None,
+            args.decimals,
+        )?),
+        instructions::initialize(&config.mint, &payer.pubkey()),
+        instructions::initialize_extra_account_meta_list(&config.mint, &payer.pubkey()),
+    ]);
+
+    println!("Creating mint {} and its transfer tree...", config.mint);
+    send(&create_mint, &[&mint], "Created mint")?;
+
+    // Written into the space paid for above. It is left out of the first transaction to keep it within the size limit
+    println!("Writing token metadata...");
+    send(
+        &[v3(spl_token_metadata_interface::instruction::initialize(
+            &token_program,
+            &mint_key,
+            &payer_key,
+            &mint_key,
+            &payer_key,
+            args.name,
+            args.symbol,
+            args.uri,
+        ))],
+        &[],
+        "Wrote metadata",
+    )?;
+
+    println!("Minting initial supply of {}...", supply);
+    send(
+        &[
+            instructions::create_associated_token_account_idempotent(
+                &payer.pubkey(),
+                &recipient,
+                &config.mint,
+            ),
+            v3(spl_token_2022::instruction::mint_to(
+                &token_program,
+                &mint_key,
+                &v2(&config.recipient_ata),
+                &payer_key,
+                &[],
+                supply,
+            )?),
+        ],
+        &[],
+        "Minted initial supply",
+    )?;
+
+    println!("Handing mint authority to the condenser...");
+    send(
+        &[v3(spl_token_2022::instruction::set_authority(
+            &token_program,
+            &mint_key,
+            Some(&v2(&config.mint_authority)),
+            AuthorityType::MintTokens,
+            &payer_key,
+            &[],
+        )?)],
+        &[],
+        "Set mint authority",
+    )?;
+
+    // As with `cargo xtask upload-vk`, a key is registered over several transactions
+    for entry in &vk_entries {
+        let circuit = entry.circuit()?;
+        println!(
+            "Registering {:?} verifying key version {}...",
+            circuit, entry.version
+        );
+        for ix in entry.instructions(&config.mint, &payer.pubkey())? {
+            send(&[ix], &[], "Wrote verifying key")?;
+        }
+        config.verifying_keys.push((circuit, entry.version));
+    }
+
+    Ok(config)
+}
+
+/// spl-token-2022 still builds instructions with the solana 2 types
+fn v2(pubkey: &Pubkey) -> solana_program::pubkey::Pubkey {
+    solana_program::pubkey::Pubkey::new_from_array(pubkey.to_bytes())
+}
+
+fn v3(ix: solana_program::instruction::Instruction) -> Instruction {
+    Instruction {
+        program_id: Pubkey::new_from_array(ix.program_id.to_bytes()),
+        accounts: ix
+            .accounts
+            .into_iter()
+            .map(|meta| AccountMeta {
+                pubkey: Pubkey::new_from_array(meta.pubkey.to_bytes()),
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+            .collect(),
+        data: ix.data,
+    }
+}
@@ -0,0 +1,52 @@
+use clap::Parser;
+use solana_sdk::signature::read_keypair_file;
+
+mod deploy;
+mod policy;
+
+#[derive(clap::Parser)]
+#[clap(version, about = "Admin tool for deploying and managing Solana Vapor Tokens", long_about = None)]
+struct Args {
+    #[clap(subcommand)]
+    cmd: Command,
+
+    #[clap(long, env = "SOL_RPC", default_value = "https://api.devnet.solana.com")]
+    rpc_url: String,
+
+    /// Keypair that pays for and initially controls the deployment
+    #[clap(long, default_value = "~/.config/solana/id.json")]
+    keypair: String,
+}
+
+#[derive(Clone, clap::Subcommand)]
+enum Command {
+    /// Deploy a new vapor token
+    /// This creates a Token-2022 mint using the transfer hook, initializes the transfer tree,
+    /// mints the initial supply to the mint recipient and hands the mint authority to the condenser
+    Deploy(deploy::DeployArgs),
+    /// Set which transfers the transfer hook appends to the tree.
+    /// Skipped transfers still succeed but can never be condensed
+    AdmissionPolicy(policy::AdmissionPolicyArgs),
+}
+
+fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+    let args = Args::parse();
+
+    let keypair = shellexpand::tilde(&args.keypair).to_string();
+    let payer = read_keypair_file(&keypair)
+        .map_err(|e| anyhow::anyhow!("failed to read keypair {}: {}", keypair, e))?;
+
+    match args.cmd {
+        Command::Deploy(deploy_args) => {
+            let config = deploy::deploy(&args.rpc_url, &payer, deploy_args)?;
+            println!("{}", config);
+        }
+        Command::AdmissionPolicy(policy_args) => {
+            let policy = policy::set_admission_policy(&args.rpc_url, &payer, policy_args)?;
+            println!("{:?}", policy);
+        }
+    };
+
+    Ok(())
+}
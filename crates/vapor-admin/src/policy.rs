@@ -0,0 +1,54 @@
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use vapor_client::{AdmissionPolicy, accounts, instructions, pda};
+
+#[derive(Clone, clap::Args)]
+pub(crate) struct AdmissionPolicyArgs {
+    #[clap(long, env = "MINT")]
+    mint: Pubkey,
+
+    /// Skip transfers to owners that are not on the ed25519 curve, such as PDAs
+    #[clap(long)]
+    skip_off_curve_owners: bool,
+
+    /// Skip transfers of zero tokens
+    #[clap(long)]
+    skip_zero_amounts: bool,
+
+    /// Skip transfers of less than this many base units
+    #[clap(long, default_value = "0")]
+    min_amount: u64,
+}
+
+/// Replace the admission policy of a mint's transfer tree. The keypair must be the transfer hook authority of the mint
+pub(crate) fn set_admission_policy(
+    rpc_url: &str,
+    authority: &Keypair,
+    args: AdmissionPolicyArgs,
+) -> anyhow::Result<AdmissionPolicy> {
+    let client = RpcClient::new(rpc_url.to_string());
+
+    let policy = AdmissionPolicy {
+        skip_off_curve_owners: args.skip_off_curve_owners,
+        skip_zero_amounts: args.skip_zero_amounts,
+        min_amount: args.min_amount,
+    };
+    let tx = Transaction::new_signed_with_payer(
+        &[instructions::set_admission_policy(
+            &args.mint,
+            &authority.pubkey(),
+            policy,
+        )],
+        Some(&authority.pubkey()),
+        &[authority],
+        client.get_latest_blockhash()?,
+    );
+    let sig = client.send_and_confirm_transaction(&tx)?;
+    println!("Set admission policy: {}", sig);
+
+    let tree =
+        accounts::decode_merkle_tree(&client.get_account_data(&pda::merkle_tree(&args.mint).0)?)?;
+    Ok(tree.admission_policy())
+}
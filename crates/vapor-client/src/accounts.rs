@@ -0,0 +1,120 @@
+//! Decoders for the accounts owned by the vapor token programs
+use anchor_lang::{AccountDeserialize, Discriminator};
+use solana_sdk::pubkey::Pubkey;
+
+pub use vaportoken_condenser::{
+    WithdrawnTracker,
+    registry::{Circuit, VerifyingKeyAccount},
+};
+pub use vaportoken_transfer_hook::{AdmissionPolicy, MerkleTree, MerkleTreeAccount};
+
+use crate::ClientError;
+
+/// Decode the zero-copy transfer tree account of a mint, see [`crate::pda::merkle_tree`]
+pub fn decode_merkle_tree(data: &[u8]) -> Result<MerkleTreeAccount, ClientError> {
+    let disc_len = MerkleTreeAccount::DISCRIMINATOR.len();
+    let len = disc_len + std::mem::size_of::<MerkleTreeAccount>();
+    if data.len() < len {
+        return Err(ClientError::AccountTooSmall("MerkleTreeAccount"));
+    }
+    if data[..disc_len] != *MerkleTreeAccount::DISCRIMINATOR {
+        return Err(ClientError::InvalidDiscriminator("MerkleTreeAccount"));
+    }
+    Ok(bytemuck::pod_read_unaligned(&data[disc_len..len]))
+}
+
+/// Whether the hook appends a leaf for a transfer of `amount` to a token account owned by `to`,
+/// under the admission policy of a decoded tree account. On transfer fee mints `amount` is net of the fee
+pub fn admits(tree: &MerkleTreeAccount, to: &Pubkey, amount: u64) -> bool {
+    tree.admits(
+        &anchor_lang::prelude::Pubkey::new_from_array(to.to_bytes()),
+        amount,
+    )
+}
+
+/// Amount of a transfer of `amount` in `epoch` that reaches the destination, net of any transfer fee,
+/// given the data of a Token-2022 mint. This is the amount the hook records and checks for admission
+pub fn net_amount(mint_data: &[u8], epoch: u64, amount: u64) -> Result<u64, ClientError> {
+    Ok(vaportoken_transfer_hook::net_amount(
+        mint_data, epoch, amount,
+    )?)
+}
+
+/// Decode the amount withdrawn by a recipient, see [`crate::pda::withdrawn`]
+pub fn decode_withdrawn_tracker(data: &[u8]) -> Result<WithdrawnTracker, ClientError> {
+    Ok(WithdrawnTracker::try_deserialize(&mut &data[..])?)
+}
+
+/// Decode a registered verifying key, see [`crate::pda::verifying_key`]
+pub fn decode_verifying_key(data: &[u8]) -> Result<VerifyingKeyAccount, ClientError> {
+    Ok(VerifyingKeyAccount::try_deserialize(&mut &data[..])?)
+}
+
+/// Read the decimals of a Token-2022 mint from its account data
+pub fn decode_mint_decimals(data: &[u8]) -> Result<u8, ClientError> {
+    // Mint layout: mint_authority (36) | supply (8) | decimals (1) | ...
+    const DECIMALS_OFFSET: usize = 44;
+    data.get(DECIMALS_OFFSET)
+        .copied()
+        .ok_or(ClientError::AccountTooSmall("Mint"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AccountSerialize;
+
+    #[test]
+    fn test_decode_withdrawn_tracker() {
+        let mut data = Vec::new();
+        WithdrawnTracker {
+            total_withdrawn: 1234,
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+
+        assert_eq!(
+            decode_withdrawn_tracker(&data).unwrap().total_withdrawn,
+            1234
+        );
+        assert!(decode_withdrawn_tracker(&data[8..]).is_err());
+    }
+
+    #[test]
+    fn test_decode_merkle_tree() {
+        let mut tree: MerkleTreeAccount = bytemuck::Zeroable::zeroed();
+        tree.next_index = 7;
+        tree.root = [9u8; 32];
+
+        let mut data = MerkleTreeAccount::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(&tree));
+
+        let decoded = decode_merkle_tree(&data).unwrap();
+        assert_eq!(decoded.next_index, 7);
+        assert_eq!(decoded.root, [9u8; 32]);
+
+        data[0] ^= 1;
+        assert!(matches!(
+            decode_merkle_tree(&data),
+            Err(ClientError::InvalidDiscriminator(_))
+        ));
+        assert!(decode_merkle_tree(&data[..16]).is_err());
+    }
+
+    #[test]
+    fn test_decode_legacy_merkle_tree() {
+        // Trees created before the admission policy hold the unused 1000 SOL deposit limit where
+        // the minimum amount is now kept, with every admission flag in zeroed padding
+        let mut tree: MerkleTreeAccount = bytemuck::Zeroable::zeroed();
+        tree.next_index = 3;
+        tree.min_deposit_amount = 1_000_000_000_000;
+
+        let mut data = MerkleTreeAccount::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(&tree));
+
+        let decoded = decode_merkle_tree(&data).unwrap();
+        assert_eq!(decoded.next_index, 3);
+        assert_eq!(decoded.admission_policy(), AdmissionPolicy::default());
+        assert!(admits(&decoded, &Pubkey::new_unique(), 1));
+    }
+}
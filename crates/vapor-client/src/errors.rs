@@ -0,0 +1,128 @@
+//! Decoding of custom program errors returned by the condenser and transfer hook programs
+use std::fmt;
+
+use solana_sdk::pubkey::Pubkey;
+use vaportoken_condenser::ErrorCode as CondenserErrorCode;
+use vaportoken_transfer_hook::ErrorCode as TransferHookErrorCode;
+
+use crate::{CONDENSER_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TRANSFER_HOOK_PROGRAM_ID};
+
+const CONDENSER_ERRORS: [CondenserErrorCode; 12] = [
+    CondenserErrorCode::BadAmount,
+    CondenserErrorCode::InvalidProof,
+    CondenserErrorCode::InvalidRecipient,
+    CondenserErrorCode::RecipientMismatch,
+    CondenserErrorCode::MerkleRootNotInHistory,
+    CondenserErrorCode::ArithmeticOverflow,
+    CondenserErrorCode::DestinationNotOwnedByRecipient,
+    CondenserErrorCode::Unauthorized,
+    CondenserErrorCode::TooManyCommitments,
+    CondenserErrorCode::VerifyingKeyOverflow,
+    CondenserErrorCode::VerifyingKeyIncomplete,
+    CondenserErrorCode::VerifyingKeyRetired,
+];
+
+const TRANSFER_HOOK_ERRORS: [TransferHookErrorCode; 4] = [
+    TransferHookErrorCode::ArithmeticOverflow,
+    TransferHookErrorCode::MerkleTreeFull,
+    TransferHookErrorCode::IsNotCurrentlyTransferring,
+    TransferHookErrorCode::Unauthorized,
+];
+
+/// A custom error raised by one of the vapor token programs
+#[derive(Clone, Copy, Debug)]
+pub enum ProgramError {
+    Condenser(CondenserErrorCode),
+    TransferHook(TransferHookErrorCode),
+}
+
+impl ProgramError {
+    /// Decode a `Custom` instruction error code raised by `program_id`, see [`failing_program`].
+    /// When only the program of the top level instruction is known, the transfer hook's errors are looked up
+    /// on Token-2022 instructions as the hook is only ever reached through Token-2022.
+    pub fn decode(program_id: &Pubkey, code: u32) -> Option<Self> {
+        if *program_id == CONDENSER_PROGRAM_ID {
+            CONDENSER_ERRORS
+                .into_iter()
+                .find(|e| u32::from(*e) == code)
+                .map(Self::Condenser)
+        } else if *program_id == TRANSFER_HOOK_PROGRAM_ID || *program_id == TOKEN_2022_PROGRAM_ID {
+            TRANSFER_HOOK_ERRORS
+                .into_iter()
+                .find(|e| u32::from(*e) == code)
+                .map(Self::TransferHook)
+        } else {
+            None
+        }
+    }
+}
+
+/// The program that raised the custom error of a failed transaction, found from its logs.
+/// An error raised in a CPI fails each caller in turn, so it is the first program logged as failing with it
+pub fn failing_program(logs: &[String]) -> Option<Pubkey> {
+    logs.iter().find_map(|log| {
+        let (program_id, _) = log
+            .strip_prefix("Program ")?
+            .split_once(" failed: custom program error: ")?;
+        program_id.parse().ok()
+    })
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Condenser(e) => write!(f, "condenser error {}: {}", e.name(), e),
+            Self::TransferHook(e) => write!(f, "transfer hook error {}: {}", e.name(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_condenser_error() {
+        let err = ProgramError::decode(&CONDENSER_PROGRAM_ID, 6004).unwrap();
+        assert!(matches!(
+            err,
+            ProgramError::Condenser(CondenserErrorCode::MerkleRootNotInHistory)
+        ));
+        assert_eq!(
+            err.to_string(),
+            "condenser error MerkleRootNotInHistory: Merkle root not found in recent root history"
+        );
+    }
+
+    #[test]
+    fn test_decode_hook_error_through_token_program() {
+        let err = ProgramError::decode(&TOKEN_2022_PROGRAM_ID, 6001).unwrap();
+        assert!(matches!(
+            err,
+            ProgramError::TransferHook(TransferHookErrorCode::MerkleTreeFull)
+        ));
+    }
+
+    #[test]
+    fn test_failing_program_is_innermost() {
+        let logs: Vec<String> = [
+            format!("Program {} invoke [1]", TOKEN_2022_PROGRAM_ID),
+            format!("Program {} invoke [2]", TRANSFER_HOOK_PROGRAM_ID),
+            "Program log: AnchorError occurred. Error Code: MerkleTreeFull. Error Number: 6001. Error Message: Merkle tree is full: cannot add more leaves.".to_string(),
+            format!("Program {} consumed 5000 of 190000 compute units", TRANSFER_HOOK_PROGRAM_ID),
+            format!("Program {} failed: custom program error: 0x1771", TRANSFER_HOOK_PROGRAM_ID),
+            format!("Program {} failed: custom program error: 0x1771", TOKEN_2022_PROGRAM_ID),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(failing_program(&logs), Some(TRANSFER_HOOK_PROGRAM_ID));
+        assert_eq!(failing_program(&logs[..2]), None);
+    }
+
+    #[test]
+    fn test_decode_unknown() {
+        assert!(ProgramError::decode(&CONDENSER_PROGRAM_ID, 1).is_none());
+        assert!(ProgramError::decode(&TOKEN_2022_PROGRAM_ID, 6100).is_none());
+        assert!(ProgramError::decode(&Pubkey::new_unique(), 6000).is_none());
+    }
+}
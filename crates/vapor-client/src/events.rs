@@ -0,0 +1,249 @@
+//! Decoding of the events emitted by the transfer hook
+use anchor_lang::Discriminator;
+use base64::{Engine, engine::general_purpose::STANDARD as Base64};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{MEMO_PROGRAM_ID, TRANSFER_HOOK_PROGRAM_ID};
+
+/// A transfer seen by the transfer hook
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransferEvent {
+    /// Owner of the destination token account, the address committed to in the leaf
+    pub to: Pubkey,
+    pub amount: u64,
+    /// Whether the transfer was appended to the tree. Transfers skipped by the admission policy have no leaf
+    pub appended: bool,
+}
+
+/// Decode the data of an anchor event, returning `None` if it is not a transfer hook `Transfer` event.
+/// Events emitted before the admission policy have no `appended` flag, every such transfer was appended
+pub fn decode_transfer_event(data: &[u8]) -> Option<TransferEvent> {
+    let disc = vaportoken_transfer_hook::Transfer::DISCRIMINATOR;
+    let body = data.strip_prefix(disc)?;
+    let appended = match body.len() {
+        40 => true,
+        41 => match body[40] {
+            0 => false,
+            1 => true,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(TransferEvent {
+        to: Pubkey::new_from_array(body[..32].try_into().unwrap()),
+        amount: u64::from_le_bytes(body[32..40].try_into().unwrap()),
+        appended,
+    })
+}
+
+/// What the transfer hook and the memo program logged in a transaction
+enum Logged<'a> {
+    Transfer(TransferEvent),
+    /// A log line of the memo program, which includes the memo text
+    Memo(&'a str),
+}
+
+/// Walk a transaction's log messages, keeping track of the running program.
+/// Only lines logged while the transfer hook or the memo program is the innermost running program are considered,
+/// other anchor programs can emit events with the same discriminator
+fn logged(logs: &[String]) -> Vec<Logged<'_>> {
+    let hook_id = TRANSFER_HOOK_PROGRAM_ID.to_string();
+    let memo_id = MEMO_PROGRAM_ID.to_string();
+    let mut invoked: Vec<&str> = Vec::new();
+    let mut logged = Vec::new();
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(data) = rest.strip_prefix("data: ") {
+            if invoked.last().copied() == Some(hook_id.as_str()) {
+                logged.extend(
+                    Base64
+                        .decode(data)
+                        .ok()
+                        .and_then(|data| decode_transfer_event(&data))
+                        .map(Logged::Transfer),
+                );
+            }
+            continue;
+        }
+        if rest.starts_with("log: ") {
+            if invoked.last().copied() == Some(memo_id.as_str()) {
+                logged.push(Logged::Memo(line));
+            }
+            continue;
+        }
+        let mut words = rest.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some(program), Some("invoke")) => invoked.push(program),
+            (Some(_), Some("success" | "failed:")) => {
+                invoked.pop();
+            }
+            _ => {}
+        }
+    }
+    logged
+}
+
+/// Transfer events in a transaction's log messages, in the order they were seen by the hook.
+/// Those with `appended` set are the transaction's leaves, in the order they were appended to the tree.
+pub fn transfer_events(logs: &[String]) -> Vec<TransferEvent> {
+    logged(logs)
+        .into_iter()
+        .filter_map(|logged| match logged {
+            Logged::Transfer(event) => Some(event),
+            Logged::Memo(_) => None,
+        })
+        .collect()
+}
+
+/// The leaves a transaction appended, in tree order, each with the first memo accepted by `parse_memo`
+/// that was logged after its transfer and before the next one. This is where a sender puts the memo
+/// announcing a stealth payment, so a transaction can carry several
+pub fn leaves_with_memos<T>(
+    logs: &[String],
+    parse_memo: impl Fn(&str) -> Option<T>,
+) -> Vec<(TransferEvent, Option<T>)> {
+    let mut leaves: Vec<(TransferEvent, Option<T>)> = Vec::new();
+    // A memo after a transfer skipped by the admission policy has no leaf to go with
+    let mut after_leaf = false;
+    for logged in logged(logs) {
+        match logged {
+            Logged::Transfer(event) => {
+                after_leaf = event.appended;
+                if event.appended {
+                    leaves.push((event, None));
+                }
+            }
+            Logged::Memo(line) => match leaves.last_mut() {
+                Some((_, memo)) if after_leaf && memo.is_none() => *memo = parse_memo(line),
+                _ => {}
+            },
+        }
+    }
+    leaves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_log(to: Pubkey, amount: u64, appended: bool) -> String {
+        let mut data = vaportoken_transfer_hook::Transfer::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(to.as_ref());
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.push(appended as u8);
+        format!("Program data: {}", Base64.encode(data))
+    }
+
+    #[test]
+    fn test_transfer_events() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        let hook = TRANSFER_HOOK_PROGRAM_ID;
+        let other = Pubkey::new_unique();
+        let logs = vec![
+            "Program TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb invoke [1]".to_string(),
+            "Program log: Instruction: TransferChecked".to_string(),
+            format!("Program {} invoke [2]", hook),
+            event_log(a, 10, true),
+            format!("Program {} consumed 1000 of 2000 compute units", hook),
+            format!("Program {} success", hook),
+            "Program TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb success".to_string(),
+            // The same event emitted by another program is not a transfer
+            format!("Program {} invoke [1]", other),
+            event_log(Pubkey::new_unique(), 30, true),
+            format!("Program {} success", other),
+            format!("Program {} invoke [1]", hook),
+            "Program data: AQID".to_string(),
+            event_log(b, 20, false),
+            format!("Program {} success", hook),
+        ];
+
+        assert_eq!(
+            transfer_events(&logs),
+            vec![
+                TransferEvent {
+                    to: a,
+                    amount: 10,
+                    appended: true
+                },
+                TransferEvent {
+                    to: b,
+                    amount: 20,
+                    appended: false
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_legacy_transfer_event() {
+        let to = Pubkey::new_unique();
+        let mut data = vaportoken_transfer_hook::Transfer::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(to.as_ref());
+        data.extend_from_slice(&7u64.to_le_bytes());
+
+        assert_eq!(
+            decode_transfer_event(&data),
+            Some(TransferEvent {
+                to,
+                amount: 7,
+                appended: true
+            })
+        );
+        data.push(2);
+        assert_eq!(decode_transfer_event(&data), None);
+        data.truncate(data.len() - 2);
+        assert_eq!(decode_transfer_event(&data), None);
+    }
+
+    #[test]
+    fn test_leaves_with_memos() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        let c = Pubkey::new_unique();
+        let hook = TRANSFER_HOOK_PROGRAM_ID;
+        let memo_program = MEMO_PROGRAM_ID;
+        let memo = |text: &str| {
+            vec![
+                format!("Program {} invoke [1]", memo_program),
+                format!("Program log: Memo (len {}): \"{}\"", text.len(), text),
+                format!("Program {} success", memo_program),
+            ]
+        };
+        let hooked = |to: Pubkey, appended: bool| {
+            vec![
+                format!("Program {} invoke [2]", hook),
+                event_log(to, 10, appended),
+                format!("Program {} success", hook),
+            ]
+        };
+        let logs: Vec<String> = [
+            // A memo before any transfer goes with none
+            memo("memo:0"),
+            hooked(a, true),
+            memo("hello"),
+            memo("memo:1"),
+            memo("memo:2"),
+            hooked(b, false),
+            memo("memo:3"),
+            hooked(c, true),
+            // Only the memo program's logs are memos
+            vec!["Program log: memo:4".to_string()],
+        ]
+        .concat();
+
+        let leaves = leaves_with_memos(&logs, |line| {
+            line.split_once("memo:")
+                .map(|(_, rest)| rest[..1].to_string())
+        });
+        assert_eq!(
+            leaves
+                .iter()
+                .map(|(event, memo)| (event.to, memo.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![(a, Some("1")), (c, None)]
+        );
+    }
+}
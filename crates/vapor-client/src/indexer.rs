@@ -0,0 +1,225 @@
+//! HTTP API of the transfer tree indexer and a blocking client for it.
+//!
+//! The indexer only serves public data in bulk: leaves by index range, the current frontier and the root
+//! after every leaf. Clients download whole ranges and build their proofs locally, so the indexer never
+//! learns which leaf a client is interested in.
+//!
+//! Routes, all `GET` and returning JSON:
+//! - `/leaves?start=&end=` the [`IndexedLeaf`]s in `start..end`, at most [`MAX_RANGE`] per request
+//! - `/frontier` the current [`Frontier`]
+//! - `/roots?start=&end=` the [`RootEntry`]s for tree sizes in `start..end`, at most [`MAX_RANGE`] per request
+use serde::{Deserialize, Serialize};
+
+use crate::ClientError;
+
+/// Largest range of leaves or roots served in one response
+pub const MAX_RANGE: u64 = 1000;
+
+/// A transfer in the tree
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedLeaf {
+    pub index: u64,
+    pub slot: u64,
+    /// Owner of the destination token account, base58
+    #[serde(with = "base58")]
+    pub to: [u8; 32],
+    pub amount: u64,
+    /// Ephemeral key of a stealth payment memo attached to the transfer, base58
+    #[serde(
+        default,
+        with = "optional_base58",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub ephemeral_key: Option<[u8; 32]>,
+}
+
+/// State needed to keep appending to the tree without the earlier leaves
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Frontier {
+    pub next_index: u64,
+    #[serde(with = "hex32")]
+    pub root: [u8; 32],
+    /// Most recently filled node on each level, starting from the leaves
+    pub frontier: Vec<Hash>,
+}
+
+/// Root of the tree once it held `next_index` leaves
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RootEntry {
+    pub next_index: u64,
+    #[serde(with = "hex32")]
+    pub root: [u8; 32],
+}
+
+/// A tree node, hex encoded
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Hash(#[serde(with = "hex32")] pub [u8; 32]);
+
+pub struct IndexerClient {
+    url: String,
+    agent: ureq::Agent,
+}
+
+impl IndexerClient {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            agent: ureq::Agent::new(),
+        }
+    }
+
+    /// Leaves with indices in `start..end`. Fewer are returned if the indexer has not seen them yet
+    pub fn leaves(&self, start: u64, end: u64) -> Result<Vec<IndexedLeaf>, ClientError> {
+        let mut leaves = Vec::new();
+        for (start, end) in pages(start, end) {
+            let page: Vec<IndexedLeaf> =
+                self.get(&format!("/leaves?start={}&end={}", start, end))?;
+            let complete = page.len() as u64 == end - start;
+            leaves.extend(page);
+            if !complete {
+                break;
+            }
+        }
+        Ok(leaves)
+    }
+
+    pub fn frontier(&self) -> Result<Frontier, ClientError> {
+        self.get("/frontier")
+    }
+
+    /// Roots of the tree for sizes in `start..end`
+    pub fn roots(&self, start: u64, end: u64) -> Result<Vec<RootEntry>, ClientError> {
+        let mut roots = Vec::new();
+        for (start, end) in pages(start, end) {
+            let page: Vec<RootEntry> = self.get(&format!("/roots?start={}&end={}", start, end))?;
+            let complete = page.len() as u64 == end - start;
+            roots.extend(page);
+            if !complete {
+                break;
+            }
+        }
+        Ok(roots)
+    }
+
+    fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, ClientError> {
+        self.agent
+            .get(&format!("{}{}", self.url, path))
+            .call()
+            .map_err(|e| ClientError::Indexer(e.to_string()))?
+            .into_json()
+            .map_err(|e| ClientError::Indexer(e.to_string()))
+    }
+}
+
+/// Split `start..end` into ranges the indexer will serve
+fn pages(start: u64, end: u64) -> impl Iterator<Item = (u64, u64)> {
+    (start..end)
+        .step_by(MAX_RANGE as usize)
+        .map(move |s| (s, end.min(s + MAX_RANGE)))
+}
+
+mod base58 {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(value: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&bs58::encode(value).into_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        let s = String::deserialize(deserializer)?;
+        bs58::decode(&s)
+            .into_vec()
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| D::Error::custom(format!("invalid base58 key {:?}", s)))
+    }
+}
+
+mod optional_base58 {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<[u8; 32]>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => super::base58::serialize(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<[u8; 32]>, D::Error> {
+        #[derive(Deserialize)]
+        struct Wrapped(#[serde(with = "super::base58")] [u8; 32]);
+
+        Ok(Option::<Wrapped>::deserialize(deserializer)?.map(|Wrapped(key)| key))
+    }
+}
+
+mod hex32 {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(value: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        let s = String::deserialize(deserializer)?;
+        hex::decode(&s)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| D::Error::custom(format!("invalid 32 byte hex {:?}", s)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pages() {
+        assert_eq!(pages(0, 0).count(), 0);
+        assert_eq!(pages(5, 10).collect::<Vec<_>>(), vec![(5, 10)]);
+        assert_eq!(
+            pages(0, 2500).collect::<Vec<_>>(),
+            vec![(0, 1000), (1000, 2000), (2000, 2500)]
+        );
+    }
+
+    #[test]
+    fn test_leaf_json_round_trip() {
+        let plain = IndexedLeaf {
+            index: 3,
+            slot: 100,
+            to: [7; 32],
+            amount: 5,
+            ephemeral_key: None,
+        };
+        let json = serde_json::to_string(&plain).unwrap();
+        assert!(!json.contains("ephemeral_key"));
+        assert_eq!(serde_json::from_str::<IndexedLeaf>(&json).unwrap(), plain);
+
+        let stealth = IndexedLeaf {
+            ephemeral_key: Some([9; 32]),
+            ..plain
+        };
+        let json = serde_json::to_string(&stealth).unwrap();
+        assert_eq!(serde_json::from_str::<IndexedLeaf>(&json).unwrap(), stealth);
+    }
+
+    #[test]
+    fn test_frontier_json() {
+        let frontier = Frontier {
+            next_index: 1,
+            root: [1; 32],
+            frontier: vec![Hash([2; 32])],
+        };
+        let json = serde_json::to_value(&frontier).unwrap();
+        assert_eq!(json["root"], hex::encode([1u8; 32]));
+        assert_eq!(json["frontier"][0], hex::encode([2u8; 32]));
+        assert_eq!(serde_json::from_value::<Frontier>(json).unwrap(), frontier);
+    }
+}
@@ -0,0 +1,428 @@
+//! Typed builders for the vapor token program instructions
+use anchor_lang::{AnchorDeserialize, Discriminator, InstructionData, ToAccountMetas};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk_ids::system_program;
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use spl_transfer_hook_interface::instruction::{ExecuteInstruction, TransferHookInstruction};
+use spl_type_length_value::state::TlvStateBorrowed;
+use vaportoken_condenser::registry::{COMMITMENT_KEY_LEN, Circuit, G1_LEN, VerifyingKeyHeader};
+
+use crate::{
+    ASSOCIATED_TOKEN_PROGRAM_ID, CONDENSER_PROGRAM_ID, ClientError, MEMO_PROGRAM_ID,
+    TOKEN_2022_PROGRAM_ID, TRANSFER_HOOK_PROGRAM_ID, pda,
+};
+
+/// Create the transfer tree for a mint. `authority` must be the transfer hook authority of the mint and pays for the tree
+pub fn initialize(mint: &Pubkey, authority: &Pubkey) -> Instruction {
+    build(
+        TRANSFER_HOOK_PROGRAM_ID,
+        vaportoken_transfer_hook::accounts::Initialize {
+            mint: to_anchor(mint),
+            tree_account: to_anchor(&pda::merkle_tree(mint).0),
+            authority: to_anchor(authority),
+            system_program: to_anchor(&system_program::ID),
+        },
+        vaportoken_transfer_hook::instruction::Initialize {},
+    )
+}
+
+/// Create the ExtraAccountMetaList that tells Token-2022 which accounts the hook needs on transfer
+pub fn initialize_extra_account_meta_list(mint: &Pubkey, payer: &Pubkey) -> Instruction {
+    build(
+        TRANSFER_HOOK_PROGRAM_ID,
+        vaportoken_transfer_hook::accounts::InitializeExtraAccountMetaList {
+            payer: to_anchor(payer),
+            extra_account_meta_list: to_anchor(&pda::extra_account_metas(mint).0),
+            mint: to_anchor(mint),
+            token_program: to_anchor(&TOKEN_2022_PROGRAM_ID),
+            associated_token_program: to_anchor(&ASSOCIATED_TOKEN_PROGRAM_ID),
+            system_program: to_anchor(&system_program::ID),
+        },
+        vaportoken_transfer_hook::instruction::InitializeExtraAccountMetaList {},
+    )
+}
+
+/// Set which transfers the hook appends to the transfer tree. `authority` must be the transfer hook authority of the mint
+pub fn set_admission_policy(
+    mint: &Pubkey,
+    authority: &Pubkey,
+    policy: vaportoken_transfer_hook::AdmissionPolicy,
+) -> Instruction {
+    build(
+        TRANSFER_HOOK_PROGRAM_ID,
+        vaportoken_transfer_hook::accounts::SetAdmissionPolicy {
+            mint: to_anchor(mint),
+            tree_account: to_anchor(&pda::merkle_tree(mint).0),
+            authority: to_anchor(authority),
+        },
+        vaportoken_transfer_hook::instruction::SetAdmissionPolicy { policy },
+    )
+}
+
+/// Mint condensed tokens to the recipient's associated token account given a proof-of-burn
+/// made for the verifying key registered under `vk_version`.
+/// The recipient's ATA must exist, see [`create_associated_token_account_idempotent`]
+pub fn condense(
+    mint: &Pubkey,
+    recipient: &Pubkey,
+    payer: &Pubkey,
+    vk_version: u32,
+    proof_bytes: Vec<u8>,
+    pub_witness_bytes: Vec<u8>,
+) -> Instruction {
+    build(
+        CONDENSER_PROGRAM_ID,
+        vaportoken_condenser::accounts::Condense {
+            mint: to_anchor(mint),
+            to: to_anchor(&pda::associated_token_account(recipient, mint)),
+            mint_authority: to_anchor(&pda::mint_authority(mint).0),
+            token_program: to_anchor(&TOKEN_2022_PROGRAM_ID),
+            tree_account: to_anchor(&pda::merkle_tree(mint).0),
+            verifying_key: to_anchor(&pda::verifying_key(mint, vk_version).0),
+            withdrawn: to_anchor(&pda::withdrawn(mint, recipient).0),
+            payer: to_anchor(payer),
+            system_program: to_anchor(&system_program::ID),
+        },
+        vaportoken_condenser::instruction::Condense {
+            recipient: to_anchor(recipient),
+            proof_bytes,
+            pub_witness_bytes,
+        },
+    )
+}
+
+/// A condense instruction found in a transaction, see [`condense`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedCondense {
+    pub mint: Pubkey,
+    pub recipient: Pubkey,
+    pub proof_bytes: Vec<u8>,
+    pub pub_witness_bytes: Vec<u8>,
+}
+
+/// Decode the top level condense instructions of a transaction message.
+/// Instructions whose mint is loaded from an address lookup table are skipped, [`condense`] never does that
+pub fn decode_condenses(message: &VersionedMessage) -> Vec<DecodedCondense> {
+    let keys = message.static_account_keys();
+    message
+        .instructions()
+        .iter()
+        .filter(|ix| keys.get(ix.program_id_index as usize) == Some(&CONDENSER_PROGRAM_ID))
+        .filter_map(|ix| {
+            let args = ix
+                .data
+                .strip_prefix(vaportoken_condenser::instruction::Condense::DISCRIMINATOR)?;
+            let args = vaportoken_condenser::instruction::Condense::try_from_slice(args).ok()?;
+            // The mint is the first account of the instruction
+            let mint = *keys.get(*ix.accounts.first()? as usize)?;
+            Some(DecodedCondense {
+                mint,
+                recipient: Pubkey::new_from_array(args.recipient.to_bytes()),
+                proof_bytes: args.proof_bytes,
+                pub_witness_bytes: args.pub_witness_bytes,
+            })
+        })
+        .collect()
+}
+
+/// Register a verifying key under `version`. `authority` must be the transfer hook authority of the mint
+/// and pays for the account. The key is unusable until its points are written, see [`write_verifying_key`]
+pub fn register_verifying_key(
+    mint: &Pubkey,
+    authority: &Pubkey,
+    version: u32,
+    header: VerifyingKeyHeader,
+) -> Instruction {
+    build(
+        CONDENSER_PROGRAM_ID,
+        vaportoken_condenser::accounts::RegisterVerifyingKey {
+            mint: to_anchor(mint),
+            verifying_key: to_anchor(&pda::verifying_key(mint, version).0),
+            authority: to_anchor(authority),
+            system_program: to_anchor(&system_program::ID),
+        },
+        vaportoken_condenser::instruction::RegisterVerifyingKey { version, header },
+    )
+}
+
+/// Register the key disclosures are checked against under `version`, written like a condenser key
+pub fn register_disclosure_key(
+    mint: &Pubkey,
+    authority: &Pubkey,
+    version: u32,
+    header: VerifyingKeyHeader,
+) -> Instruction {
+    build(
+        CONDENSER_PROGRAM_ID,
+        vaportoken_condenser::accounts::RegisterDisclosureKey {
+            mint: to_anchor(mint),
+            verifying_key: to_anchor(&pda::disclosure_key(mint, version).0),
+            authority: to_anchor(authority),
+            system_program: to_anchor(&system_program::ID),
+        },
+        vaportoken_condenser::instruction::RegisterDisclosureKey { version, header },
+    )
+}
+
+/// Append K points and commitment keys to the key of `circuit` registered under `version`
+pub fn write_verifying_key(
+    mint: &Pubkey,
+    authority: &Pubkey,
+    circuit: Circuit,
+    version: u32,
+    k: Vec<[u8; G1_LEN]>,
+    commitment_keys: Vec<[u8; COMMITMENT_KEY_LEN]>,
+) -> Instruction {
+    build(
+        CONDENSER_PROGRAM_ID,
+        update_verifying_key_accounts(mint, authority, circuit, version),
+        vaportoken_condenser::instruction::WriteVerifyingKey { k, commitment_keys },
+    )
+}
+
+/// Retire the key of `circuit` registered under `version` so it can no longer be used to condense
+/// or to check disclosures
+pub fn retire_verifying_key(
+    mint: &Pubkey,
+    authority: &Pubkey,
+    circuit: Circuit,
+    version: u32,
+) -> Instruction {
+    build(
+        CONDENSER_PROGRAM_ID,
+        update_verifying_key_accounts(mint, authority, circuit, version),
+        vaportoken_condenser::instruction::RetireVerifyingKey {},
+    )
+}
+
+fn update_verifying_key_accounts(
+    mint: &Pubkey,
+    authority: &Pubkey,
+    circuit: Circuit,
+    version: u32,
+) -> vaportoken_condenser::accounts::UpdateVerifyingKey {
+    let verifying_key = match circuit {
+        Circuit::Condenser => pda::verifying_key(mint, version).0,
+        Circuit::Disclosure => pda::disclosure_key(mint, version).0,
+    };
+    vaportoken_condenser::accounts::UpdateVerifyingKey {
+        mint: to_anchor(mint),
+        verifying_key: to_anchor(&verifying_key),
+        authority: to_anchor(authority),
+    }
+}
+
+/// Create the Token-2022 associated token account of `owner` if it does not already exist
+pub fn create_associated_token_account_idempotent(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(pda::associated_token_account(owner, mint), false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false),
+        ],
+        // AssociatedTokenAccountInstruction::CreateIdempotent
+        data: vec![1],
+    }
+}
+
+/// SPL Memo instruction with no signers, used to attach a stealth payment's ephemeral key to a transfer
+pub fn memo(text: &str) -> Instruction {
+    Instruction {
+        program_id: MEMO_PROGRAM_ID,
+        accounts: vec![],
+        data: text.as_bytes().to_vec(),
+    }
+}
+
+/// Token-2022 `transfer_checked` between the associated token accounts of two owners.
+/// The accounts required by the transfer hook are resolved from the data of the mint's ExtraAccountMetaList
+/// account (see [`pda::extra_account_metas`]) and appended in the order Token-2022 expects.
+/// The destination ATA must exist, see [`create_associated_token_account_idempotent`]
+pub fn transfer_checked(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    destination_owner: &Pubkey,
+    amount: u64,
+    decimals: u8,
+    extra_account_metas_data: &[u8],
+) -> Result<Instruction, ClientError> {
+    let source = pda::associated_token_account(owner, mint);
+    let destination = pda::associated_token_account(destination_owner, mint);
+    let validation_state = pda::extra_account_metas(mint).0;
+
+    // TokenInstruction::TransferChecked
+    let mut data = vec![12];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(decimals);
+
+    let mut accounts = vec![
+        AccountMeta::new(source, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(destination, false),
+        AccountMeta::new_readonly(*owner, true),
+    ];
+
+    // Extra account seeds may refer to any account of the hook's execute instruction by index
+    let execute_data = TransferHookInstruction::Execute { amount }.pack();
+    let mut execute_keys = [source, *mint, destination, *owner, validation_state]
+        .iter()
+        .map(to_anchor)
+        .collect::<Vec<_>>();
+
+    let tlv_state = TlvStateBorrowed::unpack(extra_account_metas_data)
+        .map_err(ClientError::ExtraAccountMetas)?;
+    let extra_metas = ExtraAccountMetaList::unpack_with_tlv_state::<ExecuteInstruction>(&tlv_state)
+        .map_err(ClientError::ExtraAccountMetas)?;
+    for extra_meta in extra_metas.data() {
+        let meta = extra_meta
+            .resolve(&execute_data, &to_anchor(&TRANSFER_HOOK_PROGRAM_ID), |i| {
+                execute_keys.get(i).map(|key| (key, None))
+            })
+            .map_err(ClientError::ExtraAccountMetas)?;
+        accounts.push(AccountMeta {
+            pubkey: Pubkey::new_from_array(meta.pubkey.to_bytes()),
+            is_signer: meta.is_signer,
+            is_writable: meta.is_writable,
+        });
+        execute_keys.push(meta.pubkey);
+    }
+
+    accounts.push(AccountMeta::new_readonly(TRANSFER_HOOK_PROGRAM_ID, false));
+    accounts.push(AccountMeta::new_readonly(validation_state, false));
+
+    Ok(Instruction {
+        program_id: TOKEN_2022_PROGRAM_ID,
+        accounts,
+        data,
+    })
+}
+
+fn build(
+    program_id: Pubkey,
+    accounts: impl ToAccountMetas,
+    data: impl InstructionData,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: accounts
+            .to_account_metas(None)
+            .into_iter()
+            .map(|meta| AccountMeta {
+                pubkey: Pubkey::new_from_array(meta.pubkey.to_bytes()),
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+            .collect(),
+        data: data.data(),
+    }
+}
+
+fn to_anchor(pubkey: &Pubkey) -> anchor_lang::prelude::Pubkey {
+    anchor_lang::prelude::Pubkey::new_from_array(pubkey.to_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::message::Message;
+
+    #[test]
+    fn test_condense_accounts() {
+        let mint = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let ix = condense(&mint, &recipient, &payer, 2, vec![1, 2], vec![3]);
+
+        assert_eq!(ix.program_id, CONDENSER_PROGRAM_ID);
+        assert_eq!(
+            ix.data[..8],
+            *vaportoken_condenser::instruction::Condense::DISCRIMINATOR
+        );
+
+        let keys = ix.accounts.iter().map(|m| m.pubkey).collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            vec![
+                mint,
+                pda::associated_token_account(&recipient, &mint),
+                pda::mint_authority(&mint).0,
+                TOKEN_2022_PROGRAM_ID,
+                pda::merkle_tree(&mint).0,
+                pda::verifying_key(&mint, 2).0,
+                pda::withdrawn(&mint, &recipient).0,
+                payer,
+                system_program::ID,
+            ]
+        );
+        assert!(ix.accounts[7].is_signer);
+    }
+
+    #[test]
+    fn test_decode_condenses() {
+        let mint = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let message = Message::new(
+            &[
+                memo("not a condense"),
+                condense(&mint, &recipient, &payer, 1, vec![1, 2], vec![3]),
+            ],
+            Some(&payer),
+        );
+
+        assert_eq!(
+            decode_condenses(&VersionedMessage::Legacy(message)),
+            vec![DecodedCondense {
+                mint,
+                recipient,
+                proof_bytes: vec![1, 2],
+                pub_witness_bytes: vec![3],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_transfer_checked_resolves_hook_accounts() {
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+
+        let extra_metas =
+            vaportoken_transfer_hook::InitializeExtraAccountMetaList::extra_account_metas()
+                .unwrap();
+        let mut data = vec![0u8; ExtraAccountMetaList::size_of(extra_metas.len()).unwrap()];
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &extra_metas).unwrap();
+
+        let ix = transfer_checked(&owner, &mint, &destination, 500, 9, &data).unwrap();
+
+        assert_eq!(ix.program_id, TOKEN_2022_PROGRAM_ID);
+        assert_eq!(ix.data[0], 12);
+        assert_eq!(ix.data[1..9], 500u64.to_le_bytes());
+        assert_eq!(ix.data[9], 9);
+
+        let keys = ix.accounts.iter().map(|m| m.pubkey).collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            vec![
+                pda::associated_token_account(&owner, &mint),
+                mint,
+                pda::associated_token_account(&destination, &mint),
+                owner,
+                pda::merkle_tree(&mint).0,
+                TRANSFER_HOOK_PROGRAM_ID,
+                pda::extra_account_metas(&mint).0,
+            ]
+        );
+        assert!(ix.accounts[4].is_writable);
+    }
+}
@@ -0,0 +1,53 @@
+//! Client SDK for integrating with vapor tokens
+//!
+//! Provides PDA derivation, typed instruction builders, account and event decoders, proof verification and verifying key registry entries for the transfer hook and
+//! condenser programs, plus a client for the transfer tree indexer, so services can interact with a vapor token mint without depending
+//! on the CLI wallet.
+use solana_sdk::pubkey::Pubkey;
+
+pub mod accounts;
+pub mod errors;
+pub mod events;
+pub mod indexer;
+pub mod instructions;
+pub mod pda;
+pub mod proof;
+pub mod registry;
+
+pub use accounts::{
+    AdmissionPolicy, Circuit, MerkleTreeAccount, VerifyingKeyAccount, WithdrawnTracker,
+};
+
+pub const CONDENSER_PROGRAM_ID: Pubkey =
+    Pubkey::new_from_array(vaportoken_condenser::ID.to_bytes());
+pub const TRANSFER_HOOK_PROGRAM_ID: Pubkey =
+    Pubkey::new_from_array(vaportoken_transfer_hook::ID.to_bytes());
+pub const TOKEN_2022_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+pub const MEMO_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("account data is too small for {0}")]
+    AccountTooSmall(&'static str),
+    #[error("account discriminator does not match {0}")]
+    InvalidDiscriminator(&'static str),
+    #[error("failed to resolve transfer hook accounts: {0}")]
+    ExtraAccountMetas(anchor_lang::solana_program::program_error::ProgramError),
+    #[error("invalid {what} encoding: {reason}")]
+    InvalidEncoding {
+        what: &'static str,
+        reason: &'static str,
+    },
+    #[error("proof does not verify against the condenser verifying key")]
+    InvalidProof,
+    #[error("unusable verifying key: {0}")]
+    InvalidVerifyingKey(&'static str),
+    #[error("indexer request failed: {0}")]
+    Indexer(String),
+    #[error(transparent)]
+    Anchor(#[from] anchor_lang::error::Error),
+}
@@ -0,0 +1,62 @@
+//! Program derived addresses used by the vapor token programs
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    ASSOCIATED_TOKEN_PROGRAM_ID, CONDENSER_PROGRAM_ID, TOKEN_2022_PROGRAM_ID,
+    TRANSFER_HOOK_PROGRAM_ID,
+};
+
+/// PDA of the condenser that must be set as the mint authority of a vapor token
+pub fn mint_authority(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"mint_authority", mint.as_ref()], &CONDENSER_PROGRAM_ID)
+}
+
+/// The transfer tree account that the hook appends to on every transfer of the mint
+pub fn merkle_tree(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"merkle_tree", mint.as_ref()], &TRANSFER_HOOK_PROGRAM_ID)
+}
+
+/// Tracks the total amount condensed to a recipient
+pub fn withdrawn(mint: &Pubkey, recipient: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"withdrawn", mint.as_ref(), recipient.as_ref()],
+        &CONDENSER_PROGRAM_ID,
+    )
+}
+
+/// A condenser verifying key registered for the mint under `version`
+pub fn verifying_key(mint: &Pubkey, version: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"verifying_key", mint.as_ref(), &version.to_le_bytes()],
+        &CONDENSER_PROGRAM_ID,
+    )
+}
+
+/// The disclosure verifying key registered for the mint under `version`
+pub fn disclosure_key(mint: &Pubkey, version: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"disclosure_key", mint.as_ref(), &version.to_le_bytes()],
+        &CONDENSER_PROGRAM_ID,
+    )
+}
+
+/// The ExtraAccountMetaList the Token-2022 program reads to resolve the hook's accounts
+pub fn extra_account_metas(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"extra-account-metas", mint.as_ref()],
+        &TRANSFER_HOOK_PROGRAM_ID,
+    )
+}
+
+/// The Token-2022 associated token account of `owner` for the mint
+pub fn associated_token_account(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            owner.as_ref(),
+            TOKEN_2022_PROGRAM_ID.as_ref(),
+            mint.as_ref(),
+        ],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
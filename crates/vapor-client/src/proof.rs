@@ -0,0 +1,448 @@
+//! Codec for the gnark Groth16 proofs, public witnesses and verifying keys produced by sunspot,
+//! and native verification of condenser and disclosure proofs against the verifying keys registered with the program.
+use gnark_verifier_solana::{
+    proof::GnarkProof, verifier::GnarkVerifier, vk::GnarkVerifyingkey, witness::GnarkWitness,
+};
+use utils::CondenserPublicInputs;
+use vaportoken_condenser::registry::{
+    COMMITMENT_KEY_LEN, Circuit, DISCLOSURE_NR_PUBLIC_INPUTS, G1_LEN, G2_LEN, NR_PUBLIC_INPUTS,
+    VerifyingKeyAccount,
+};
+
+use crate::ClientError;
+
+const FIELD_LEN: usize = 32;
+
+/// Number of public inputs of the condenser circuit
+pub const CONDENSER_NR_INPUTS: usize = NR_PUBLIC_INPUTS;
+
+/// Number of public inputs of the disclosure circuit
+pub const DISCLOSURE_NR_INPUTS: usize = DISCLOSURE_NR_PUBLIC_INPUTS;
+
+/// A BN254 Groth16 proof in gnark's uncompressed binary encoding.
+/// Points are big-endian affine coordinates and the commitments are prefixed by their count as a big-endian u32
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proof {
+    pub ar: [u8; G1_LEN],
+    pub bs: [u8; G2_LEN],
+    pub krs: [u8; G1_LEN],
+    pub commitments: Vec<[u8; G1_LEN]>,
+    pub commitment_pok: [u8; G1_LEN],
+}
+
+impl Proof {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ClientError> {
+        let mut reader = Reader::new(bytes, "proof");
+        let ar = reader.array()?;
+        let bs = reader.array()?;
+        let krs = reader.array()?;
+        let nb_commitments = reader.u32()? as usize;
+        let commitments = (0..nb_commitments)
+            .map(|_| reader.array())
+            .collect::<Result<_, _>>()?;
+        let commitment_pok = reader.array()?;
+        reader.finish()?;
+
+        Ok(Self {
+            ar,
+            bs,
+            krs,
+            commitments,
+            commitment_pok,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(4 * G1_LEN + G2_LEN + 4 + G1_LEN * self.commitments.len());
+        out.extend_from_slice(&self.ar);
+        out.extend_from_slice(&self.bs);
+        out.extend_from_slice(&self.krs);
+        out.extend_from_slice(&(self.commitments.len() as u32).to_be_bytes());
+        for commitment in &self.commitments {
+            out.extend_from_slice(commitment);
+        }
+        out.extend_from_slice(&self.commitment_pok);
+        out
+    }
+}
+
+/// A gnark public witness.
+/// Encoded as the number of public and secret variables followed by the length prefixed vector of big-endian field elements
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicWitness {
+    pub nb_public: u32,
+    pub nb_secret: u32,
+    pub entries: Vec<[u8; FIELD_LEN]>,
+}
+
+impl PublicWitness {
+    pub fn new(entries: Vec<[u8; FIELD_LEN]>) -> Self {
+        Self {
+            nb_public: entries.len() as u32,
+            nb_secret: 0,
+            entries,
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ClientError> {
+        let mut reader = Reader::new(bytes, "public witness");
+        let nb_public = reader.u32()?;
+        let nb_secret = reader.u32()?;
+        let nb_entries = reader.u32()? as usize;
+        let entries = (0..nb_entries)
+            .map(|_| reader.array())
+            .collect::<Result<_, _>>()?;
+        reader.finish()?;
+
+        Ok(Self {
+            nb_public,
+            nb_secret,
+            entries,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(12 + FIELD_LEN * self.entries.len());
+        out.extend_from_slice(&self.nb_public.to_be_bytes());
+        out.extend_from_slice(&self.nb_secret.to_be_bytes());
+        out.extend_from_slice(&(self.entries.len() as u32).to_be_bytes());
+        for entry in &self.entries {
+            out.extend_from_slice(entry);
+        }
+        out
+    }
+
+    /// Decode the recipient, amount and merkle root exactly as the condenser program does
+    pub fn condenser_inputs(&self) -> Result<CondenserPublicInputs, ClientError> {
+        CondenserPublicInputs::from_entries(&self.entries).ok_or(ClientError::InvalidEncoding {
+            what: "public witness",
+            reason: "expected 4 condenser public inputs",
+        })
+    }
+}
+
+/// A BN254 Groth16 verifying key in gnark's uncompressed binary encoding, as written by `sunspot setup`.
+/// The points [α]1, [β]1, [β]2, [γ]2, [δ]1 and [δ]2 are followed by K, the public inputs each commitment commits to
+/// as lists of u64 indices, and the Pedersen commitment keys. Each variable length part is prefixed by its length as a big-endian u32
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifyingKey {
+    pub alpha_g1: [u8; G1_LEN],
+    pub beta_g1: [u8; G1_LEN],
+    pub beta_g2: [u8; G2_LEN],
+    pub gamma_g2: [u8; G2_LEN],
+    pub delta_g1: [u8; G1_LEN],
+    pub delta_g2: [u8; G2_LEN],
+    pub k: Vec<[u8; G1_LEN]>,
+    pub public_and_commitment_committed: Vec<Vec<u64>>,
+    pub commitment_keys: Vec<[u8; COMMITMENT_KEY_LEN]>,
+}
+
+impl VerifyingKey {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ClientError> {
+        let mut reader = Reader::new(bytes, "verifying key");
+        let alpha_g1 = reader.array()?;
+        let beta_g1 = reader.array()?;
+        let beta_g2 = reader.array()?;
+        let gamma_g2 = reader.array()?;
+        let delta_g1 = reader.array()?;
+        let delta_g2 = reader.array()?;
+        let nb_k = reader.u32()? as usize;
+        let k = (0..nb_k)
+            .map(|_| reader.array())
+            .collect::<Result<_, _>>()?;
+        let nb_committed = reader.u32()? as usize;
+        let public_and_commitment_committed = (0..nb_committed)
+            .map(|_| {
+                let len = reader.u32()? as usize;
+                (0..len).map(|_| reader.u64()).collect::<Result<_, _>>()
+            })
+            .collect::<Result<_, _>>()?;
+        let nb_commitment_keys = reader.u32()? as usize;
+        let commitment_keys = (0..nb_commitment_keys)
+            .map(|_| reader.array())
+            .collect::<Result<_, _>>()?;
+        reader.finish()?;
+
+        Ok(Self {
+            alpha_g1,
+            beta_g1,
+            beta_g2,
+            gamma_g2,
+            delta_g1,
+            delta_g2,
+            k,
+            public_and_commitment_committed,
+            commitment_keys,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&self.alpha_g1);
+        out.extend_from_slice(&self.beta_g1);
+        out.extend_from_slice(&self.beta_g2);
+        out.extend_from_slice(&self.gamma_g2);
+        out.extend_from_slice(&self.delta_g1);
+        out.extend_from_slice(&self.delta_g2);
+        out.extend_from_slice(&(self.k.len() as u32).to_be_bytes());
+        for point in &self.k {
+            out.extend_from_slice(point);
+        }
+        out.extend_from_slice(&(self.public_and_commitment_committed.len() as u32).to_be_bytes());
+        for committed in &self.public_and_commitment_committed {
+            out.extend_from_slice(&(committed.len() as u32).to_be_bytes());
+            for index in committed {
+                out.extend_from_slice(&index.to_be_bytes());
+            }
+        }
+        out.extend_from_slice(&(self.commitment_keys.len() as u32).to_be_bytes());
+        for key in &self.commitment_keys {
+            out.extend_from_slice(key);
+        }
+        out
+    }
+}
+
+/// Verify a condenser proof natively against a registered verifying key, see [`crate::accounts::decode_verifying_key`],
+/// and return the public inputs it commits to. A proof that passes here will pass the program's proof check
+/// as long as the key is not retired.
+pub fn verify_condense(
+    verifying_key: &VerifyingKeyAccount,
+    proof_bytes: &[u8],
+    pub_witness_bytes: &[u8],
+) -> Result<CondenserPublicInputs, ClientError> {
+    let public_inputs = PublicWitness::from_bytes(pub_witness_bytes)?.condenser_inputs()?;
+    check_registered(verifying_key, Circuit::Condenser)?;
+    verify_with::<CONDENSER_NR_INPUTS>(
+        &verifying_key.verifying_key(),
+        proof_bytes,
+        pub_witness_bytes,
+    )?;
+    Ok(public_inputs)
+}
+
+/// Verify a disclosure proof natively against the disclosure key registered for the mint
+pub fn verify_disclosure(
+    verifying_key: &VerifyingKeyAccount,
+    proof_bytes: &[u8],
+    pub_witness_bytes: &[u8],
+) -> Result<(), ClientError> {
+    check_registered(verifying_key, Circuit::Disclosure)?;
+    verify_with::<DISCLOSURE_NR_INPUTS>(
+        &verifying_key.verifying_key(),
+        proof_bytes,
+        pub_witness_bytes,
+    )
+}
+
+fn check_registered(
+    verifying_key: &VerifyingKeyAccount,
+    circuit: Circuit,
+) -> Result<(), ClientError> {
+    if verifying_key.circuit != circuit {
+        return Err(ClientError::InvalidVerifyingKey(
+            "registered for another circuit",
+        ));
+    }
+    if !verifying_key.is_complete() {
+        return Err(ClientError::InvalidVerifyingKey("not completely written"));
+    }
+    Ok(())
+}
+
+fn verify_with<const N: usize>(
+    vk: &GnarkVerifyingkey<'_>,
+    proof_bytes: &[u8],
+    pub_witness_bytes: &[u8],
+) -> Result<(), ClientError> {
+    // Decode with the strict codec first so malformed input is an error rather than a panic in the verifier
+    Proof::from_bytes(proof_bytes)?;
+    if PublicWitness::from_bytes(pub_witness_bytes)?.entries.len() != N {
+        return Err(ClientError::InvalidEncoding {
+            what: "public witness",
+            reason: "wrong number of public inputs",
+        });
+    }
+
+    let proof = GnarkProof::from_bytes(proof_bytes).map_err(|_| ClientError::InvalidEncoding {
+        what: "proof",
+        reason: "rejected by verifier",
+    })?;
+    let pub_witness = GnarkWitness::<N>::from_bytes(pub_witness_bytes).map_err(|_| {
+        ClientError::InvalidEncoding {
+            what: "public witness",
+            reason: "rejected by verifier",
+        }
+    })?;
+
+    let mut verifier: GnarkVerifier<N> = GnarkVerifier::new(vk);
+    verifier
+        .verify(proof, pub_witness)
+        .map_err(|_| ClientError::InvalidProof)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    what: &'static str,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], what: &'static str) -> Self {
+        Self { bytes, what }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ClientError> {
+        if self.bytes.len() < len {
+            return Err(ClientError::InvalidEncoding {
+                what: self.what,
+                reason: "unexpected end of input",
+            });
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ClientError> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+
+    fn u32(&mut self) -> Result<u32, ClientError> {
+        self.array().map(u32::from_be_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, ClientError> {
+        self.array().map(u64::from_be_bytes)
+    }
+
+    fn finish(self) -> Result<(), ClientError> {
+        if !self.bytes.is_empty() {
+            return Err(ClientError::InvalidEncoding {
+                what: self.what,
+                reason: "trailing bytes",
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utils::{fr_to_be_32, pack_bytes, u64_to_be_32};
+
+    fn proof() -> Proof {
+        Proof {
+            ar: [1; G1_LEN],
+            bs: [2; G2_LEN],
+            krs: [3; G1_LEN],
+            commitments: vec![[4; G1_LEN]],
+            commitment_pok: [5; G1_LEN],
+        }
+    }
+
+    #[test]
+    fn test_proof_round_trip() {
+        let bytes = proof().to_bytes();
+        assert_eq!(bytes.len(), 388);
+        assert_eq!(Proof::from_bytes(&bytes).unwrap(), proof());
+    }
+
+    #[test]
+    fn test_proof_rejects_bad_lengths() {
+        let bytes = proof().to_bytes();
+        assert!(Proof::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(Proof::from_bytes(&trailing).is_err());
+    }
+
+    fn verifying_key() -> VerifyingKey {
+        VerifyingKey {
+            alpha_g1: [1; G1_LEN],
+            beta_g1: [2; G1_LEN],
+            beta_g2: [3; G2_LEN],
+            gamma_g2: [4; G2_LEN],
+            delta_g1: [5; G1_LEN],
+            delta_g2: [6; G2_LEN],
+            k: (0..6).map(|i| [10 + i; G1_LEN]).collect(),
+            public_and_commitment_committed: vec![vec![]],
+            commitment_keys: vec![[7; COMMITMENT_KEY_LEN]],
+        }
+    }
+
+    #[test]
+    fn test_verifying_key_round_trip() {
+        let bytes = verifying_key().to_bytes();
+        assert_eq!(
+            bytes.len(),
+            3 * G1_LEN + 3 * G2_LEN + 4 + 6 * G1_LEN + 4 + 4 + 4 + COMMITMENT_KEY_LEN
+        );
+        assert_eq!(VerifyingKey::from_bytes(&bytes).unwrap(), verifying_key());
+        assert!(VerifyingKey::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        let mut committed = verifying_key();
+        committed.public_and_commitment_committed = vec![vec![1, 3]];
+        assert_eq!(
+            VerifyingKey::from_bytes(&committed.to_bytes()).unwrap(),
+            committed
+        );
+    }
+
+    #[test]
+    fn test_condenser_inputs() {
+        let recipient = [7u8; 32];
+        let mut entries: Vec<[u8; 32]> = pack_bytes(&recipient).iter().map(fr_to_be_32).collect();
+        entries.push(u64_to_be_32(666));
+        entries.push([9u8; 32]);
+
+        let witness = PublicWitness::new(entries);
+        let bytes = witness.to_bytes();
+        assert_eq!(bytes.len(), 12 + 4 * 32);
+
+        let decoded = PublicWitness::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, witness);
+        assert_eq!(
+            decoded.condenser_inputs().unwrap(),
+            CondenserPublicInputs {
+                recipient,
+                amount: 666,
+                merkle_root: [9u8; 32],
+            }
+        );
+    }
+
+    #[test]
+    fn test_condenser_inputs_wrong_count() {
+        let witness = PublicWitness::new(vec![[0u8; 32]; 3]);
+        assert!(witness.condenser_inputs().is_err());
+    }
+
+    #[test]
+    fn test_verify_rejects_garbage() {
+        let mut entries: Vec<[u8; 32]> = pack_bytes(&[7u8; 32]).iter().map(fr_to_be_32).collect();
+        entries.push(u64_to_be_32(666));
+        entries.push([0u8; 32]);
+        let pub_witness = PublicWitness::new(entries).to_bytes();
+
+        let vk = crate::registry::RegistryEntry::new(1, &verifying_key())
+            .unwrap()
+            .account(&solana_sdk::pubkey::Pubkey::new_unique())
+            .unwrap();
+        assert!(verify_condense(&vk, &proof().to_bytes()[1..], &pub_witness).is_err());
+    }
+
+    #[test]
+    fn test_verify_rejects_key_for_other_circuit() {
+        let pub_witness = PublicWitness::new(vec![[0u8; 32]; 4]).to_bytes();
+        let vk = crate::registry::RegistryEntry::new(1, &verifying_key())
+            .unwrap()
+            .account(&solana_sdk::pubkey::Pubkey::new_unique())
+            .unwrap();
+        assert!(matches!(
+            verify_disclosure(&vk, &proof().to_bytes(), &pub_witness),
+            Err(ClientError::InvalidVerifyingKey(_))
+        ));
+    }
+}
@@ -0,0 +1,265 @@
+//! Entries for the condenser's verifying key registry, as emitted by `cargo xtask emit-vk`.
+//! An entry is a verifying key written by `sunspot setup` together with the version it is registered under.
+//! Keys of the condenser and disclosure circuits are told apart by their number of public inputs.
+use serde::{Deserialize, Serialize};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use vaportoken_condenser::registry::{
+    COMMITMENT_KEY_LEN, Circuit, G1_LEN, MAX_COMMITMENTS, VerifyingKeyAccount, VerifyingKeyHeader,
+};
+
+use crate::proof::VerifyingKey;
+use crate::{ClientError, instructions, pda};
+
+/// K points appended per write, keeping each transaction well under the size limit
+const K_PER_WRITE: usize = 8;
+/// Commitment keys appended per write
+const COMMITMENT_KEYS_PER_WRITE: usize = 2;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryEntry {
+    pub version: u32,
+    /// The key in gnark's binary encoding, see [`VerifyingKey`]
+    #[serde(with = "hex_bytes")]
+    pub verifying_key: Vec<u8>,
+}
+
+impl RegistryEntry {
+    /// Entry registering `verifying_key` under `version`, if it is a key the condenser can register
+    pub fn new(version: u32, verifying_key: &VerifyingKey) -> Result<Self, ClientError> {
+        let entry = Self {
+            version,
+            verifying_key: verifying_key.to_bytes(),
+        };
+        entry.parts()?;
+        Ok(entry)
+    }
+
+    /// The circuit the key verifies proofs of
+    pub fn circuit(&self) -> Result<Circuit, ClientError> {
+        Ok(self.parts()?.0)
+    }
+
+    /// The address this entry is registered at for `mint`
+    pub fn address(&self, mint: &Pubkey) -> Result<(Pubkey, u8), ClientError> {
+        Ok(match self.circuit()? {
+            Circuit::Condenser => pda::verifying_key(mint, self.version),
+            Circuit::Disclosure => pda::disclosure_key(mint, self.version),
+        })
+    }
+
+    /// The account holding this entry for `mint` once all of its instructions have run
+    pub fn account(&self, mint: &Pubkey) -> Result<VerifyingKeyAccount, ClientError> {
+        let (circuit, header, k, commitment_keys) = self.parts()?;
+        Ok(VerifyingKeyAccount {
+            version: self.version,
+            bump: self.address(mint)?.1,
+            retired: false,
+            circuit,
+            header,
+            k,
+            commitment_keys,
+        })
+    }
+
+    /// Instructions registering this entry for `mint`, each small enough to be sent in its own transaction.
+    /// They must run in order and be signed by `authority`, the transfer hook authority of the mint
+    pub fn instructions(
+        &self,
+        mint: &Pubkey,
+        authority: &Pubkey,
+    ) -> Result<Vec<Instruction>, ClientError> {
+        let (circuit, header, k, commitment_keys) = self.parts()?;
+
+        let mut ixs = vec![match circuit {
+            Circuit::Condenser => {
+                instructions::register_verifying_key(mint, authority, self.version, header)
+            }
+            Circuit::Disclosure => {
+                instructions::register_disclosure_key(mint, authority, self.version, header)
+            }
+        }];
+        for chunk in k.chunks(K_PER_WRITE) {
+            ixs.push(instructions::write_verifying_key(
+                mint,
+                authority,
+                circuit,
+                self.version,
+                chunk.to_vec(),
+                vec![],
+            ));
+        }
+        for chunk in commitment_keys.chunks(COMMITMENT_KEYS_PER_WRITE) {
+            ixs.push(instructions::write_verifying_key(
+                mint,
+                authority,
+                circuit,
+                self.version,
+                vec![],
+                chunk.to_vec(),
+            ));
+        }
+        Ok(ixs)
+    }
+
+    /// Split the key into the parts the program takes, checking it can be registered
+    #[allow(clippy::type_complexity)]
+    fn parts(
+        &self,
+    ) -> Result<
+        (
+            Circuit,
+            VerifyingKeyHeader,
+            Vec<[u8; G1_LEN]>,
+            Vec<[u8; COMMITMENT_KEY_LEN]>,
+        ),
+        ClientError,
+    > {
+        let vk = VerifyingKey::from_bytes(&self.verifying_key)?;
+        if vk.commitment_keys.len() > MAX_COMMITMENTS {
+            return Err(ClientError::InvalidVerifyingKey("too many commitments"));
+        }
+        if vk
+            .public_and_commitment_committed
+            .iter()
+            .any(|committed| !committed.is_empty())
+        {
+            return Err(ClientError::InvalidVerifyingKey(
+                "commitments to public inputs are not supported",
+            ));
+        }
+        let nr_commitments = vk.commitment_keys.len() as u8;
+        let circuit = [Circuit::Condenser, Circuit::Disclosure]
+            .into_iter()
+            .find(|circuit| vk.k.len() == VerifyingKeyAccount::k_len(*circuit, nr_commitments))
+            .ok_or(ClientError::InvalidVerifyingKey(
+                "number of public inputs does not match the condenser or disclosure circuit",
+            ))?;
+
+        Ok((
+            circuit,
+            VerifyingKeyHeader {
+                alpha_g1: vk.alpha_g1,
+                beta_g2: vk.beta_g2,
+                gamma_g2: vk.gamma_g2,
+                delta_g2: vk.delta_g2,
+                nr_commitments,
+            },
+            vk.k,
+            vk.commitment_keys,
+        ))
+    }
+}
+
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        hex::decode(&s).map_err(|e| D::Error::custom(format!("invalid hex: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+    use vaportoken_condenser::registry::G2_LEN;
+
+    use crate::CONDENSER_PROGRAM_ID;
+
+    fn verifying_key(nr_commitments: usize) -> VerifyingKey {
+        key_of(Circuit::Condenser, nr_commitments)
+    }
+
+    fn key_of(circuit: Circuit, nr_commitments: usize) -> VerifyingKey {
+        VerifyingKey {
+            alpha_g1: [1; G1_LEN],
+            beta_g1: [2; G1_LEN],
+            beta_g2: [3; G2_LEN],
+            gamma_g2: [4; G2_LEN],
+            delta_g1: [5; G1_LEN],
+            delta_g2: [6; G2_LEN],
+            k: vec![[7; G1_LEN]; VerifyingKeyAccount::k_len(circuit, nr_commitments as u8)],
+            public_and_commitment_committed: vec![vec![]; nr_commitments],
+            commitment_keys: vec![[8; COMMITMENT_KEY_LEN]; nr_commitments],
+        }
+    }
+
+    #[test]
+    fn test_entry_json_round_trip() {
+        let entry = RegistryEntry::new(2, &verifying_key(1)).unwrap();
+        let json = serde_json::to_string(&entry).unwrap();
+        assert_eq!(serde_json::from_str::<RegistryEntry>(&json).unwrap(), entry);
+    }
+
+    #[test]
+    fn test_entry_rejects_unsupported_keys() {
+        let mut committed = verifying_key(1);
+        committed.public_and_commitment_committed = vec![vec![1]];
+        assert!(RegistryEntry::new(1, &committed).is_err());
+
+        let mut wrong_inputs = verifying_key(1);
+        wrong_inputs.k.pop();
+        assert!(RegistryEntry::new(1, &wrong_inputs).is_err());
+
+        assert!(RegistryEntry::new(1, &verifying_key(MAX_COMMITMENTS + 1)).is_err());
+    }
+
+    #[test]
+    fn test_entry_instructions() {
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let entry = RegistryEntry::new(5, &verifying_key(MAX_COMMITMENTS)).unwrap();
+
+        // 9 K points over two writes and 4 commitment keys over another two
+        let ixs = entry.instructions(&mint, &authority).unwrap();
+        assert_eq!(ixs.len(), 5);
+        assert!(ixs.iter().all(|ix| ix.program_id == CONDENSER_PROGRAM_ID));
+        assert_eq!(
+            ixs[0].data[..8],
+            *vaportoken_condenser::instruction::RegisterVerifyingKey::DISCRIMINATOR
+        );
+        for ix in &ixs[1..] {
+            assert_eq!(
+                ix.data[..8],
+                *vaportoken_condenser::instruction::WriteVerifyingKey::DISCRIMINATOR
+            );
+            assert_eq!(ix.accounts[1].pubkey, pda::verifying_key(&mint, 5).0);
+        }
+
+        let account = entry.account(&mint).unwrap();
+        assert!(account.is_complete());
+        assert_eq!(account.version, 5);
+        assert_eq!(account.circuit, Circuit::Condenser);
+        assert_eq!(account.header.nr_commitments, MAX_COMMITMENTS as u8);
+    }
+
+    #[test]
+    fn test_disclosure_entry_instructions() {
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let entry = RegistryEntry::new(1, &key_of(Circuit::Disclosure, 1)).unwrap();
+        assert_eq!(entry.circuit().unwrap(), Circuit::Disclosure);
+
+        // 36 K points over five writes and the commitment key in one more
+        let ixs = entry.instructions(&mint, &authority).unwrap();
+        assert_eq!(ixs.len(), 7);
+        assert_eq!(
+            ixs[0].data[..8],
+            *vaportoken_condenser::instruction::RegisterDisclosureKey::DISCRIMINATOR
+        );
+        for ix in &ixs {
+            assert_eq!(ix.accounts[1].pubkey, pda::disclosure_key(&mint, 1).0);
+        }
+
+        let account = entry.account(&mint).unwrap();
+        assert!(account.is_complete());
+        assert_eq!(account.circuit, Circuit::Disclosure);
+        assert_eq!(account.bump, pda::disclosure_key(&mint, 1).1);
+    }
+}
@@ -0,0 +1,137 @@
+use std::str::FromStr;
+
+use solana_client::{
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+    rpc_config::{CommitmentConfig, RpcTransactionConfig},
+    rpc_response::OptionSerializer,
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::UiTransactionEncoding;
+use vapor_client::{accounts::decode_merkle_tree, events::leaves_with_memos, pda};
+use vaporize_addresses::parse_stealth_memo;
+
+use crate::store::{NewLeaf, Store};
+
+const PAGE_SIZE: usize = 1000;
+/// Number of transactions whose transfers are committed to the store at once
+const BATCH_SIZE: usize = 100;
+
+/// Append the transfers from mint transactions newer than the last one processed, oldest first.
+/// Only finalized transactions are read so the tree never has to be rolled back.
+pub fn poll(client: &RpcClient, mint: &Pubkey, store: &Store) -> anyhow::Result<usize> {
+    let until = store
+        .last_signature()?
+        .map(|s| Signature::from_str(&s))
+        .transpose()?;
+
+    // Pages come newest -> oldest, walk back until the last processed signature
+    let mut signatures = Vec::new();
+    let mut before = None;
+    loop {
+        let page = client.get_signatures_for_address_with_config(
+            mint,
+            GetConfirmedSignaturesForAddress2Config {
+                limit: Some(PAGE_SIZE),
+                before,
+                until,
+                commitment: Some(CommitmentConfig::finalized()),
+            },
+        )?;
+        let done = page.len() < PAGE_SIZE;
+        before = page
+            .last()
+            .map(|info| Signature::from_str(&info.signature))
+            .transpose()?;
+        signatures.extend(page);
+        if done {
+            break;
+        }
+    }
+    signatures.reverse();
+
+    let mut appended = 0;
+    for batch in signatures.chunks(BATCH_SIZE) {
+        let mut leaves = Vec::new();
+        for info in batch.iter().filter(|info| info.err.is_none()) {
+            leaves.extend(fetch_leaves(
+                client,
+                &Signature::from_str(&info.signature)?,
+            )?);
+        }
+        // chunks are never empty
+        store.append(&leaves, &batch.last().unwrap().signature)?;
+        appended += leaves.len();
+    }
+
+    Ok(appended)
+}
+
+/// Transfers recorded by the hook in one transaction
+fn fetch_leaves(client: &RpcClient, sig: &Signature) -> anyhow::Result<Vec<NewLeaf>> {
+    let tx = client.get_transaction_with_config(
+        sig,
+        RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(CommitmentConfig::finalized()),
+            max_supported_transaction_version: Some(0),
+        },
+    )?;
+
+    let logs = match tx.transaction.meta.map(|meta| meta.log_messages) {
+        Some(OptionSerializer::Some(logs)) => logs,
+        _ => return Ok(Vec::new()),
+    };
+    // A stealth transfer carries the sender's ephemeral key in a memo right after it.
+    // Transfers skipped by the admission policy have no leaf
+    Ok(leaves_with_memos(&logs, parse_stealth_memo)
+        .into_iter()
+        .map(|(event, ephemeral_key)| {
+            tracing::debug!(
+                "Slot {}: transfer of {} to {}",
+                tx.slot,
+                event.amount,
+                event.to
+            );
+            NewLeaf {
+                slot: tx.slot,
+                to: event.to.to_bytes(),
+                amount: event.amount,
+                ephemeral_key,
+            }
+        })
+        .collect())
+}
+
+/// Compare the store with the tree account on chain.
+/// Once both hold the same number of leaves their roots must match, otherwise transfers were missed.
+/// Failing to reach the RPC is not an error, the check is repeated after the next poll
+pub fn check_onchain(client: &RpcClient, mint: &Pubkey, store: &Store) -> anyhow::Result<()> {
+    let (tree_account, _) = pda::merkle_tree(mint);
+    let account =
+        match client.get_account_with_commitment(&tree_account, CommitmentConfig::finalized()) {
+            Ok(response) => response.value,
+            Err(e) => {
+                tracing::warn!("Could not read the tree account: {}", e);
+                return Ok(());
+            }
+        };
+    let data = account
+        .ok_or_else(|| anyhow::anyhow!("transfer tree account {} not found", tree_account))?
+        .data;
+    let onchain = decode_merkle_tree(&data)?;
+
+    if onchain.next_index == store.next_index() {
+        anyhow::ensure!(
+            onchain.root == store.root(),
+            "indexed root differs from the on-chain root at {} leaves",
+            onchain.next_index
+        );
+    } else {
+        tracing::info!(
+            "Indexed {} of {} leaves",
+            store.next_index(),
+            onchain.next_index
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::Parser;
+use solana_client::{rpc_client::RpcClient, rpc_config::CommitmentConfig};
+use solana_sdk::pubkey::Pubkey;
+
+mod follow;
+mod server;
+mod store;
+
+#[derive(clap::Parser)]
+#[clap(
+    version,
+    about = "Follows the transfer hook of a vapor token mint and serves its transfer tree over HTTP",
+    long_about = None
+)]
+struct Args {
+    #[clap(long, env = "SOL_RPC", default_value = "https://api.devnet.solana.com")]
+    rpc_url: String,
+
+    /// The vapor token mint to index
+    #[clap(long, env = "MINT")]
+    mint: String,
+
+    /// Path to the indexer database file
+    #[clap(long, env = "INDEXER_PATH", default_value = "indexer.redb")]
+    db_file: String,
+
+    /// Address to serve the HTTP API on
+    #[clap(long, env = "INDEXER_LISTEN", default_value = "127.0.0.1:3030")]
+    listen: String,
+
+    /// Seconds to wait between polls for new transactions
+    #[clap(long, default_value = "5")]
+    poll_interval: u64,
+}
+
+fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+    let args = Args::parse();
+
+    let mint = Pubkey::from_str(&args.mint)?;
+    let store = Arc::new(store::Store::open(&args.db_file)?);
+    let client = RpcClient::new_with_timeout_and_commitment(
+        args.rpc_url.clone(),
+        Duration::from_secs(30),
+        CommitmentConfig::finalized(),
+    );
+
+    let server = {
+        let store = store.clone();
+        let listen = args.listen.clone();
+        std::thread::spawn(move || server::serve(store, &listen))
+    };
+
+    tracing::info!("Indexing mint {}", mint);
+    while !server.is_finished() {
+        match follow::poll(&client, &mint, &store) {
+            Ok(0) => {}
+            Ok(appended) => tracing::info!(
+                "Appended {} leaves, tree now holds {}",
+                appended,
+                store.next_index()
+            ),
+            Err(e) => tracing::warn!("Poll failed: {:#}", e),
+        }
+        // A mismatch means the index is wrong, stop serving it
+        follow::check_onchain(&client, &mint, &store)?;
+        std::thread::sleep(Duration::from_secs(args.poll_interval));
+    }
+
+    server.join().expect("server thread panicked")
+}
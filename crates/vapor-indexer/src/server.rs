@@ -0,0 +1,132 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use tiny_http::{Header, Method, Response, Server};
+use vapor_client::indexer::MAX_RANGE;
+
+use crate::store::Store;
+
+/// Serve the indexer API, see [`vapor_client::indexer`] for the routes
+pub fn serve(store: Arc<Store>, addr: &str) -> anyhow::Result<()> {
+    let server =
+        Server::http(addr).map_err(|e| anyhow::anyhow!("failed to listen on {}: {}", addr, e))?;
+    tracing::info!("Listening on http://{}", addr);
+
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    for request in server.incoming_requests() {
+        let (status, body) = if *request.method() == Method::Get {
+            route(&store, request.url())
+        } else {
+            (405, error_body("only GET is supported"))
+        };
+        tracing::debug!("{} {} -> {}", request.method(), request.url(), status);
+
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(content_type.clone());
+        if let Err(e) = request.respond(response) {
+            tracing::warn!("Failed to respond: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn route(store: &Store, url: &str) -> (u16, String) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let result = match path {
+        "/leaves" => match range(query) {
+            Ok(range) => store.leaves(range).map(|l| serde_json::to_string(&l)),
+            Err(e) => return (400, error_body(&e)),
+        },
+        "/roots" => match range(query) {
+            Ok(range) => store.roots(range).map(|r| serde_json::to_string(&r)),
+            Err(e) => return (400, error_body(&e)),
+        },
+        "/frontier" => Ok(serde_json::to_string(&store.frontier())),
+        _ => return (404, error_body("not found")),
+    };
+
+    match result {
+        Ok(Ok(body)) => (200, body),
+        Ok(Err(e)) => (500, error_body(&e.to_string())),
+        Err(e) => {
+            tracing::error!("Failed to read store: {}", e);
+            (500, error_body("failed to read store"))
+        }
+    }
+}
+
+/// Parse `start` and `end` query parameters into a range of at most [`MAX_RANGE`]
+fn range(query: &str) -> Result<Range<u64>, String> {
+    let mut start = None;
+    let mut end = None;
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = value
+            .parse::<u64>()
+            .map_err(|_| format!("invalid {}: {:?}", key, value));
+        match key {
+            "start" => start = Some(value?),
+            "end" => end = Some(value?),
+            _ => return Err(format!("unknown parameter {:?}", key)),
+        }
+    }
+
+    let start = start.ok_or("missing start")?;
+    let end = end.ok_or("missing end")?;
+    if end < start || end - start > MAX_RANGE {
+        return Err(format!(
+            "range must have start <= end and cover at most {} entries",
+            MAX_RANGE
+        ));
+    }
+    Ok(start..end)
+}
+
+fn error_body(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::tests::leaf;
+    use vapor_client::indexer::{Frontier, IndexedLeaf, RootEntry};
+
+    #[test]
+    fn test_range() {
+        assert_eq!(range("start=2&end=5"), Ok(2..5));
+        assert_eq!(range("end=5&start=5"), Ok(5..5));
+        assert!(range("start=2").is_err());
+        assert!(range("start=5&end=2").is_err());
+        assert!(range("start=0&end=1001").is_err());
+        assert!(range("start=a&end=2").is_err());
+        assert!(range("start=0&end=2&leaf=1").is_err());
+    }
+
+    #[test]
+    fn test_routes() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let store = Store::open(file.path().to_str().unwrap()).unwrap();
+        store.append(&[leaf(0), leaf(1), leaf(2)], "sig").unwrap();
+
+        let (status, body) = route(&store, "/leaves?start=1&end=10");
+        assert_eq!(status, 200);
+        let leaves: Vec<IndexedLeaf> = serde_json::from_str(&body).unwrap();
+        assert_eq!(leaves.iter().map(|l| l.index).collect::<Vec<_>>(), [1, 2]);
+
+        let (status, body) = route(&store, "/roots?start=0&end=10");
+        assert_eq!(status, 200);
+        let roots: Vec<RootEntry> = serde_json::from_str(&body).unwrap();
+        assert_eq!(roots.len(), 4);
+
+        let (status, body) = route(&store, "/frontier");
+        assert_eq!(status, 200);
+        let frontier: Frontier = serde_json::from_str(&body).unwrap();
+        assert_eq!(frontier.next_index, 3);
+        assert_eq!(frontier.root, roots[3].root);
+
+        assert_eq!(route(&store, "/leaves?start=0").0, 400);
+        assert_eq!(route(&store, "/leaf/1").0, 404);
+    }
+}
@@ -0,0 +1,218 @@
+use std::ops::Range;
+use std::sync::RwLock;
+
+use redb::{ReadableDatabase, ReadableTable, TableDefinition};
+use transfer_tree::TransferTree;
+use vapor_client::indexer::{Frontier, Hash, IndexedLeaf, RootEntry};
+
+pub const TREE_HEIGHT: usize = 26;
+
+/// Table of leaves indexed by position in the tree, holding destination, amount and slot.
+const LEAVES: TableDefinition<u64, ([u8; 32], u64, u64)> = TableDefinition::new("leaves");
+
+/// Table of ephemeral keys from stealth memos indexed by the position of the leaf they were sent with.
+const EPHEMERAL_KEYS: TableDefinition<u64, [u8; 32]> = TableDefinition::new("ephemeral-keys");
+
+/// Table of tree roots indexed by the number of leaves in the tree.
+const ROOTS: TableDefinition<u64, [u8; 32]> = TableDefinition::new("roots");
+
+/// Table holding the newest mint transaction signature that has been processed.
+const CURSOR: TableDefinition<&str, &str> = TableDefinition::new("cursor");
+const LAST_SIGNATURE: &str = "last-signature";
+
+/// A transfer seen on chain that is not yet in the store
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NewLeaf {
+    pub slot: u64,
+    pub to: [u8; 32],
+    pub amount: u64,
+    pub ephemeral_key: Option<[u8; 32]>,
+}
+
+/// Leaves and roots on disk together with the tree they build, kept in memory to serve the frontier
+pub struct Store {
+    db: redb::Database,
+    tree: RwLock<TransferTree<TREE_HEIGHT>>,
+}
+
+impl Store {
+    /// Open or create the store, rebuilding the tree from the stored leaves
+    pub fn open(path: &str) -> anyhow::Result<Self> {
+        let db = redb::Database::create(path)?;
+        let mut tree = TransferTree::<TREE_HEIGHT>::new();
+
+        let write_txn = db.begin_write()?;
+        {
+            write_txn.open_table(LEAVES)?;
+            write_txn.open_table(EPHEMERAL_KEYS)?;
+            write_txn.open_table(CURSOR)?;
+            let mut roots = write_txn.open_table(ROOTS)?;
+            if roots.get(0)?.is_none() {
+                roots.insert(0, tree.root())?;
+            }
+        }
+        write_txn.commit()?;
+
+        let read_txn = db.begin_read()?;
+        {
+            let leaves = read_txn.open_table(LEAVES)?;
+            let roots = read_txn.open_table(ROOTS)?;
+            for result in leaves.iter()? {
+                let (index, leaf) = result?;
+                let (to, amount, _) = leaf.value();
+                tree.append_transfer(to, amount)?;
+                let stored = roots.get(index.value() + 1)?.map(|r| r.value());
+                anyhow::ensure!(
+                    stored == Some(tree.root()),
+                    "stored root for {} leaves does not match the rebuilt tree",
+                    index.value() + 1
+                );
+            }
+        }
+        tracing::info!("Loaded {} leaves", tree.next_index());
+
+        Ok(Self {
+            db,
+            tree: RwLock::new(tree),
+        })
+    }
+
+    /// Signature of the newest transaction whose transfers are in the store
+    pub fn last_signature(&self) -> anyhow::Result<Option<String>> {
+        let read_txn = self.db.begin_read()?;
+        let cursor = read_txn.open_table(CURSOR)?;
+        Ok(cursor.get(LAST_SIGNATURE)?.map(|s| s.value().to_string()))
+    }
+
+    /// Append the transfers from transactions up to and including `signature`.
+    /// The leaves, their roots and the cursor are committed together so a restart never double counts
+    pub fn append(&self, new_leaves: &[NewLeaf], signature: &str) -> anyhow::Result<()> {
+        let mut tree = self.tree.write().unwrap();
+        let mut updated = tree.clone();
+
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut leaves = write_txn.open_table(LEAVES)?;
+            let mut ephemeral_keys = write_txn.open_table(EPHEMERAL_KEYS)?;
+            let mut roots = write_txn.open_table(ROOTS)?;
+            for leaf in new_leaves {
+                let index = updated.next_index();
+                updated.append_transfer(leaf.to, leaf.amount)?;
+                leaves.insert(index, (leaf.to, leaf.amount, leaf.slot))?;
+                if let Some(key) = leaf.ephemeral_key {
+                    ephemeral_keys.insert(index, key)?;
+                }
+                roots.insert(index + 1, updated.root())?;
+            }
+            let mut cursor = write_txn.open_table(CURSOR)?;
+            cursor.insert(LAST_SIGNATURE, signature)?;
+        }
+        write_txn.commit()?;
+
+        *tree = updated;
+        Ok(())
+    }
+
+    pub fn next_index(&self) -> u64 {
+        self.tree.read().unwrap().next_index()
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.tree.read().unwrap().root()
+    }
+
+    pub fn frontier(&self) -> Frontier {
+        let tree = self.tree.read().unwrap();
+        Frontier {
+            next_index: tree.next_index(),
+            root: tree.root(),
+            frontier: tree.frontier().iter().copied().map(Hash).collect(),
+        }
+    }
+
+    pub fn leaves(&self, range: Range<u64>) -> anyhow::Result<Vec<IndexedLeaf>> {
+        let read_txn = self.db.begin_read()?;
+        let leaves = read_txn.open_table(LEAVES)?;
+        let ephemeral_keys = read_txn.open_table(EPHEMERAL_KEYS)?;
+
+        let mut out = Vec::new();
+        for result in leaves.range(range)? {
+            let (index, leaf) = result?;
+            let (to, amount, slot) = leaf.value();
+            out.push(IndexedLeaf {
+                index: index.value(),
+                slot,
+                to,
+                amount,
+                ephemeral_key: ephemeral_keys.get(index.value())?.map(|k| k.value()),
+            });
+        }
+        Ok(out)
+    }
+
+    pub fn roots(&self, range: Range<u64>) -> anyhow::Result<Vec<RootEntry>> {
+        let read_txn = self.db.begin_read()?;
+        let roots = read_txn.open_table(ROOTS)?;
+
+        let mut out = Vec::new();
+        for result in roots.range(range)? {
+            let (next_index, root) = result?;
+            out.push(RootEntry {
+                next_index: next_index.value(),
+                root: root.value(),
+            });
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn leaf(i: u64) -> NewLeaf {
+        NewLeaf {
+            slot: 100 + i,
+            to: [i as u8; 32],
+            amount: i * 10,
+            ephemeral_key: (i % 2 == 1).then_some([7; 32]),
+        }
+    }
+
+    #[test]
+    fn test_append_and_reopen() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let path = file.path().to_str().unwrap();
+
+        let store = Store::open(path).unwrap();
+        assert_eq!(store.last_signature().unwrap(), None);
+        store.append(&[leaf(0), leaf(1)], "sig-1").unwrap();
+        store.append(&[leaf(2)], "sig-2").unwrap();
+
+        let mut expected = TransferTree::<TREE_HEIGHT>::new();
+        for i in 0..3 {
+            expected.append_transfer([i as u8; 32], i * 10).unwrap();
+        }
+        assert_eq!(store.root(), expected.root());
+
+        let leaves = store.leaves(1..3).unwrap();
+        assert_eq!(leaves.len(), 2);
+        assert_eq!(leaves[0].to, [1; 32]);
+        assert_eq!(leaves[0].ephemeral_key, Some([7; 32]));
+        assert_eq!(leaves[1].ephemeral_key, None);
+
+        let roots = store.roots(0..10).unwrap();
+        assert_eq!(roots.len(), 4);
+        assert_eq!(roots[3].root, expected.root());
+        drop(store);
+
+        let store = Store::open(path).unwrap();
+        assert_eq!(store.next_index(), 3);
+        assert_eq!(store.root(), expected.root());
+        assert_eq!(
+            store.frontier().frontier[0].0,
+            *expected.frontier().first().unwrap()
+        );
+        assert_eq!(store.last_signature().unwrap().as_deref(), Some("sig-2"));
+    }
+}
@@ -0,0 +1,1231 @@
+// This file was autogenerated by some hot garbage in the `uniffi` crate.
+// Trust me, you don't want to mess with it!
+
+// swiftlint:disable all
+import Foundation
+
+// Depending on the consumer's build setup, the low-level FFI code
+// might be in a separate module, or it might be compiled inline into
+// this module. This is a bit of light hackery to work with both.
+#if canImport(vapor_mobileFFI)
+import vapor_mobileFFI
+#endif
+
+fileprivate extension RustBuffer {
+    // Allocate a new buffer, copying the contents of a `UInt8` array.
+    init(bytes: [UInt8]) {
+        let rbuf = bytes.withUnsafeBufferPointer { ptr in
+            RustBuffer.from(ptr)
+        }
+        self.init(capacity: rbuf.capacity, len: rbuf.len, data: rbuf.data)
+    }
+
+    static func empty() -> RustBuffer {
+        RustBuffer(capacity: 0, len:0, data: nil)
+    }
+
+    static func from(_ ptr: UnsafeBufferPointer<UInt8>) -> RustBuffer {
+        try! rustCall { ffi_vapor_mobile_rustbuffer_from_bytes(ForeignBytes(bufferPointer: ptr), $0) }
+    }
+
+    // Frees the buffer in place.
+    // The buffer must not be used after this is called.
+    func deallocate() {
+        try! rustCall { ffi_vapor_mobile_rustbuffer_free(self, $0) }
+    }
+}
+
+fileprivate extension ForeignBytes {
+    init(bufferPointer: UnsafeBufferPointer<UInt8>) {
+        self.init(len: Int32(bufferPointer.count), data: bufferPointer.baseAddress)
+    }
+}
+
+// For every type used in the interface, we provide helper methods for conveniently
+// lifting and lowering that type from C-compatible data, and for reading and writing
+// values of that type in a buffer.
+
+// Helper classes/extensions that don't change.
+// Someday, this will be in a library of its own.
+
+fileprivate extension Data {
+    init(rustBuffer: RustBuffer) {
+        self.init(
+            bytesNoCopy: rustBuffer.data!,
+            count: Int(rustBuffer.len),
+            deallocator: .none
+        )
+    }
+}
+
+// Define reader functionality.  Normally this would be defined in a class or
+// struct, but we use standalone functions instead in order to make external
+// types work.
+//
+// With external types, one swift source file needs to be able to call the read
+// method on another source file's FfiConverter, but then what visibility
+// should Reader have?
+// - If Reader is fileprivate, then this means the read() must also
+//   be fileprivate, which doesn't work with external types.
+// - If Reader is internal/public, we'll get compile errors since both source
+//   files will try define the same type.
+//
+// Instead, the read() method and these helper functions input a tuple of data
+
+fileprivate func createReader(data: Data) -> (data: Data, offset: Data.Index) {
+    (data: data, offset: 0)
+}
+
+// Reads an integer at the current offset, in big-endian order, and advances
+// the offset on success. Throws if reading the integer would move the
+// offset past the end of the buffer.
+fileprivate func readInt<T: FixedWidthInteger>(_ reader: inout (data: Data, offset: Data.Index)) throws -> T {
+    let range = reader.offset..<reader.offset + MemoryLayout<T>.size
+    guard reader.data.count >= range.upperBound else {
+        throw UniffiInternalError.bufferOverflow
+    }
+    if T.self == UInt8.self {
+        let value = reader.data[reader.offset]
+        reader.offset += 1
+        return value as! T
+    }
+    var value: T = 0
+    let _ = withUnsafeMutableBytes(of: &value, { reader.data.copyBytes(to: $0, from: range)})
+    reader.offset = range.upperBound
+    return value.bigEndian
+}
+
+// Reads an arbitrary number of bytes, to be used to read
+// raw bytes, this is useful when lifting strings
+fileprivate func readBytes(_ reader: inout (data: Data, offset: Data.Index), count: Int) throws -> Array<UInt8> {
+    let range = reader.offset..<(reader.offset+count)
+    guard reader.data.count >= range.upperBound else {
+        throw UniffiInternalError.bufferOverflow
+    }
+    var value = [UInt8](repeating: 0, count: count)
+    value.withUnsafeMutableBufferPointer({ buffer in
+        reader.data.copyBytes(to: buffer, from: range)
+    })
+    reader.offset = range.upperBound
+    return value
+}
+
+// Reads a float at the current offset.
+fileprivate func readFloat(_ reader: inout (data: Data, offset: Data.Index)) throws -> Float {
+    return Float(bitPattern: try readInt(&reader))
+}
+
+// Reads a float at the current offset.
+fileprivate func readDouble(_ reader: inout (data: Data, offset: Data.Index)) throws -> Double {
+    return Double(bitPattern: try readInt(&reader))
+}
+
+// Indicates if the offset has reached the end of the buffer.
+fileprivate func hasRemaining(_ reader: (data: Data, offset: Data.Index)) -> Bool {
+    return reader.offset < reader.data.count
+}
+
+// Define writer functionality.  Normally this would be defined in a class or
+// struct, but we use standalone functions instead in order to make external
+// types work.  See the above discussion on Readers for details.
+
+fileprivate func createWriter() -> [UInt8] {
+    return []
+}
+
+fileprivate func writeBytes<S>(_ writer: inout [UInt8], _ byteArr: S) where S: Sequence, S.Element == UInt8 {
+    writer.append(contentsOf: byteArr)
+}
+
+// Writes an integer in big-endian order.
+//
+// Warning: make sure what you are trying to write
+// is in the correct type!
+fileprivate func writeInt<T: FixedWidthInteger>(_ writer: inout [UInt8], _ value: T) {
+    var value = value.bigEndian
+    withUnsafeBytes(of: &value) { writer.append(contentsOf: $0) }
+}
+
+fileprivate func writeFloat(_ writer: inout [UInt8], _ value: Float) {
+    writeInt(&writer, value.bitPattern)
+}
+
+fileprivate func writeDouble(_ writer: inout [UInt8], _ value: Double) {
+    writeInt(&writer, value.bitPattern)
+}
+
+// Protocol for types that transfer other types across the FFI. This is
+// analogous to the Rust trait of the same name.
+fileprivate protocol FfiConverter {
+    associatedtype FfiType
+    associatedtype SwiftType
+
+    static func lift(_ value: FfiType) throws -> SwiftType
+    static func lower(_ value: SwiftType) -> FfiType
+    static func read(from buf: inout (data: Data, offset: Data.Index)) throws -> SwiftType
+    static func write(_ value: SwiftType, into buf: inout [UInt8])
+}
+
+// Types conforming to `Primitive` pass themselves directly over the FFI.
+fileprivate protocol FfiConverterPrimitive: FfiConverter where FfiType == SwiftType { }
+
+extension FfiConverterPrimitive {
+#if swift(>=5.8)
+    @_documentation(visibility: private)
+#endif
+    public static func lift(_ value: FfiType) throws -> SwiftType {
+        return value
+    }
+
+#if swift(>=5.8)
+    @_documentation(visibility: private)
+#endif
+    public static func lower(_ value: SwiftType) -> FfiType {
+        return value
+    }
+}
+
+// Types conforming to `FfiConverterRustBuffer` lift and lower into a `RustBuffer`.
+// Used for complex types where it's hard to write a custom lift/lower.
+fileprivate protocol FfiConverterRustBuffer: FfiConverter where FfiType == RustBuffer {}
+
+extension FfiConverterRustBuffer {
+#if swift(>=5.8)
+    @_documentation(visibility: private)
+#endif
+    public static func lift(_ buf: RustBuffer) throws -> SwiftType {
+        var reader = createReader(data: Data(rustBuffer: buf))
+        let value = try read(from: &reader)
+        if hasRemaining(reader) {
+            throw UniffiInternalError.incompleteData
+        }
+        buf.deallocate()
+        return value
+    }
+
+#if swift(>=5.8)
+    @_documentation(visibility: private)
+#endif
+    public static func lower(_ value: SwiftType) -> RustBuffer {
+          var writer = createWriter()
+          write(value, into: &writer)
+          return RustBuffer(bytes: writer)
+    }
+}
+// An error type for FFI errors. These errors occur at the UniFFI level, not
+// the library level.
+fileprivate enum UniffiInternalError: LocalizedError {
+    case bufferOverflow
+    case incompleteData
+    case unexpectedOptionalTag
+    case unexpectedEnumCase
+    case unexpectedNullPointer
+    case unexpectedRustCallStatusCode
+    case unexpectedRustCallError
+    case unexpectedStaleHandle
+    case rustPanic(_ message: String)
+
+    public var errorDescription: String? {
+        switch self {
+        case .bufferOverflow: return "Reading the requested value would read past the end of the buffer"
+        case .incompleteData: return "The buffer still has data after lifting its containing value"
+        case .unexpectedOptionalTag: return "Unexpected optional tag; should be 0 or 1"
+        case .unexpectedEnumCase: return "Raw enum value doesn't match any cases"
+        case .unexpectedNullPointer: return "Raw pointer value was null"
+        case .unexpectedRustCallStatusCode: return "Unexpected RustCallStatus code"
+        case .unexpectedRustCallError: return "CALL_ERROR but no errorClass specified"
+        case .unexpectedStaleHandle: return "The object in the handle map has been dropped already"
+        case let .rustPanic(message): return message
+        }
+    }
+}
+
+fileprivate extension NSLock {
+    func withLock<T>(f: () throws -> T) rethrows -> T {
+        self.lock()
+        defer { self.unlock() }
+        return try f()
+    }
+}
+
+fileprivate let CALL_SUCCESS: Int8 = 0
+fileprivate let CALL_ERROR: Int8 = 1
+fileprivate let CALL_UNEXPECTED_ERROR: Int8 = 2
+fileprivate let CALL_CANCELLED: Int8 = 3
+
+fileprivate extension RustCallStatus {
+    init() {
+        self.init(
+            code: CALL_SUCCESS,
+            errorBuf: RustBuffer.init(
+                capacity: 0,
+                len: 0,
+                data: nil
+            )
+        )
+    }
+}
+
+private func rustCall<T>(_ callback: (UnsafeMutablePointer<RustCallStatus>) -> T) throws -> T {
+    let neverThrow: ((RustBuffer) throws -> Never)? = nil
+    return try makeRustCall(callback, errorHandler: neverThrow)
+}
+
+private func rustCallWithError<T, E: Swift.Error>(
+    _ errorHandler: @escaping (RustBuffer) throws -> E,
+    _ callback: (UnsafeMutablePointer<RustCallStatus>) -> T) throws -> T {
+    try makeRustCall(callback, errorHandler: errorHandler)
+}
+
+private func makeRustCall<T, E: Swift.Error>(
+    _ callback: (UnsafeMutablePointer<RustCallStatus>) -> T,
+    errorHandler: ((RustBuffer) throws -> E)?
+) throws -> T {
+    uniffiEnsureInitialized()
+    var callStatus = RustCallStatus.init()
+    let returnedVal = callback(&callStatus)
+    try uniffiCheckCallStatus(callStatus: callStatus, errorHandler: errorHandler)
+    return returnedVal
+}
+
+private func uniffiCheckCallStatus<E: Swift.Error>(
+    callStatus: RustCallStatus,
+    errorHandler: ((RustBuffer) throws -> E)?
+) throws {
+    switch callStatus.code {
+        case CALL_SUCCESS:
+            return
+
+        case CALL_ERROR:
+            if let errorHandler = errorHandler {
+                throw try errorHandler(callStatus.errorBuf)
+            } else {
+                callStatus.errorBuf.deallocate()
+                throw UniffiInternalError.unexpectedRustCallError
+            }
+
+        case CALL_UNEXPECTED_ERROR:
+            // When the rust code sees a panic, it tries to construct a RustBuffer
+            // with the message.  But if that code panics, then it just sends back
+            // an empty buffer.
+            if callStatus.errorBuf.len > 0 {
+                throw UniffiInternalError.rustPanic(try FfiConverterString.lift(callStatus.errorBuf))
+            } else {
+                callStatus.errorBuf.deallocate()
+                throw UniffiInternalError.rustPanic("Rust panic")
+            }
+
+        case CALL_CANCELLED:
+            fatalError("Cancellation not supported yet")
+
+        default:
+            throw UniffiInternalError.unexpectedRustCallStatusCode
+    }
+}
+
+private func uniffiTraitInterfaceCall<T>(
+    callStatus: UnsafeMutablePointer<RustCallStatus>,
+    makeCall: () throws -> T,
+    writeReturn: (T) -> ()
+) {
+    do {
+        try writeReturn(makeCall())
+    } catch let error {
+        callStatus.pointee.code = CALL_UNEXPECTED_ERROR
+        callStatus.pointee.errorBuf = FfiConverterString.lower(String(describing: error))
+    }
+}
+
+private func uniffiTraitInterfaceCallWithError<T, E>(
+    callStatus: UnsafeMutablePointer<RustCallStatus>,
+    makeCall: () throws -> T,
+    writeReturn: (T) -> (),
+    lowerError: (E) -> RustBuffer
+) {
+    do {
+        try writeReturn(makeCall())
+    } catch let error as E {
+        callStatus.pointee.code = CALL_ERROR
+        callStatus.pointee.errorBuf = lowerError(error)
+    } catch {
+        callStatus.pointee.code = CALL_UNEXPECTED_ERROR
+        callStatus.pointee.errorBuf = FfiConverterString.lower(String(describing: error))
+    }
+}
+fileprivate class UniffiHandleMap<T> {
+    private var map: [UInt64: T] = [:]
+    private let lock = NSLock()
+    private var currentHandle: UInt64 = 1
+
+    func insert(obj: T) -> UInt64 {
+        lock.withLock {
+            let handle = currentHandle
+            currentHandle += 1
+            map[handle] = obj
+            return handle
+        }
+    }
+
+     func get(handle: UInt64) throws -> T {
+        try lock.withLock {
+            guard let obj = map[handle] else {
+                throw UniffiInternalError.unexpectedStaleHandle
+            }
+            return obj
+        }
+    }
+
+    @discardableResult
+    func remove(handle: UInt64) throws -> T {
+        try lock.withLock {
+            guard let obj = map.removeValue(forKey: handle) else {
+                throw UniffiInternalError.unexpectedStaleHandle
+            }
+            return obj
+        }
+    }
+
+    var count: Int {
+        get {
+            map.count
+        }
+    }
+}
+
+
+// Public interface members begin here.
+
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+fileprivate struct FfiConverterUInt64: FfiConverterPrimitive {
+    typealias FfiType = UInt64
+    typealias SwiftType = UInt64
+
+    public static func read(from buf: inout (data: Data, offset: Data.Index)) throws -> UInt64 {
+        return try lift(readInt(&buf))
+    }
+
+    public static func write(_ value: SwiftType, into buf: inout [UInt8]) {
+        writeInt(&buf, lower(value))
+    }
+}
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+fileprivate struct FfiConverterBool : FfiConverter {
+    typealias FfiType = Int8
+    typealias SwiftType = Bool
+
+    public static func lift(_ value: Int8) throws -> Bool {
+        return value != 0
+    }
+
+    public static func lower(_ value: Bool) -> Int8 {
+        return value ? 1 : 0
+    }
+
+    public static func read(from buf: inout (data: Data, offset: Data.Index)) throws -> Bool {
+        return try lift(readInt(&buf))
+    }
+
+    public static func write(_ value: Bool, into buf: inout [UInt8]) {
+        writeInt(&buf, lower(value))
+    }
+}
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+fileprivate struct FfiConverterString: FfiConverter {
+    typealias SwiftType = String
+    typealias FfiType = RustBuffer
+
+    public static func lift(_ value: RustBuffer) throws -> String {
+        defer {
+            value.deallocate()
+        }
+        if value.data == nil {
+            return String()
+        }
+        let bytes = UnsafeBufferPointer<UInt8>(start: value.data!, count: Int(value.len))
+        return String(bytes: bytes, encoding: String.Encoding.utf8)!
+    }
+
+    public static func lower(_ value: String) -> RustBuffer {
+        return value.utf8CString.withUnsafeBufferPointer { ptr in
+            // The swift string gives us int8_t, we want uint8_t.
+            ptr.withMemoryRebound(to: UInt8.self) { ptr in
+                // The swift string gives us a trailing null byte, we don't want it.
+                let buf = UnsafeBufferPointer(rebasing: ptr.prefix(upTo: ptr.count - 1))
+                return RustBuffer.from(buf)
+            }
+        }
+    }
+
+    public static func read(from buf: inout (data: Data, offset: Data.Index)) throws -> String {
+        let len: Int32 = try readInt(&buf)
+        return String(bytes: try readBytes(&buf, count: Int(len)), encoding: String.Encoding.utf8)!
+    }
+
+    public static func write(_ value: String, into buf: inout [UInt8]) {
+        let len = Int32(value.utf8.count)
+        writeInt(&buf, len)
+        writeBytes(&buf, value.utf8)
+    }
+}
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+fileprivate struct FfiConverterData: FfiConverterRustBuffer {
+    typealias SwiftType = Data
+
+    public static func read(from buf: inout (data: Data, offset: Data.Index)) throws -> Data {
+        let len: Int32 = try readInt(&buf)
+        return Data(try readBytes(&buf, count: Int(len)))
+    }
+
+    public static func write(_ value: Data, into buf: inout [UInt8]) {
+        let len = Int32(value.count)
+        writeInt(&buf, len)
+        writeBytes(&buf, value)
+    }
+}
+
+
+
+
+/**
+ * Inputs for the `condenser` circuit
+ */
+public protocol CondenserWitnessProtocol : AnyObject {
+    
+    /**
+     * The same inputs as a JSON object
+     */
+    func toJson()  -> String
+    
+    /**
+     * Prover.toml contents for nargo
+     */
+    func toToml()  -> String
+    
+}
+
+/**
+ * Inputs for the `condenser` circuit
+ */
+open class CondenserWitness:
+    CondenserWitnessProtocol {
+    fileprivate let pointer: UnsafeMutableRawPointer!
+
+    /// Used to instantiate a [FFIObject] without an actual pointer, for fakes in tests, mostly.
+#if swift(>=5.8)
+    @_documentation(visibility: private)
+#endif
+    public struct NoPointer {
+        public init() {}
+    }
+
+    // TODO: We'd like this to be `private` but for Swifty reasons,
+    // we can't implement `FfiConverter` without making this `required` and we can't
+    // make it `required` without making it `public`.
+    required public init(unsafeFromRawPointer pointer: UnsafeMutableRawPointer) {
+        self.pointer = pointer
+    }
+
+    // This constructor can be used to instantiate a fake object.
+    // - Parameter noPointer: Placeholder value so we can have a constructor separate from the default empty one that may be implemented for classes extending [FFIObject].
+    //
+    // - Warning:
+    //     Any object instantiated with this constructor cannot be passed to an actual Rust-backed object. Since there isn't a backing [Pointer] the FFI lower functions will crash.
+#if swift(>=5.8)
+    @_documentation(visibility: private)
+#endif
+    public init(noPointer: NoPointer) {
+        self.pointer = nil
+    }
+
+#if swift(>=5.8)
+    @_documentation(visibility: private)
+#endif
+    public func uniffiClonePointer() -> UnsafeMutableRawPointer {
+        return try! rustCall { uniffi_vapor_mobile_fn_clone_condenserwitness(self.pointer, $0) }
+    }
+    /**
+     * Witness for condensing the deposit of `deposit_amount` to `vapor_addr` proven by `proof`.
+     * `amount` is the recipient's total withdrawn once this condense is applied, at most `deposit_amount`
+     */
+public convenience init(recipient: String, amount: UInt64, vaporAddr: String, depositAmount: UInt64, proof: MerkleProof, secret: String)throws  {
+    let pointer =
+        try rustCallWithError(FfiConverterTypeVaporError.lift) {
+    uniffi_vapor_mobile_fn_constructor_condenserwitness_new(
+        FfiConverterString.lower(recipient),
+        FfiConverterUInt64.lower(amount),
+        FfiConverterString.lower(vaporAddr),
+        FfiConverterUInt64.lower(depositAmount),
+        FfiConverterTypeMerkleProof.lower(proof),
+        FfiConverterString.lower(secret),$0
+    )
+}
+    self.init(unsafeFromRawPointer: pointer)
+}
+
+    deinit {
+        guard let pointer = pointer else {
+            return
+        }
+
+        try! rustCall { uniffi_vapor_mobile_fn_free_condenserwitness(pointer, $0) }
+    }
+
+    
+
+    
+    /**
+     * The same inputs as a JSON object
+     */
+open func toJson() -> String {
+    return try!  FfiConverterString.lift(try! rustCall() {
+    uniffi_vapor_mobile_fn_method_condenserwitness_to_json(self.uniffiClonePointer(),$0
+    )
+})
+}
+    
+    /**
+     * Prover.toml contents for nargo
+     */
+open func toToml() -> String {
+    return try!  FfiConverterString.lift(try! rustCall() {
+    uniffi_vapor_mobile_fn_method_condenserwitness_to_toml(self.uniffiClonePointer(),$0
+    )
+})
+}
+    
+
+}
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+public struct FfiConverterTypeCondenserWitness: FfiConverter {
+
+    typealias FfiType = UnsafeMutableRawPointer
+    typealias SwiftType = CondenserWitness
+
+    public static func lift(_ pointer: UnsafeMutableRawPointer) throws -> CondenserWitness {
+        return CondenserWitness(unsafeFromRawPointer: pointer)
+    }
+
+    public static func lower(_ value: CondenserWitness) -> UnsafeMutableRawPointer {
+        return value.uniffiClonePointer()
+    }
+
+    public static func read(from buf: inout (data: Data, offset: Data.Index)) throws -> CondenserWitness {
+        let v: UInt64 = try readInt(&buf)
+        // The Rust code won't compile if a pointer won't fit in a UInt64.
+        // We have to go via `UInt` because that's the thing that's the size of a pointer.
+        let ptr = UnsafeMutableRawPointer(bitPattern: UInt(truncatingIfNeeded: v))
+        if (ptr == nil) {
+            throw UniffiInternalError.unexpectedNullPointer
+        }
+        return try lift(ptr!)
+    }
+
+    public static func write(_ value: CondenserWitness, into buf: inout [UInt8]) {
+        // This fiddling is because `Int` is the thing that's the same size as a pointer.
+        // The Rust code won't compile if a pointer won't fit in a `UInt64`.
+        writeInt(&buf, UInt64(bitPattern: Int64(Int(bitPattern: lower(value)))))
+    }
+}
+
+
+
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+public func FfiConverterTypeCondenserWitness_lift(_ pointer: UnsafeMutableRawPointer) throws -> CondenserWitness {
+    return try FfiConverterTypeCondenserWitness.lift(pointer)
+}
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+public func FfiConverterTypeCondenserWitness_lower(_ value: CondenserWitness) -> UnsafeMutableRawPointer {
+    return FfiConverterTypeCondenserWitness.lower(value)
+}
+
+
+
+
+/**
+ * The transfer tree of a mint, rebuilt by appending its leaves in order.
+ * Inclusion proofs are kept for tracked leaves, usually the deposits to the wallet's own addresses
+ */
+public protocol TransferTreeProtocol : AnyObject {
+    
+    /**
+     * Append a leaf and return its index. `track` keeps an inclusion proof for it
+     */
+    func append(leaf: String, track: Bool) throws  -> UInt64
+    
+    /**
+     * Append the leaf for a transfer of `amount` to `destination` and return its index
+     */
+    func appendTransfer(destination: String, amount: UInt64, track: Bool) throws  -> UInt64
+    
+    /**
+     * Inclusion proof of a tracked leaf against the current root
+     */
+    func merkleProof(index: UInt64) throws  -> MerkleProof
+    
+    /**
+     * Number of leaves in the tree
+     */
+    func nextIndex()  -> UInt64
+    
+    func root()  -> String
+    
+}
+
+/**
+ * The transfer tree of a mint, rebuilt by appending its leaves in order.
+ * Inclusion proofs are kept for tracked leaves, usually the deposits to the wallet's own addresses
+ */
+open class TransferTree:
+    TransferTreeProtocol {
+    fileprivate let pointer: UnsafeMutableRawPointer!
+
+    /// Used to instantiate a [FFIObject] without an actual pointer, for fakes in tests, mostly.
+#if swift(>=5.8)
+    @_documentation(visibility: private)
+#endif
+    public struct NoPointer {
+        public init() {}
+    }
+
+    // TODO: We'd like this to be `private` but for Swifty reasons,
+    // we can't implement `FfiConverter` without making this `required` and we can't
+    // make it `required` without making it `public`.
+    required public init(unsafeFromRawPointer pointer: UnsafeMutableRawPointer) {
+        self.pointer = pointer
+    }
+
+    // This constructor can be used to instantiate a fake object.
+    // - Parameter noPointer: Placeholder value so we can have a constructor separate from the default empty one that may be implemented for classes extending [FFIObject].
+    //
+    // - Warning:
+    //     Any object instantiated with this constructor cannot be passed to an actual Rust-backed object. Since there isn't a backing [Pointer] the FFI lower functions will crash.
+#if swift(>=5.8)
+    @_documentation(visibility: private)
+#endif
+    public init(noPointer: NoPointer) {
+        self.pointer = nil
+    }
+
+#if swift(>=5.8)
+    @_documentation(visibility: private)
+#endif
+    public func uniffiClonePointer() -> UnsafeMutableRawPointer {
+        return try! rustCall { uniffi_vapor_mobile_fn_clone_transfertree(self.pointer, $0) }
+    }
+public convenience init() {
+    let pointer =
+        try! rustCall() {
+    uniffi_vapor_mobile_fn_constructor_transfertree_new($0
+    )
+}
+    self.init(unsafeFromRawPointer: pointer)
+}
+
+    deinit {
+        guard let pointer = pointer else {
+            return
+        }
+
+        try! rustCall { uniffi_vapor_mobile_fn_free_transfertree(pointer, $0) }
+    }
+
+    
+
+    
+    /**
+     * Append a leaf and return its index. `track` keeps an inclusion proof for it
+     */
+open func append(leaf: String, track: Bool)throws  -> UInt64 {
+    return try  FfiConverterUInt64.lift(try rustCallWithError(FfiConverterTypeVaporError.lift) {
+    uniffi_vapor_mobile_fn_method_transfertree_append(self.uniffiClonePointer(),
+        FfiConverterString.lower(leaf),
+        FfiConverterBool.lower(track),$0
+    )
+})
+}
+    
+    /**
+     * Append the leaf for a transfer of `amount` to `destination` and return its index
+     */
+open func appendTransfer(destination: String, amount: UInt64, track: Bool)throws  -> UInt64 {
+    return try  FfiConverterUInt64.lift(try rustCallWithError(FfiConverterTypeVaporError.lift) {
+    uniffi_vapor_mobile_fn_method_transfertree_append_transfer(self.uniffiClonePointer(),
+        FfiConverterString.lower(destination),
+        FfiConverterUInt64.lower(amount),
+        FfiConverterBool.lower(track),$0
+    )
+})
+}
+    
+    /**
+     * Inclusion proof of a tracked leaf against the current root
+     */
+open func merkleProof(index: UInt64)throws  -> MerkleProof {
+    return try  FfiConverterTypeMerkleProof.lift(try rustCallWithError(FfiConverterTypeVaporError.lift) {
+    uniffi_vapor_mobile_fn_method_transfertree_merkle_proof(self.uniffiClonePointer(),
+        FfiConverterUInt64.lower(index),$0
+    )
+})
+}
+    
+    /**
+     * Number of leaves in the tree
+     */
+open func nextIndex() -> UInt64 {
+    return try!  FfiConverterUInt64.lift(try! rustCall() {
+    uniffi_vapor_mobile_fn_method_transfertree_next_index(self.uniffiClonePointer(),$0
+    )
+})
+}
+    
+open func root() -> String {
+    return try!  FfiConverterString.lift(try! rustCall() {
+    uniffi_vapor_mobile_fn_method_transfertree_root(self.uniffiClonePointer(),$0
+    )
+})
+}
+    
+
+}
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+public struct FfiConverterTypeTransferTree: FfiConverter {
+
+    typealias FfiType = UnsafeMutableRawPointer
+    typealias SwiftType = TransferTree
+
+    public static func lift(_ pointer: UnsafeMutableRawPointer) throws -> TransferTree {
+        return TransferTree(unsafeFromRawPointer: pointer)
+    }
+
+    public static func lower(_ value: TransferTree) -> UnsafeMutableRawPointer {
+        return value.uniffiClonePointer()
+    }
+
+    public static func read(from buf: inout (data: Data, offset: Data.Index)) throws -> TransferTree {
+        let v: UInt64 = try readInt(&buf)
+        // The Rust code won't compile if a pointer won't fit in a UInt64.
+        // We have to go via `UInt` because that's the thing that's the size of a pointer.
+        let ptr = UnsafeMutableRawPointer(bitPattern: UInt(truncatingIfNeeded: v))
+        if (ptr == nil) {
+            throw UniffiInternalError.unexpectedNullPointer
+        }
+        return try lift(ptr!)
+    }
+
+    public static func write(_ value: TransferTree, into buf: inout [UInt8]) {
+        // This fiddling is because `Int` is the thing that's the same size as a pointer.
+        // The Rust code won't compile if a pointer won't fit in a `UInt64`.
+        writeInt(&buf, UInt64(bitPattern: Int64(Int(bitPattern: lower(value)))))
+    }
+}
+
+
+
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+public func FfiConverterTypeTransferTree_lift(_ pointer: UnsafeMutableRawPointer) throws -> TransferTree {
+    return try FfiConverterTypeTransferTree.lift(pointer)
+}
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+public func FfiConverterTypeTransferTree_lower(_ value: TransferTree) -> UnsafeMutableRawPointer {
+    return FfiConverterTypeTransferTree.lower(value)
+}
+
+
+/**
+ * Inclusion proof of a leaf in the transfer tree
+ */
+public struct MerkleProof {
+    public var index: UInt64
+    /**
+     * Root of the tree when the proof was taken
+     */
+    public var root: String
+    /**
+     * Sibling at each level, starting from the leaf
+     */
+    public var siblings: [String]
+    /**
+     * Position of the node at each level relative to its sibling, 0 for left and 1 for right
+     */
+    public var pathIndices: Data
+
+    // Default memberwise initializers are never public by default, so we
+    // declare one manually.
+    public init(index: UInt64, 
+        /**
+         * Root of the tree when the proof was taken
+         */root: String, 
+        /**
+         * Sibling at each level, starting from the leaf
+         */siblings: [String], 
+        /**
+         * Position of the node at each level relative to its sibling, 0 for left and 1 for right
+         */pathIndices: Data) {
+        self.index = index
+        self.root = root
+        self.siblings = siblings
+        self.pathIndices = pathIndices
+    }
+}
+
+
+
+extension MerkleProof: Equatable, Hashable {
+    public static func ==(lhs: MerkleProof, rhs: MerkleProof) -> Bool {
+        if lhs.index != rhs.index {
+            return false
+        }
+        if lhs.root != rhs.root {
+            return false
+        }
+        if lhs.siblings != rhs.siblings {
+            return false
+        }
+        if lhs.pathIndices != rhs.pathIndices {
+            return false
+        }
+        return true
+    }
+
+    public func hash(into hasher: inout Hasher) {
+        hasher.combine(index)
+        hasher.combine(root)
+        hasher.combine(siblings)
+        hasher.combine(pathIndices)
+    }
+}
+
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+public struct FfiConverterTypeMerkleProof: FfiConverterRustBuffer {
+    public static func read(from buf: inout (data: Data, offset: Data.Index)) throws -> MerkleProof {
+        return
+            try MerkleProof(
+                index: FfiConverterUInt64.read(from: &buf), 
+                root: FfiConverterString.read(from: &buf), 
+                siblings: FfiConverterSequenceString.read(from: &buf), 
+                pathIndices: FfiConverterData.read(from: &buf)
+        )
+    }
+
+    public static func write(_ value: MerkleProof, into buf: inout [UInt8]) {
+        FfiConverterUInt64.write(value.index, into: &buf)
+        FfiConverterString.write(value.root, into: &buf)
+        FfiConverterSequenceString.write(value.siblings, into: &buf)
+        FfiConverterData.write(value.pathIndices, into: &buf)
+    }
+}
+
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+public func FfiConverterTypeMerkleProof_lift(_ buf: RustBuffer) throws -> MerkleProof {
+    return try FfiConverterTypeMerkleProof.lift(buf)
+}
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+public func FfiConverterTypeMerkleProof_lower(_ value: MerkleProof) -> RustBuffer {
+    return FfiConverterTypeMerkleProof.lower(value)
+}
+
+
+/**
+ * A new vapor address and the secret needed to condense deposits made to it.
+ * Anyone holding the secret can condense deposits to the address
+ */
+public struct VaporizeAddress {
+    public var address: String
+    public var secret: String
+
+    // Default memberwise initializers are never public by default, so we
+    // declare one manually.
+    public init(address: String, secret: String) {
+        self.address = address
+        self.secret = secret
+    }
+}
+
+
+
+extension VaporizeAddress: Equatable, Hashable {
+    public static func ==(lhs: VaporizeAddress, rhs: VaporizeAddress) -> Bool {
+        if lhs.address != rhs.address {
+            return false
+        }
+        if lhs.secret != rhs.secret {
+            return false
+        }
+        return true
+    }
+
+    public func hash(into hasher: inout Hasher) {
+        hasher.combine(address)
+        hasher.combine(secret)
+    }
+}
+
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+public struct FfiConverterTypeVaporizeAddress: FfiConverterRustBuffer {
+    public static func read(from buf: inout (data: Data, offset: Data.Index)) throws -> VaporizeAddress {
+        return
+            try VaporizeAddress(
+                address: FfiConverterString.read(from: &buf), 
+                secret: FfiConverterString.read(from: &buf)
+        )
+    }
+
+    public static func write(_ value: VaporizeAddress, into buf: inout [UInt8]) {
+        FfiConverterString.write(value.address, into: &buf)
+        FfiConverterString.write(value.secret, into: &buf)
+    }
+}
+
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+public func FfiConverterTypeVaporizeAddress_lift(_ buf: RustBuffer) throws -> VaporizeAddress {
+    return try FfiConverterTypeVaporizeAddress.lift(buf)
+}
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+public func FfiConverterTypeVaporizeAddress_lower(_ value: VaporizeAddress) -> RustBuffer {
+    return FfiConverterTypeVaporizeAddress.lower(value)
+}
+
+
+public enum VaporError {
+
+    
+    
+    case InvalidInput(what: String, reason: String
+    )
+    case TreeFull
+    case UntrackedLeaf(index: UInt64
+    )
+}
+
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+public struct FfiConverterTypeVaporError: FfiConverterRustBuffer {
+    typealias SwiftType = VaporError
+
+    public static func read(from buf: inout (data: Data, offset: Data.Index)) throws -> VaporError {
+        let variant: Int32 = try readInt(&buf)
+        switch variant {
+
+        
+
+        
+        case 1: return .InvalidInput(
+            what: try FfiConverterString.read(from: &buf), 
+            reason: try FfiConverterString.read(from: &buf)
+            )
+        case 2: return .TreeFull
+        case 3: return .UntrackedLeaf(
+            index: try FfiConverterUInt64.read(from: &buf)
+            )
+
+         default: throw UniffiInternalError.unexpectedEnumCase
+        }
+    }
+
+    public static func write(_ value: VaporError, into buf: inout [UInt8]) {
+        switch value {
+
+        
+
+        
+        
+        case let .InvalidInput(what,reason):
+            writeInt(&buf, Int32(1))
+            FfiConverterString.write(what, into: &buf)
+            FfiConverterString.write(reason, into: &buf)
+            
+        
+        case .TreeFull:
+            writeInt(&buf, Int32(2))
+        
+        
+        case let .UntrackedLeaf(index):
+            writeInt(&buf, Int32(3))
+            FfiConverterUInt64.write(index, into: &buf)
+            
+        }
+    }
+}
+
+
+extension VaporError: Equatable, Hashable {}
+
+extension VaporError: Foundation.LocalizedError {
+    public var errorDescription: String? {
+        String(reflecting: self)
+    }
+}
+
+#if swift(>=5.8)
+@_documentation(visibility: private)
+#endif
+fileprivate struct FfiConverterSequenceString: FfiConverterRustBuffer {
+    typealias SwiftType = [String]
+
+    public static func write(_ value: [String], into buf: inout [UInt8]) {
+        let len = Int32(value.count)
+        writeInt(&buf, len)
+        for item in value {
+            FfiConverterString.write(item, into: &buf)
+        }
+    }
+
+    public static func read(from buf: inout (data: Data, offset: Data.Index)) throws -> [String] {
+        let len: Int32 = try readInt(&buf)
+        var seq = [String]()
+        seq.reserveCapacity(Int(len))
+        for _ in 0 ..< len {
+            seq.append(try FfiConverterString.read(from: &buf))
+        }
+        return seq
+    }
+}
+/**
+ * Generate a vapor address committing to `recipient`, a base58 Solana address
+ */
+public func generateVaporizeAddress(recipient: String)throws  -> VaporizeAddress {
+    return try  FfiConverterTypeVaporizeAddress.lift(try rustCallWithError(FfiConverterTypeVaporError.lift) {
+    uniffi_vapor_mobile_fn_func_generate_vaporize_address(
+        FfiConverterString.lower(recipient),$0
+    )
+})
+}
+/**
+ * Pack bytes into field elements 31 bytes at a time, as the circuits do
+ */
+public func packBytes(bytes: Data) -> [String] {
+    return try!  FfiConverterSequenceString.lift(try! rustCall() {
+    uniffi_vapor_mobile_fn_func_pack_bytes(
+        FfiConverterData.lower(bytes),$0
+    )
+})
+}
+/**
+ * Leaf the transfer hook records for a transfer of `amount` to `destination`
+ */
+public func transferLeaf(destination: String, amount: UInt64)throws  -> String {
+    return try  FfiConverterString.lift(try rustCallWithError(FfiConverterTypeVaporError.lift) {
+    uniffi_vapor_mobile_fn_func_transfer_leaf(
+        FfiConverterString.lower(destination),
+        FfiConverterUInt64.lower(amount),$0
+    )
+})
+}
+
+private enum InitializationResult {
+    case ok
+    case contractVersionMismatch
+    case apiChecksumMismatch
+}
+// Use a global variable to perform the versioning checks. Swift ensures that
+// the code inside is only computed once.
+private var initializationResult: InitializationResult = {
+    // Get the bindings contract version from our ComponentInterface
+    let bindings_contract_version = 26
+    // Get the scaffolding contract version by calling the into the dylib
+    let scaffolding_contract_version = ffi_vapor_mobile_uniffi_contract_version()
+    if bindings_contract_version != scaffolding_contract_version {
+        return InitializationResult.contractVersionMismatch
+    }
+    if (uniffi_vapor_mobile_checksum_func_generate_vaporize_address() != 40362) {
+        return InitializationResult.apiChecksumMismatch
+    }
+    if (uniffi_vapor_mobile_checksum_func_pack_bytes() != 15072) {
+        return InitializationResult.apiChecksumMismatch
+    }
+    if (uniffi_vapor_mobile_checksum_func_transfer_leaf() != 25830) {
+        return InitializationResult.apiChecksumMismatch
+    }
+    if (uniffi_vapor_mobile_checksum_method_condenserwitness_to_json() != 61340) {
+        return InitializationResult.apiChecksumMismatch
+    }
+    if (uniffi_vapor_mobile_checksum_method_condenserwitness_to_toml() != 2369) {
+        return InitializationResult.apiChecksumMismatch
+    }
+    if (uniffi_vapor_mobile_checksum_method_transfertree_append() != 40014) {
+        return InitializationResult.apiChecksumMismatch
+    }
+    if (uniffi_vapor_mobile_checksum_method_transfertree_append_transfer() != 37081) {
+        return InitializationResult.apiChecksumMismatch
+    }
+    if (uniffi_vapor_mobile_checksum_method_transfertree_merkle_proof() != 16805) {
+        return InitializationResult.apiChecksumMismatch
+    }
+    if (uniffi_vapor_mobile_checksum_method_transfertree_next_index() != 13419) {
+        return InitializationResult.apiChecksumMismatch
+    }
+    if (uniffi_vapor_mobile_checksum_method_transfertree_root() != 7204) {
+        return InitializationResult.apiChecksumMismatch
+    }
+    if (uniffi_vapor_mobile_checksum_constructor_condenserwitness_new() != 47856) {
+        return InitializationResult.apiChecksumMismatch
+    }
+    if (uniffi_vapor_mobile_checksum_constructor_transfertree_new() != 630) {
+        return InitializationResult.apiChecksumMismatch
+    }
+
+    return InitializationResult.ok
+}()
+
+private func uniffiEnsureInitialized() {
+    switch initializationResult {
+    case .ok:
+        break
+    case .contractVersionMismatch:
+        fatalError("UniFFI contract version mismatch: try cleaning and rebuilding your project")
+    case .apiChecksumMismatch:
+        fatalError("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+}
+
+// swiftlint:enable all
\ No newline at end of file
@@ -0,0 +1,429 @@
+//! UniFFI bindings for mobile wallets.
+//!
+//! Exposes vapor address generation, the transfer tree with its inclusion proofs and condenser witness
+//! building to Kotlin and Swift, using the same Poseidon and ed25519 code as the CLI wallet. Invalid input
+//! is reported as a [`VaporError`] rather than a panic.
+//!
+//! Values use the same encodings as the `vapor-wasm` bindings: addresses are base58 strings, leaves, roots
+//! and other field elements are `0x` prefixed big-endian hex strings and spend secrets are decimal strings.
+//!
+//! The generated Kotlin and Swift bindings are checked in under `bindings/`. Regenerate them with
+//! `just mobile_bindings` after changing anything exported here.
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use transfer_tree::Witness;
+use utils::{SpendSecret, fr_to_be_32};
+
+uniffi::setup_scaffolding!();
+
+/// Height of the transfer tree kept by the transfer hook
+pub const TREE_HEIGHT: usize = 26;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error, uniffi::Error)]
+pub enum VaporError {
+    #[error("invalid {what}: {reason}")]
+    InvalidInput { what: String, reason: String },
+    #[error("transfer tree is full")]
+    TreeFull,
+    #[error("leaf {index} is not tracked")]
+    UntrackedLeaf { index: u64 },
+}
+
+impl VaporError {
+    fn invalid(what: &str, reason: impl ToString) -> Self {
+        VaporError::InvalidInput {
+            what: what.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl From<transfer_tree::TreeFull> for VaporError {
+    fn from(_: transfer_tree::TreeFull) -> Self {
+        VaporError::TreeFull
+    }
+}
+
+/// A new vapor address and the secret needed to condense deposits made to it.
+/// Anyone holding the secret can condense deposits to the address
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct VaporizeAddress {
+    pub address: String,
+    pub secret: String,
+}
+
+/// Generate a vapor address committing to `recipient`, a base58 Solana address
+#[uniffi::export]
+pub fn generate_vaporize_address(recipient: String) -> Result<VaporizeAddress, VaporError> {
+    let recipient = parse_address("recipient", &recipient)?;
+    let (address, secret) =
+        vaporize_addresses::generate_vaporize_address(&mut rand::thread_rng(), recipient);
+    Ok(VaporizeAddress {
+        address: bs58::encode(address).into_string(),
+        secret: secret.to_decimal_string().as_str().to_string(),
+    })
+}
+
+/// Pack bytes into field elements 31 bytes at a time, as the circuits do
+#[uniffi::export]
+pub fn pack_bytes(bytes: Vec<u8>) -> Vec<String> {
+    utils::pack_bytes(&bytes)
+        .iter()
+        .map(|field| to_hex(&fr_to_be_32(field)))
+        .collect()
+}
+
+/// Leaf the transfer hook records for a transfer of `amount` to `destination`
+#[uniffi::export]
+pub fn transfer_leaf(destination: String, amount: u64) -> Result<String, VaporError> {
+    let destination = parse_address("destination", &destination)?;
+    Ok(to_hex(&transfer_tree::transfer_leaf(&destination, amount)))
+}
+
+/// Inclusion proof of a leaf in the transfer tree
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct MerkleProof {
+    pub index: u64,
+    /// Root of the tree when the proof was taken
+    pub root: String,
+    /// Sibling at each level, starting from the leaf
+    pub siblings: Vec<String>,
+    /// Position of the node at each level relative to its sibling, 0 for left and 1 for right
+    pub path_indices: Vec<u8>,
+}
+
+struct TreeState {
+    tree: transfer_tree::TransferTree<TREE_HEIGHT>,
+    witnesses: BTreeMap<u64, Witness<TREE_HEIGHT>>,
+}
+
+/// The transfer tree of a mint, rebuilt by appending its leaves in order.
+/// Inclusion proofs are kept for tracked leaves, usually the deposits to the wallet's own addresses
+#[derive(uniffi::Object)]
+pub struct TransferTree {
+    state: Mutex<TreeState>,
+}
+
+#[uniffi::export]
+impl TransferTree {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(TreeState {
+                tree: transfer_tree::TransferTree::new(),
+                witnesses: BTreeMap::new(),
+            }),
+        })
+    }
+
+    pub fn root(&self) -> String {
+        to_hex(&self.lock().tree.root())
+    }
+
+    /// Number of leaves in the tree
+    pub fn next_index(&self) -> u64 {
+        self.lock().tree.next_index()
+    }
+
+    /// Append a leaf and return its index. `track` keeps an inclusion proof for it
+    pub fn append(&self, leaf: String, track: bool) -> Result<u64, VaporError> {
+        let leaf = parse_hex("leaf", &leaf)?;
+        let mut state = self.lock();
+        let path = state.tree.append(leaf)?;
+        for witness in state.witnesses.values_mut() {
+            witness.update(&path);
+        }
+        if track {
+            state.witnesses.insert(path.index, Witness::new(&path));
+        }
+        Ok(path.index)
+    }
+
+    /// Append the leaf for a transfer of `amount` to `destination` and return its index
+    pub fn append_transfer(
+        &self,
+        destination: String,
+        amount: u64,
+        track: bool,
+    ) -> Result<u64, VaporError> {
+        let leaf = transfer_leaf(destination, amount)?;
+        self.append(leaf, track)
+    }
+
+    /// Inclusion proof of a tracked leaf against the current root
+    pub fn merkle_proof(&self, index: u64) -> Result<MerkleProof, VaporError> {
+        let state = self.lock();
+        let witness = state
+            .witnesses
+            .get(&index)
+            .ok_or(VaporError::UntrackedLeaf { index })?;
+        Ok(MerkleProof {
+            index,
+            root: to_hex(&state.tree.root()),
+            siblings: witness.siblings.iter().map(to_hex).collect(),
+            path_indices: witness.path_indices().to_vec(),
+        })
+    }
+}
+
+impl TransferTree {
+    fn lock(&self) -> std::sync::MutexGuard<'_, TreeState> {
+        // Appends can't leave the tree half updated, so a poisoned lock is still usable
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Inputs for the `condenser` circuit
+#[derive(uniffi::Object)]
+pub struct CondenserWitness(condenser_witness::CondenserWitness<TREE_HEIGHT>);
+
+#[uniffi::export]
+impl CondenserWitness {
+    /// Witness for condensing the deposit of `deposit_amount` to `vapor_addr` proven by `proof`.
+    /// `amount` is the recipient's total withdrawn once this condense is applied, at most `deposit_amount`
+    #[uniffi::constructor]
+    pub fn new(
+        recipient: String,
+        amount: u64,
+        vapor_addr: String,
+        deposit_amount: u64,
+        proof: MerkleProof,
+        secret: String,
+    ) -> Result<Arc<Self>, VaporError> {
+        let siblings = proof
+            .siblings
+            .iter()
+            .map(|sibling| parse_hex("merkle proof", sibling))
+            .collect::<Result<Vec<_>, _>>()?
+            .try_into()
+            .map_err(|_| {
+                VaporError::invalid("merkle proof", format!("must have {} levels", TREE_HEIGHT))
+            })?;
+        let path_indices: [u8; TREE_HEIGHT] = proof.path_indices.try_into().map_err(|_| {
+            VaporError::invalid("path indices", format!("must have {} levels", TREE_HEIGHT))
+        })?;
+        if path_indices.iter().any(|index| *index > 1) {
+            return Err(VaporError::invalid("path indices", "must be 0 or 1"));
+        }
+
+        Ok(Arc::new(Self(
+            condenser_witness::CondenserWitness::builder()
+                .recipient(parse_address("recipient", &recipient)?)
+                .amount(amount)
+                .merkle_root(parse_hex("merkle root", &proof.root)?)
+                .vapor_addr(parse_address("vapor address", &vapor_addr)?)
+                .deposit_amount(deposit_amount)
+                .merkle_proof(siblings)
+                .merkle_proof_indices(path_indices)
+                .secret(
+                    SpendSecret::from_str(&secret).map_err(|e| VaporError::invalid("secret", e))?,
+                )
+                .build(),
+        )))
+    }
+
+    /// Prover.toml contents for nargo
+    pub fn to_toml(&self) -> String {
+        self.0.to_toml().as_str().to_string()
+    }
+
+    /// The same inputs as a JSON object
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.0).expect("witness is always representable as JSON")
+    }
+}
+
+fn parse_address(what: &str, s: &str) -> Result<[u8; 32], VaporError> {
+    bs58::decode(s)
+        .into_vec()
+        .map_err(|e| VaporError::invalid(what, e))?
+        .try_into()
+        .map_err(|_| VaporError::invalid(what, "must be 32 bytes"))
+}
+
+fn parse_hex(what: &str, s: &str) -> Result<[u8; 32], VaporError> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s))
+        .map_err(|e| VaporError::invalid(what, e))?
+        .try_into()
+        .map_err(|_| VaporError::invalid(what, "must be 32 bytes"))
+}
+
+fn to_hex(bytes: &[u8; 32]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+    use transfer_tree::Poseidon;
+
+    use super::*;
+
+    const PROVER_TOML: &str = include_str!("../../../circuits/condenser/Prover.toml");
+
+    fn address(bytes: [u8; 32]) -> String {
+        bs58::encode(bytes).into_string()
+    }
+
+    fn from_hex(s: &str) -> [u8; 32] {
+        parse_hex("test", s).unwrap()
+    }
+
+    #[test]
+    fn test_generate_vaporize_address() {
+        for i in 0..255 {
+            let generated = generate_vaporize_address(address([i; 32])).unwrap();
+            let addr = parse_address("test", &generated.address).unwrap();
+            CompressedEdwardsY(addr)
+                .decompress()
+                .expect("not a valid point encoding");
+            assert!(SpendSecret::from_str(&generated.secret).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_invalid_input_is_an_error() {
+        assert!(matches!(
+            generate_vaporize_address("not base58!".to_string()),
+            Err(VaporError::InvalidInput { .. })
+        ));
+        assert!(matches!(
+            transfer_leaf(address([1u8; 32])[1..].to_string(), 1),
+            Err(VaporError::InvalidInput { .. })
+        ));
+        assert!(matches!(
+            TransferTree::new().append("0x1234".to_string(), false),
+            Err(VaporError::InvalidInput { .. })
+        ));
+        assert_eq!(
+            TransferTree::new().merkle_proof(0),
+            Err(VaporError::UntrackedLeaf { index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_matches_native_hashing() {
+        let recipient = [7u8; 32];
+        let packed: Vec<_> = utils::pack_bytes(&recipient)
+            .iter()
+            .map(|field| from_hex(&to_hex(&fr_to_be_32(field))))
+            .collect();
+        let ffi: Vec<_> = pack_bytes(recipient.to_vec())
+            .iter()
+            .map(|field| from_hex(field))
+            .collect();
+        assert_eq!(ffi, packed);
+
+        assert_eq!(
+            from_hex(&transfer_leaf(address(recipient), 1000).unwrap()),
+            transfer_tree::transfer_leaf(&recipient, 1000)
+        );
+    }
+
+    #[test]
+    fn test_tracked_proofs_stay_current() {
+        let tree = TransferTree::new();
+        let mut native = transfer_tree::TransferTree::<TREE_HEIGHT>::new();
+        assert_eq!(from_hex(&tree.root()), native.root());
+
+        for i in 0..9u8 {
+            let index = tree
+                .append_transfer(address([i; 32]), i as u64, i % 3 == 0)
+                .unwrap();
+            native.append_transfer([i; 32], i as u64).unwrap();
+            assert_eq!(index, i as u64);
+        }
+        assert_eq!(tree.next_index(), 9);
+        assert_eq!(from_hex(&tree.root()), native.root());
+
+        for i in [0u8, 3, 6] {
+            let proof = tree.merkle_proof(i as u64).unwrap();
+            let witness = Witness::<TREE_HEIGHT> {
+                index: proof.index,
+                siblings: proof
+                    .siblings
+                    .iter()
+                    .map(|sibling| from_hex(sibling))
+                    .collect::<Vec<_>>()
+                    .try_into()
+                    .unwrap(),
+            };
+            assert_eq!(proof.path_indices, witness.path_indices().to_vec());
+            assert_eq!(
+                witness.root::<Poseidon>(transfer_tree::transfer_leaf(&[i; 32], i as u64)),
+                native.root()
+            );
+        }
+        assert!(tree.merkle_proof(1).is_err());
+    }
+
+    #[test]
+    fn test_witness_matches_prover_toml() {
+        // Rebuild the fixture from its values through the bindings
+        let expected =
+            condenser_witness::CondenserWitness::<TREE_HEIGHT>::from_toml(PROVER_TOML).unwrap();
+        let recipient =
+            utils::unpack_bytes_from_le_fields(&expected.recipient.map(|f| fr_to_be_32(&f)), 32);
+        let proof = MerkleProof {
+            index: 1,
+            root: to_hex(&fr_to_be_32(&expected.merkle_root)),
+            siblings: expected
+                .merkle_proof
+                .iter()
+                .map(|node| to_hex(&fr_to_be_32(node)))
+                .collect(),
+            path_indices: expected.merkle_proof_indices.to_vec(),
+        };
+
+        let witness = CondenserWitness::new(
+            bs58::encode(recipient).into_string(),
+            600000000000,
+            address(expected.vapor_addr),
+            666000000000,
+            proof.clone(),
+            expected.secret.to_decimal_string().as_str().to_string(),
+        )
+        .unwrap();
+        assert_eq!(witness.0, expected);
+        assert_eq!(
+            condenser_witness::CondenserWitness::<TREE_HEIGHT>::from_toml(&witness.to_toml())
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            serde_json::from_str::<condenser_witness::CondenserWitness<TREE_HEIGHT>>(
+                &witness.to_json()
+            )
+            .unwrap(),
+            expected
+        );
+
+        let short_proof = MerkleProof {
+            siblings: proof.siblings[1..].to_vec(),
+            ..proof.clone()
+        };
+        let bad_indices = MerkleProof {
+            path_indices: vec![2; TREE_HEIGHT],
+            ..proof.clone()
+        };
+        for (proof, secret) in [
+            (short_proof, "1"),
+            (bad_indices, "1"),
+            (proof, "not a secret"),
+        ] {
+            assert!(matches!(
+                CondenserWitness::new(
+                    address([1u8; 32]),
+                    1,
+                    address([2u8; 32]),
+                    1,
+                    proof,
+                    secret.to_string(),
+                ),
+                Err(VaporError::InvalidInput { .. })
+            ));
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use redb::{ReadableDatabase, ReadableTable};
+use serde::Serialize;
+
+use crate::{Result, TRANSFERS, TransferEvent};
+
+/// Fewer leaves of the same amount than this and the deposit stands out when its amount is revealed
+const MIN_SAME_AMOUNT: usize = 10;
+/// Fewer leaves appended after the deposit than this and the timing links the condense to it
+const MIN_LEAVES_SINCE: usize = 50;
+/// Number of common amounts to suggest for future payments
+const SUGGESTED_AMOUNTS: usize = 3;
+
+/// How well a deposit hides among the rest of the tree once its amount is revealed by a condense
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AnonymitySet {
+    pub amount: u64,
+    /// Leaves in the tree with the same amount, including the deposit itself
+    pub same_amount: usize,
+    /// Leaves appended after the deposit
+    pub leaves_since: usize,
+    /// Most frequent amounts smaller than the deposit with how often they occur, most common first
+    pub common_amounts: Vec<(u64, usize)>,
+}
+
+impl AnonymitySet {
+    /// Analyse the deposit at `index` among `transfers`, which are in tree order
+    pub fn analyze(transfers: &[TransferEvent], index: usize) -> Self {
+        let amount = transfers[index].amount;
+
+        let mut counts: HashMap<u64, usize> = HashMap::new();
+        for transfer in transfers {
+            *counts.entry(transfer.amount).or_default() += 1;
+        }
+        let same_amount = counts[&amount];
+
+        let mut common_amounts: Vec<(u64, usize)> =
+            counts.into_iter().filter(|&(a, _)| a < amount).collect();
+        common_amounts.sort_by(|a, b| b.1.cmp(&a.1).then(b.0.cmp(&a.0)));
+        common_amounts.truncate(SUGGESTED_AMOUNTS);
+
+        Self {
+            amount,
+            same_amount,
+            leaves_since: transfers.len() - index - 1,
+            common_amounts,
+        }
+    }
+
+    /// Advice for improving the anonymity set, empty if it looks large enough
+    pub fn recommendations(&self) -> Vec<String> {
+        let mut advice = Vec::new();
+        if self.leaves_since < MIN_LEAVES_SINCE {
+            advice.push(format!(
+                "Only {} transfer(s) have been made since this deposit. Wait for at least {} so the condense cannot be linked to it by timing",
+                self.leaves_since, MIN_LEAVES_SINCE
+            ));
+        }
+        if self.same_amount < MIN_SAME_AMOUNT {
+            let mut line = format!(
+                "Only {} transfer(s) of {} exist so the revealed amount narrows the deposit down. Wait for more. Only one deposit per recipient can be condensed so it can't be re-sent in parts, but future payments hide better in amounts that are common",
+                self.same_amount, self.amount
+            );
+            if !self.common_amounts.is_empty() {
+                let common: Vec<String> = self
+                    .common_amounts
+                    .iter()
+                    .map(|(amount, count)| format!("{} ({}x)", amount, count))
+                    .collect();
+                line.push_str(&format!(", such as {}", common.join(", ")));
+            }
+            advice.push(line);
+        }
+        advice
+    }
+}
+
+impl fmt::Display for AnonymitySet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} transfer(s) of the same amount, {} transfer(s) since",
+            self.same_amount, self.leaves_since
+        )
+    }
+}
+
+/// All synced transfers in tree order
+pub(crate) fn load_transfers(db: &redb::Database) -> Result<Vec<TransferEvent>> {
+    let read_txn = db.begin_read()?;
+    let transfers = read_txn.open_table(TRANSFERS)?;
+    transfers
+        .iter()?
+        .map(|result| Ok(result?.1.value()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfers(amounts: &[u64]) -> Vec<TransferEvent> {
+        amounts
+            .iter()
+            .map(|&amount| TransferEvent {
+                to: [0; 32],
+                amount,
+                slot: 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_unique_recent_deposit() {
+        let transfers = transfers(&[100, 100, 50, 100, 7_777, 1]);
+        let set = AnonymitySet::analyze(&transfers, 4);
+        assert_eq!(set.same_amount, 1);
+        assert_eq!(set.leaves_since, 1);
+        assert_eq!(set.common_amounts, vec![(100, 3), (50, 1), (1, 1)]);
+
+        let advice = set.recommendations();
+        assert_eq!(advice.len(), 2);
+        assert!(advice[1].contains("100 (3x)"));
+    }
+
+    #[test]
+    fn test_well_hidden_deposit() {
+        let mut amounts = vec![100; MIN_SAME_AMOUNT];
+        amounts.extend(vec![5; MIN_LEAVES_SINCE]);
+        let set = AnonymitySet::analyze(&transfers(&amounts), 0);
+        assert_eq!(set.same_amount, MIN_SAME_AMOUNT);
+        assert_eq!(set.leaves_since, MIN_SAME_AMOUNT + MIN_LEAVES_SINCE - 1);
+        assert!(set.recommendations().is_empty());
+    }
+}
@@ -0,0 +1,85 @@
+use borsh::{BorshDeserialize, BorshSerialize, to_vec};
+use redb::{TypeName, Value};
+use std::fmt::Debug;
+
+/// A type stored in a wallet table
+pub(crate) trait Record: Debug + BorshDeserialize + BorshSerialize {
+    /// Name redb stores with the table and checks on every open.
+    /// Fixed rather than derived from the Rust path so existing wallets stay readable when the type moves
+    const TYPE_NAME: &'static str;
+}
+
+#[derive(Debug)]
+pub(crate) struct BorshRecord<T>(pub T);
+
+impl<T: Record> Value for BorshRecord<T> {
+    type SelfType<'a>
+        = T
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        T::try_from_slice(data).unwrap()
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'a,
+        Self: 'b,
+    {
+        to_vec(value).unwrap()
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new(&format!("Borsh<{}>", T::TYPE_NAME))
+    }
+}
+
+/// Borsh encoding for spend secrets, kept as the decimal string earlier wallets stored
+pub(crate) mod spend_secret {
+    use borsh::io::{Error, ErrorKind, Read, Write};
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use utils::SpendSecret;
+    use zeroize::Zeroizing;
+
+    pub fn serialize<W: Write>(secret: &SpendSecret, writer: &mut W) -> Result<(), Error> {
+        secret.to_decimal_string().serialize(writer)
+    }
+
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<SpendSecret, Error> {
+        let decimal = Zeroizing::new(String::deserialize_reader(reader)?);
+        decimal
+            .parse()
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+/// Borsh encoding for stealth scan secrets as their canonical scalar bytes
+pub(crate) mod scan_secret {
+    use borsh::io::{Error, ErrorKind, Read, Write};
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use vaporize_addresses::ScanSecret;
+    use zeroize::Zeroizing;
+
+    pub fn serialize<W: Write>(secret: &ScanSecret, writer: &mut W) -> Result<(), Error> {
+        secret.to_bytes().serialize(writer)
+    }
+
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<ScanSecret, Error> {
+        let bytes = Zeroizing::new(<[u8; 32]>::deserialize_reader(reader)?);
+        ScanSecret::from_bytes(*bytes)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "invalid scan secret"))
+    }
+}
@@ -1,60 +1,43 @@
-use light_bounded_vec::BoundedVec;
 use redb::{ReadableDatabase, ReadableTable};
-use transfer_tree::TransferTreeExt;
+use transfer_tree::{Poseidon, TransferTree, Witness, transfer_leaf};
 
-use crate::TRANSFERS;
+use crate::{Result, TRANSFERS, WalletError};
 
 /// Build the Merkle proof for the transfer at the given index
 /// using transfers from the db
 pub(crate) fn build_merkle_proof<const HEIGHT: usize>(
     db: &redb::Database,
     index: usize,
-) -> anyhow::Result<([[u8; 32]; HEIGHT], [u8; HEIGHT], [u8; 32])> {
-    let mut tree = transfer_tree::TransferTree::<HEIGHT>::new_empty();
-    let mut proof = BoundedVec::with_capacity(HEIGHT);
-    let mut transfer_at_idx = None;
-    let mut changelog_index = 0;
+) -> Result<([[u8; 32]; HEIGHT], [u8; HEIGHT], [u8; 32])> {
+    let mut tree = TransferTree::<HEIGHT>::new();
+    let mut tracked: Option<([u8; 32], Witness<HEIGHT>)> = None;
 
     let read_txn = db.begin_read()?;
     {
         let transfers = read_txn.open_table(TRANSFERS)?;
-        for (i, result) in transfers.iter()?.enumerate() {
-            let (_, transfer) = result?;
-            match i {
-                _ if i < index => {
-                    tree.append_transfer(transfer.value().to, transfer.value().amount)?;
-                }
-                _ if i == index => {
-                    transfer_at_idx = Some(transfer.value());
-                    (changelog_index, _) = tree.append_transfer_with_proof(
-                        transfer.value().to,
-                        transfer.value().amount,
-                        &mut proof,
-                    )?;
-                }
-                _ if i > index => {
-                    tree.append_transfer(transfer.value().to, transfer.value().amount)?;
-                    tree.update_proof_from_changelog(changelog_index, index, &mut proof)
-                        .unwrap();
-                    changelog_index = tree.changelog_index();
-                }
-                _ => unreachable!(),
-            };
+        for result in transfers.iter()? {
+            let (i, transfer) = result?;
+            let transfer = transfer.value();
+            let path = tree.append_transfer(transfer.to, transfer.amount)?;
+            if i.value() == index as u64 {
+                let leaf = transfer_leaf(&transfer.to, transfer.amount);
+                tracked = Some((leaf, Witness::new(&path)));
+            } else if let Some((_, witness)) = &mut tracked {
+                witness.update(&path);
+            }
         }
     }
 
+    let Some((leaf, witness)) = tracked else {
+        return Err(WalletError::NotADeposit(index as u64));
+    };
+
     // sanity check that the proof verifies
-    if let Some(transfer_at_idx) = transfer_at_idx {
-        tree.validate_transfer_proof(transfer_at_idx.to, transfer_at_idx.amount, index, &proof)?;
-    } else {
-        anyhow::bail!("No transfer found at index {}", index);
+    if witness.root::<Poseidon>(leaf) != tree.root() {
+        return Err(WalletError::InvalidMerkleProof(index as u64));
     }
 
-    Ok((
-        proof.to_array().unwrap(),
-        tree.proof_indices(index),
-        tree.root(),
-    ))
+    Ok((witness.siblings, witness.path_indices(), tree.root()))
 }
 
 #[cfg(test)]
@@ -75,6 +58,7 @@ mod tests {
                 let transfer = TransferEvent {
                     to: [i as u8; 32],
                     amount: i * 100,
+                    slot: i,
                 };
                 transfers.insert(&i, &transfer).unwrap();
             }
@@ -99,6 +83,7 @@ mod tests {
                     .try_into()
                     .unwrap(),
                 amount: 1000,
+                slot: 0,
             };
             transfers.insert(&0, &transfer).unwrap();
         }
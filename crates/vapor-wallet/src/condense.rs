@@ -0,0 +1,286 @@
+use condenser_witness::CondenserWitness;
+use redb::{ReadableDatabase, ReadableTable};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use vapor_client::{VerifyingKeyAccount, accounts, instructions, pda};
+
+use crate::build_merkle_proof::build_merkle_proof;
+use crate::submit::{SubmitOptions, submit};
+use crate::{Result, TRANSFERS, TREE_HEIGHT, VAP_ADDR, WalletError};
+
+/// Everything needed to prove a condense of one deposit
+#[derive(Clone, Debug)]
+pub struct CondensePlan {
+    pub vapor_addr: [u8; 32],
+    pub recipient: [u8; 32],
+    pub leaf_index: u64,
+    pub deposit_amount: u64,
+    /// Already withdrawn to the recipient
+    pub withdrawn: u64,
+    /// The recipient's total withdrawn once this condense lands. This is the amount made public
+    pub total: u64,
+    pub root: [u8; 32],
+    pub witness: CondenserWitness<TREE_HEIGHT>,
+}
+
+impl CondensePlan {
+    /// Amount minted by this condense
+    pub fn amount(&self) -> u64 {
+        self.total - self.withdrawn
+    }
+}
+
+/// How much of a deposit a condense withdraws
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Withdrawal {
+    /// This much on top of what the recipient has withdrawn, everything up to the deposit amount if not set
+    Amount(Option<u64>),
+    /// Up to this total withdrawn by the recipient, however much of it has been withdrawn already
+    UpTo(u64),
+}
+
+/// A condense proof that has been checked locally and is ready to submit
+#[derive(Clone, Debug)]
+pub struct CondenseProof {
+    pub recipient: Pubkey,
+    /// Version of the registered verifying key the proof was checked against
+    pub vk_version: u32,
+    pub proof: Vec<u8>,
+    pub public_witness: Vec<u8>,
+}
+
+/// Work out the condense of the deposit at leaf `leaf_index`, up to the merkle proof and witness.
+/// The withdrawal can condense only part of the deposit, the rest can be condensed later.
+/// The tree must already be synced so the proof is against a recent root
+pub(crate) fn plan(
+    db: &redb::Database,
+    rpc_url: &str,
+    mint: &Pubkey,
+    vapor_addr: [u8; 32],
+    leaf_index: u64,
+    withdrawal: Withdrawal,
+) -> Result<CondensePlan> {
+    let read_txn = db.begin_read()?;
+    let (addr_record, deposit) = {
+        let addresses = read_txn.open_table(VAP_ADDR)?;
+        let transfers = read_txn.open_table(TRANSFERS)?;
+
+        let addr_record = addresses
+            .get(vapor_addr)?
+            .ok_or_else(|| WalletError::AddressNotFound(bs58::encode(vapor_addr).into_string()))?
+            .value();
+        let deposit = transfers
+            .get(leaf_index)?
+            .map(|transfer| transfer.value())
+            .filter(|transfer| transfer.to == vapor_addr)
+            .ok_or(WalletError::NotADeposit(leaf_index))?;
+        (addr_record, deposit)
+    };
+
+    // The proof states the recipient's total withdrawn after this condense, which must stay within the deposit
+    let withdrawn = total_withdrawn(
+        rpc_url,
+        mint,
+        &Pubkey::new_from_array(addr_record.recipient),
+    )?;
+    let amount = match withdrawal {
+        Withdrawal::Amount(amount) => amount,
+        Withdrawal::UpTo(total) => Some(total.saturating_sub(withdrawn)),
+    };
+    let total = withdrawal_total(withdrawn, deposit.amount, amount)?;
+
+    let (proof, proof_indices, root) = build_merkle_proof::<TREE_HEIGHT>(db, leaf_index as usize)?;
+    let witness = CondenserWitness::builder()
+        .recipient(addr_record.recipient)
+        .amount(total)
+        .merkle_root(root)
+        .merkle_proof(proof)
+        .merkle_proof_indices(proof_indices)
+        .vapor_addr(vapor_addr)
+        .deposit_amount(deposit.amount)
+        .secret(addr_record.secret)
+        .build();
+
+    Ok(CondensePlan {
+        vapor_addr,
+        recipient: addr_record.recipient,
+        leaf_index,
+        deposit_amount: deposit.amount,
+        withdrawn,
+        total,
+        root,
+        witness,
+    })
+}
+
+/// Run the prover for a planned condense and check the proof locally against the verifying key
+/// registered under `vk_version` rather than paying for a failed transaction
+pub(crate) fn prove(
+    rpc_url: &str,
+    mint: &Pubkey,
+    vk_version: u32,
+    plan: &CondensePlan,
+    show_output: bool,
+) -> Result<CondenseProof> {
+    // Fetch the key first so an unusable version is reported before spending time proving
+    let verifying_key = verifying_key(rpc_url, mint, vk_version)?;
+
+    let (proof, public_witness) =
+        crate::prove::prove::<TREE_HEIGHT>(plan.witness.clone(), show_output)?;
+
+    tracing::info!("Verifying proof...");
+    // A proof of another circuit than the key's fails here, e.g. after a circuit change with no new key registered
+    let public_inputs =
+        match vapor_client::proof::verify_condense(&verifying_key, &proof, &public_witness) {
+            Err(vapor_client::ClientError::InvalidProof) => {
+                return Err(WalletError::VerifyingKeyMismatch(vk_version));
+            }
+            result => result?,
+        };
+    if public_inputs.recipient != plan.recipient
+        || public_inputs.amount != plan.total
+        || public_inputs.merkle_root != plan.root
+    {
+        return Err(WalletError::ProofMismatch);
+    }
+
+    Ok(CondenseProof {
+        recipient: Pubkey::new_from_array(plan.recipient),
+        vk_version,
+        proof,
+        public_witness,
+    })
+}
+
+pub(crate) fn submit_proof(
+    rpc_url: &str,
+    payer: &Keypair,
+    mint: &Pubkey,
+    proof: &CondenseProof,
+    options: &SubmitOptions,
+) -> Result<Signature> {
+    let client = RpcClient::new(rpc_url.to_string());
+
+    let create_ata_ix = instructions::create_associated_token_account_idempotent(
+        &payer.pubkey(),
+        &proof.recipient,
+        mint,
+    );
+    let condense_ix = instructions::condense(
+        mint,
+        &proof.recipient,
+        &payer.pubkey(),
+        proof.vk_version,
+        proof.proof.clone(),
+        proof.public_witness.clone(),
+    );
+
+    tracing::info!("Submitting condense transaction...");
+    submit(&client, payer, &[create_ata_ix, condense_ix], options)
+}
+
+/// The verifying key registered for the mint under `version`, which must not be retired
+fn verifying_key(rpc_url: &str, mint: &Pubkey, version: u32) -> Result<VerifyingKeyAccount> {
+    let verifying_key = registered_key(rpc_url, &pda::verifying_key(mint, version).0)?
+        .ok_or(WalletError::VerifyingKeyNotFound(version))?;
+    if verifying_key.retired {
+        return Err(WalletError::VerifyingKeyRetired(version));
+    }
+    Ok(verifying_key)
+}
+
+/// The key registered at `address`, if any
+pub(crate) fn registered_key(
+    rpc_url: &str,
+    address: &Pubkey,
+) -> Result<Option<VerifyingKeyAccount>> {
+    let client = RpcClient::new(rpc_url.to_string());
+    match client
+        .get_account_with_commitment(address, client.commitment())?
+        .value
+    {
+        Some(account) => Ok(Some(accounts::decode_verifying_key(&account.data)?)),
+        None => Ok(None),
+    }
+}
+
+/// Amount already condensed to the recipient, zero if they have never been condensed to
+pub(crate) fn total_withdrawn(rpc_url: &str, mint: &Pubkey, recipient: &Pubkey) -> Result<u64> {
+    let client = RpcClient::new(rpc_url.to_string());
+    let tracker = pda::withdrawn(mint, recipient).0;
+    match client
+        .get_account_with_commitment(&tracker, client.commitment())?
+        .value
+    {
+        Some(account) => Ok(accounts::decode_withdrawn_tracker(&account.data)?.total_withdrawn),
+        None => Ok(0),
+    }
+}
+
+/// Recipient's total withdrawn once `amount` more is condensed from a deposit of `deposit_amount`.
+/// With no `amount` everything up to the deposit amount is condensed
+pub fn withdrawal_total(withdrawn: u64, deposit_amount: u64, amount: Option<u64>) -> Result<u64> {
+    let exceeds = |amount| WalletError::ExceedsDeposit {
+        amount,
+        withdrawn,
+        deposit: deposit_amount,
+    };
+    let total = match amount {
+        Some(amount) => withdrawn
+            .checked_add(amount)
+            .ok_or_else(|| exceeds(amount))?,
+        None => deposit_amount,
+    };
+    if total <= withdrawn {
+        return Err(WalletError::NothingToCondense {
+            withdrawn,
+            deposit: deposit_amount,
+        });
+    }
+    if total > deposit_amount {
+        return Err(exceeds(total - withdrawn));
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_withdrawal_total() {
+        // Whole deposit by default
+        assert_eq!(withdrawal_total(0, 100, None).unwrap(), 100);
+        // Part of it, then the remainder
+        assert_eq!(withdrawal_total(0, 100, Some(30)).unwrap(), 30);
+        assert_eq!(withdrawal_total(30, 100, Some(70)).unwrap(), 100);
+        assert_eq!(withdrawal_total(30, 100, None).unwrap(), 100);
+
+        assert!(matches!(
+            withdrawal_total(30, 100, Some(71)),
+            Err(WalletError::ExceedsDeposit { amount: 71, .. })
+        ));
+        assert!(matches!(
+            withdrawal_total(100, 100, None),
+            Err(WalletError::NothingToCondense { .. })
+        ));
+        assert!(withdrawal_total(30, 100, Some(0)).is_err());
+        assert!(withdrawal_total(1, 100, Some(u64::MAX)).is_err());
+    }
+
+    #[test]
+    fn test_two_deposits_to_one_recipient() {
+        // Withdrawals are tracked per recipient and a proof covers one deposit, so once the first of two
+        // equal deposits is condensed the second has nothing left to give
+        let first = withdrawal_total(0, 50, None).unwrap();
+        assert_eq!(first, 50);
+        assert!(matches!(
+            withdrawal_total(first, 50, None),
+            Err(WalletError::NothingToCondense { .. })
+        ));
+
+        // A larger second deposit only gives the difference
+        assert_eq!(withdrawal_total(first, 80, None).unwrap(), 80);
+    }
+}
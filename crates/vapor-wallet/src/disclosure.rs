@@ -0,0 +1,229 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{CommitmentConfig, RpcTransactionConfig};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+use utils::unpack_bytes_from_le_fields;
+use vapor_client::instructions::decode_condenses;
+use vapor_client::proof::PublicWitness;
+use vapor_client::{VerifyingKeyAccount, pda};
+
+use crate::{Result, WalletError, condense};
+
+/// Size of the header sunspot writes before the public witness entries
+const PUB_WITNESS_HEADER_LEN: usize = 12;
+
+/// Public inputs of the `disclosure` circuit, the recipient packed into two fields and the vapor address bytes
+const NR_PUBLIC_INPUTS: usize = vapor_client::proof::DISCLOSURE_NR_INPUTS;
+
+/// An artifact a recipient can hand to a third party (e.g. an auditor) to show that
+/// some condenses to them came from deposits made to a vapor address that commits to them.
+/// The secret is not revealed, instead a proof of the `disclosure` circuit shows that it is known.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Disclosure {
+    pub mint: String,
+    pub recipient: String,
+    pub vapor_addr: String,
+    pub deposits: Vec<DisclosedDeposit>,
+    /// Signatures of the condense transactions to the recipient that the disclosed deposits account for
+    pub condenses: Vec<String>,
+    /// Version of the mint's registered disclosure key the proof verifies against
+    pub vk_version: u32,
+    /// Hex encoded gnark proof
+    pub proof: String,
+    /// Hex encoded gnark public witness
+    pub public_witness: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct DisclosedDeposit {
+    pub slot: u64,
+    pub leaf_index: u64,
+    pub amount: u64,
+}
+
+impl Disclosure {
+    /// The recipient and vapor address that the proof's public witness commits to
+    pub fn public_inputs(&self) -> Result<([u8; 32], [u8; 32])> {
+        decode_public_inputs(&self.public_witness_bytes()?)
+    }
+
+    pub fn proof_bytes(&self) -> Result<Vec<u8>> {
+        hex::decode(&self.proof).map_err(|e| invalid(format!("proof is not hex: {}", e)))
+    }
+
+    pub fn public_witness_bytes(&self) -> Result<Vec<u8>> {
+        hex::decode(&self.public_witness)
+            .map_err(|e| invalid(format!("public witness is not hex: {}", e)))
+    }
+
+    pub fn condense_signatures(&self) -> Result<Vec<Signature>> {
+        self.condenses
+            .iter()
+            .map(|s| {
+                Signature::from_str(s)
+                    .map_err(|e| invalid(format!("condense signature {:?}: {}", s, e)))
+            })
+            .collect()
+    }
+
+    /// Verify the proof natively against a disclosure key registered for the mint, see [`disclosure_key`]
+    pub(crate) fn verify_proof(&self, verifying_key: &VerifyingKeyAccount) -> Result<()> {
+        match vapor_client::proof::verify_disclosure(
+            verifying_key,
+            &self.proof_bytes()?,
+            &self.public_witness_bytes()?,
+        ) {
+            Ok(()) => Ok(()),
+            Err(vapor_client::ClientError::InvalidProof) => Err(invalid("proof is invalid")),
+            Err(e) => Err(invalid(e.to_string())),
+        }
+    }
+}
+
+/// The disclosure key registered for the mint under `version`, which must not be retired
+pub(crate) fn disclosure_key(
+    rpc_url: &str,
+    mint: &Pubkey,
+    version: u32,
+) -> Result<VerifyingKeyAccount> {
+    let verifying_key = condense::registered_key(rpc_url, &pda::disclosure_key(mint, version).0)?
+        .ok_or(WalletError::DisclosureKeyNotFound(version))?;
+    if verifying_key.retired {
+        return Err(WalletError::DisclosureKeyRetired(version));
+    }
+    Ok(verifying_key)
+}
+
+/// Check that each condense transaction landed, minted `mint` to `recipient` and proved a total that one of
+/// the deposits made before it covers. The condenser circuit bounds the total by the deposit it proves,
+/// so a condense no disclosed deposit covers can't have come from them
+pub(crate) fn check_condenses(
+    rpc_url: &str,
+    mint: &Pubkey,
+    recipient: &[u8; 32],
+    deposits: &[DisclosedDeposit],
+    signatures: &[Signature],
+) -> Result<()> {
+    if signatures.is_empty() {
+        return Err(invalid("no condense transactions are disclosed"));
+    }
+    let client = RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed());
+    for signature in signatures {
+        let tx = client.get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )?;
+        if tx
+            .transaction
+            .meta
+            .as_ref()
+            .is_none_or(|meta| meta.err.is_some())
+        {
+            return Err(invalid(format!("condense {} failed", signature)));
+        }
+        let Some(transaction) = tx.transaction.transaction.decode() else {
+            return Err(invalid(format!("can't decode transaction {}", signature)));
+        };
+
+        let condenses: Vec<_> = decode_condenses(&transaction.message)
+            .into_iter()
+            .filter(|condense| {
+                condense.mint == *mint && condense.recipient.to_bytes() == *recipient
+            })
+            .collect();
+        if condenses.is_empty() {
+            return Err(invalid(format!(
+                "transaction {} does not condense to the recipient",
+                signature
+            )));
+        }
+        for condense in condenses {
+            let total = PublicWitness::from_bytes(&condense.pub_witness_bytes)?
+                .condenser_inputs()?
+                .amount;
+            if !deposits
+                .iter()
+                .any(|deposit| deposit.slot <= tx.slot && deposit.amount >= total)
+            {
+                return Err(invalid(format!(
+                    "condense {} proves a total of {} that no deposit disclosed before it covers",
+                    signature, total
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decode the public inputs of the `disclosure` circuit, `recipient: [Field; 2]` followed by `vapor_addr: [u8; 32]`
+fn decode_public_inputs(pub_witness: &[u8]) -> Result<([u8; 32], [u8; 32])> {
+    let entries = pub_witness
+        .get(PUB_WITNESS_HEADER_LEN..)
+        .ok_or_else(|| invalid("public witness is too short"))?
+        .chunks(32)
+        .map(|c| c.try_into())
+        .collect::<Result<Vec<[u8; 32]>, _>>()
+        .map_err(|_| invalid("public witness is not made of 32 byte entries"))?;
+
+    if entries.len() != NR_PUBLIC_INPUTS {
+        return Err(invalid(format!(
+            "expected {} public inputs in disclosure witness, found {}",
+            NR_PUBLIC_INPUTS,
+            entries.len()
+        )));
+    }
+
+    let recipient: [u8; 32] = unpack_bytes_from_le_fields(&entries[0..2], 32)
+        .try_into()
+        .map_err(|_| invalid("invalid recipient bytes"))?;
+
+    let mut vapor_addr = [0u8; 32];
+    for (byte, entry) in vapor_addr.iter_mut().zip(&entries[2..]) {
+        if entry[..31].iter().any(|b| *b != 0) {
+            return Err(invalid("vapor address public input is not a byte"));
+        }
+        *byte = entry[31];
+    }
+
+    Ok((recipient, vapor_addr))
+}
+
+fn invalid(reason: impl Into<String>) -> WalletError {
+    WalletError::InvalidDisclosure(reason.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use condenser_witness::DisclosureWitness;
+    use utils::{SpendSecret, fr_to_be_32, u64_to_be_32};
+
+    #[test]
+    fn test_decode_public_inputs() {
+        let recipient = [7u8; 32];
+        let vapor_addr: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let witness = DisclosureWitness::new(recipient, vapor_addr, SpendSecret::new(42u64.into()));
+
+        let mut pub_witness = vec![0u8; PUB_WITNESS_HEADER_LEN];
+        for f in &witness.recipient {
+            pub_witness.extend_from_slice(&fr_to_be_32(f));
+        }
+        for b in vapor_addr {
+            pub_witness.extend_from_slice(&u64_to_be_32(b as u64));
+        }
+
+        assert_eq!(
+            decode_public_inputs(&pub_witness).unwrap(),
+            (recipient, vapor_addr)
+        );
+        assert!(decode_public_inputs(&pub_witness[..pub_witness.len() - 32]).is_err());
+    }
+}
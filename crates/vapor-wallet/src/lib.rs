@@ -0,0 +1,257 @@
+//! Wallet for vapor tokens
+//!
+//! Keeps vapor addresses, stealth scan keys, the synced transfers of a mint and scheduled condenses in a redb
+//! database, and builds, proves and submits condenses from them. [`Wallet`] is the entry point. It never prints
+//! or prompts so the CLI, the watch daemon and other services can all sit on top of it, and reports progress
+//! through `tracing`.
+use borsh::{BorshDeserialize, BorshSerialize};
+use redb::TableDefinition;
+use serde::Serialize;
+use utils::SpendSecret;
+use vaporize_addresses::ScanSecret;
+
+use crate::borsh_record::{BorshRecord, Record};
+
+pub mod anonymity;
+mod borsh_record;
+mod build_merkle_proof;
+mod condense;
+pub mod disclosure;
+mod prove;
+pub mod schedule;
+mod send;
+mod stealth;
+mod submit;
+mod sync;
+mod wallet;
+
+pub use condense::{CondensePlan, CondenseProof, withdrawal_total};
+pub use send::{Destination, SentTransfer};
+pub use submit::SubmitOptions;
+pub use sync::SyncReport;
+pub use wallet::{Wallet, WalletConfig};
+
+/// Height of the transfer tree kept by the transfer hook
+pub const TREE_HEIGHT: usize = 26;
+
+/// Table for crated vaporize address metadata indexed by vaporize address.
+const VAP_ADDR: TableDefinition<[u8; 32], BorshRecord<VaporAddressRecord>> =
+    TableDefinition::new("vapor-addresses");
+
+/// Table for synced transfers indexed by their leaf index in the transfer tree.
+const TRANSFERS: TableDefinition<u64, BorshRecord<TransferEvent>> = TableDefinition::new("leaves");
+
+/// Table for stealth scan keys indexed by the recipient they were generated for.
+const STEALTH_KEYS: TableDefinition<[u8; 32], BorshRecord<StealthKeyRecord>> =
+    TableDefinition::new("stealth-keys");
+
+/// Table for ephemeral keys posted in stealth memos indexed by the leaf index of the transfer they were attached to.
+const EPHEMERAL_KEYS: TableDefinition<u64, [u8; 32]> = TableDefinition::new("ephemeral-keys");
+
+/// Leaf index the next stealth scan starts from, every memo before it has been checked against every scan key.
+const SCAN_CURSOR: TableDefinition<(), u64> = TableDefinition::new("scan-cursor");
+
+/// Signature of the newest transaction of the mint synced over RPC and the number of leaves up to it.
+/// The next sync fetches only the transactions after it
+const RPC_CURSOR: TableDefinition<(), (&str, u64)> = TableDefinition::new("rpc-cursor");
+
+/// Table of earlier wallets, which indexed transfers by slot and so kept only one transfer per slot.
+/// It is dropped on open so the transfers are synced again by leaf index
+const LEGACY_TRANSFERS: TableDefinition<u64, &[u8]> = TableDefinition::new("transfers");
+
+/// Table for scheduled condenses indexed by job id, kept after they run to record the result.
+const CONDENSE_JOBS: TableDefinition<u64, BorshRecord<schedule::CondenseJob>> =
+    TableDefinition::new("condense-jobs");
+
+/// A vaporize address held by the wallet along with the secret needed to condense deposits to it
+#[derive(Clone, Debug, BorshDeserialize, BorshSerialize, PartialEq)]
+pub struct VaporAddressRecord {
+    pub addr: [u8; 32],
+    pub recipient: [u8; 32],
+    #[borsh(
+        serialize_with = "borsh_record::spend_secret::serialize",
+        deserialize_with = "borsh_record::spend_secret::deserialize"
+    )]
+    pub secret: SpendSecret,
+}
+
+impl Record for VaporAddressRecord {
+    const TYPE_NAME: &'static str = "cli_wallet::VaporAddressRecord";
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+struct StealthKeyRecord {
+    recipient: [u8; 32],
+    #[borsh(
+        serialize_with = "borsh_record::scan_secret::serialize",
+        deserialize_with = "borsh_record::scan_secret::deserialize"
+    )]
+    scan_secret: ScanSecret,
+}
+
+impl Record for StealthKeyRecord {
+    const TYPE_NAME: &'static str = "cli_wallet::StealthKeyRecord";
+}
+
+/// A transfer of the mint as recorded in the transfer tree
+#[derive(Clone, Debug, BorshDeserialize, BorshSerialize, PartialEq)]
+pub struct TransferEvent {
+    pub to: [u8; 32],
+    pub amount: u64,
+    /// Slot of the transaction that made the transfer
+    pub slot: u64,
+}
+
+impl Record for TransferEvent {
+    const TYPE_NAME: &'static str = "cli_wallet::TransferEvent";
+}
+
+/// A transfer to one of the wallet's vaporize addresses
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Deposit {
+    /// Position of the transfer in the tree
+    pub leaf_index: u64,
+    pub slot: u64,
+    pub amount: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WalletError {
+    #[error("invalid {what}: {reason}")]
+    InvalidInput { what: &'static str, reason: String },
+    #[error("vaporize address {0} is not in this wallet")]
+    AddressNotFound(String),
+    #[error("leaf {0} is not a deposit to this address")]
+    NotADeposit(u64),
+    #[error("no deposit to this address found in slot {0}")]
+    NoDepositInSlot(u64),
+    #[error("no deposits to this address have been synced")]
+    NoDeposits,
+    #[error(
+        "nothing left to condense, the recipient has already withdrawn {withdrawn} and the deposit is {deposit}"
+    )]
+    NothingToCondense { withdrawn: u64, deposit: u64 },
+    #[error(
+        "cannot condense {amount} more, the recipient has already withdrawn {withdrawn} and the deposit is {deposit}"
+    )]
+    ExceedsDeposit {
+        amount: u64,
+        withdrawn: u64,
+        deposit: u64,
+    },
+    #[error(
+        "the mint's admission policy skips a transfer of {amount} receiving {received} after fees, it could never be condensed"
+    )]
+    NotAdmitted { amount: u64, received: u64 },
+    #[error("leaf {0} has already been synced with a different transfer")]
+    LeafAlreadySynced(u64),
+    #[error("indexer returned leaf {found} where {expected} was expected")]
+    IndexerOutOfOrder { expected: u64, found: u64 },
+    #[error("synced transfers do not match the on-chain tree")]
+    RootMismatch,
+    #[error("Merkle proof for transfer {0} does not match the tree root")]
+    InvalidMerkleProof(u64),
+    #[error("proof public inputs do not match the selected deposit")]
+    ProofMismatch,
+    #[error(
+        "proof does not verify against verifying key version {0}. If the circuit has changed, register the key of its trusted setup under a new version and select it with --vk-version"
+    )]
+    VerifyingKeyMismatch(u32),
+    #[error("no verifying key is registered under version {0}")]
+    VerifyingKeyNotFound(u32),
+    #[error("verifying key version {0} has been retired")]
+    VerifyingKeyRetired(u32),
+    #[error("no disclosure key is registered under version {0}")]
+    DisclosureKeyNotFound(u32),
+    #[error("disclosure key version {0} has been retired")]
+    DisclosureKeyRetired(u32),
+    #[error("prover failed: {0}")]
+    Prover(String),
+    #[error("{0}")]
+    Transaction(String),
+    #[error("invalid disclosure: {0}")]
+    InvalidDisclosure(String),
+    #[error("no job {0}")]
+    JobNotFound(u64),
+    #[error("job {0} has already finished")]
+    JobFinished(u64),
+    #[error(transparent)]
+    TreeFull(#[from] transfer_tree::TreeFull),
+    #[error(transparent)]
+    Vanity(#[from] vaporize_addresses::VanityError),
+    #[error(transparent)]
+    MetaAddress(#[from] vaporize_addresses::InvalidMetaAddress),
+    #[error(transparent)]
+    Client(#[from] vapor_client::ClientError),
+    #[error(transparent)]
+    Rpc(Box<solana_client::client_error::ClientError>),
+    #[error(transparent)]
+    Database(#[from] redb::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+pub type Result<T, E = WalletError> = std::result::Result<T, E>;
+
+impl From<solana_client::client_error::ClientError> for WalletError {
+    fn from(e: solana_client::client_error::ClientError) -> Self {
+        WalletError::Rpc(Box::new(e))
+    }
+}
+
+macro_rules! database_errors {
+    ($($error:ty),*) => {
+        $(impl From<$error> for WalletError {
+            fn from(e: $error) -> Self {
+                WalletError::Database(e.into())
+            }
+        })*
+    };
+}
+
+database_errors!(
+    redb::DatabaseError,
+    redb::TransactionError,
+    redb::TableError,
+    redb::StorageError,
+    redb::CommitError
+);
+
+/// Decode a base58 encoded 32 byte address
+pub fn parse_address(what: &'static str, s: &str) -> Result<[u8; 32]> {
+    bs58::decode(s)
+        .into_vec()
+        .map_err(|e| WalletError::InvalidInput {
+            what,
+            reason: e.to_string(),
+        })?
+        .try_into()
+        .map_err(|_| WalletError::InvalidInput {
+            what,
+            reason: "must be 32 bytes".to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_address() {
+        let addr = [7u8; 32];
+        assert_eq!(
+            parse_address("recipient", &bs58::encode(addr).into_string()).unwrap(),
+            addr
+        );
+        assert!(matches!(
+            parse_address("recipient", &bs58::encode([7u8; 31]).into_string()),
+            Err(WalletError::InvalidInput {
+                what: "recipient",
+                ..
+            })
+        ));
+        assert!(parse_address("recipient", "0OIl").is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use condenser_witness::{CondenserWitness, DisclosureWitness};
+use tempfile::TempDir;
+
+use crate::{Result, WalletError};
+
+const DEFAULT_PROVER_IMAGE: &str = "vapor-prover:latest";
+const PROVER_IMAGE_ENV: &str = "VAPOR_PROVER_IMAGE";
+const PROOF_MARKER: &[u8] = b"---PROOF---\n";
+const WITNESS_MARKER: &[u8] = b"\n---WITNESS---\n";
+const INPUT_FILE_NAME: &str = "Prover.toml";
+
+/// With `show_output` the prover's progress is passed through to stderr,
+/// otherwise it is captured and only reported if proving fails
+pub(crate) fn prove<const HEIGHT: usize>(
+    input: CondenserWitness<HEIGHT>,
+    show_output: bool,
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    prove_circuit("condenser", &input.to_toml(), show_output)
+}
+
+pub(crate) fn prove_disclosure(
+    input: DisclosureWitness,
+    show_output: bool,
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    prove_circuit("disclosure", &input.to_toml(), show_output)
+}
+
+fn prove_circuit(circuit: &str, toml: &str, show_output: bool) -> Result<(Vec<u8>, Vec<u8>)> {
+    let input = PrivateInput::new(toml)?;
+    let stdout = run_prover_container(&circuits_dir()?, circuit, &input, show_output)?;
+    parse_prover_stdout(&stdout)
+}
+
+fn run_prover_container(
+    circuits_dir: &Path,
+    circuit: &str,
+    input: &PrivateInput,
+    show_output: bool,
+) -> Result<Vec<u8>> {
+    // The circuits are mounted read-only so the container can't leave the witness behind on the host
+    let output = Command::new("docker")
+        .arg("run")
+        .arg("--rm")
+        .arg("-v")
+        .arg(format!("{}:/circuits:ro", circuits_dir.display()))
+        .arg("-v")
+        .arg(format!("{}:/input:ro", input.dir.path().display()))
+        .arg(prover_image())
+        .arg(circuit)
+        .stdout(Stdio::piped())
+        .stderr(stderr(show_output))
+        .output()?;
+
+    if !output.status.success() {
+        let captured = String::from_utf8_lossy(&output.stderr);
+        let mut message = format!("prover container failed with status {}", output.status);
+        if !captured.trim().is_empty() {
+            message = format!("{}: {}", message, captured.trim());
+        }
+        return Err(WalletError::Prover(message));
+    }
+
+    Ok(output.stdout)
+}
+
+/// Prover.toml written to a temp directory only the current user can read.
+/// The file is overwritten before the directory is removed on drop
+struct PrivateInput {
+    dir: TempDir,
+    len: usize,
+}
+
+impl PrivateInput {
+    fn new(toml: &str) -> Result<Self> {
+        let dir = tempfile::Builder::new().prefix("vapor-prover").tempdir()?;
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(dir.path().join(INPUT_FILE_NAME))?
+            .write_all(toml.as_bytes())?;
+
+        Ok(Self {
+            dir,
+            len: toml.len(),
+        })
+    }
+}
+
+impl Drop for PrivateInput {
+    fn drop(&mut self) {
+        let path = self.dir.path().join(INPUT_FILE_NAME);
+        if let Ok(mut file) = OpenOptions::new().write(true).open(&path) {
+            let _ = file.write_all(&vec![0u8; self.len]);
+            let _ = file.sync_all();
+        }
+    }
+}
+
+fn stderr(show_output: bool) -> Stdio {
+    if show_output {
+        Stdio::inherit()
+    } else {
+        Stdio::piped()
+    }
+}
+
+fn prover_image() -> String {
+    std::env::var(PROVER_IMAGE_ENV).unwrap_or_else(|_| DEFAULT_PROVER_IMAGE.to_string())
+}
+
+fn circuits_dir() -> Result<PathBuf> {
+    Ok(workspace_root()?.join("circuits"))
+}
+
+fn workspace_root() -> Result<PathBuf> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let root = manifest_dir
+        .parent()
+        .and_then(|path| path.parent())
+        .ok_or_else(|| WalletError::Prover("failed to resolve workspace root".to_string()))?;
+    Ok(root.to_path_buf())
+}
+
+fn parse_prover_stdout(stdout: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let proof_start = find_subslice(stdout, PROOF_MARKER)
+        .ok_or_else(|| WalletError::Prover("missing proof marker in prover output".to_string()))?
+        + PROOF_MARKER.len();
+    let witness_marker_pos = find_subslice(&stdout[proof_start..], WITNESS_MARKER)
+        .map(|pos| proof_start + pos)
+        .ok_or_else(|| {
+            WalletError::Prover("missing witness marker in prover output".to_string())
+        })?;
+
+    let proof_bytes = stdout[proof_start..witness_marker_pos].to_vec();
+    let witness_start = witness_marker_pos + WITNESS_MARKER.len();
+    let witness_bytes = stdout[witness_start..].to_vec();
+
+    if proof_bytes.is_empty() || witness_bytes.is_empty() {
+        return Err(WalletError::Prover(
+            "prover output did not include proof or witness bytes".to_string(),
+        ));
+    }
+
+    Ok((proof_bytes, witness_bytes))
+}
+
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(0);
+    }
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
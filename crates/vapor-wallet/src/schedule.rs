@@ -0,0 +1,327 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use rand::Rng;
+use redb::{ReadableDatabase, ReadableTable, TableError};
+
+use crate::borsh_record::Record;
+use crate::{CONDENSE_JOBS, Result, WalletError};
+
+/// Longest a failed job waits before its next attempt, however many times it has failed
+const MAX_RETRY_DELAY: u64 = 24 * 60 * 60;
+
+/// A condense waiting in the wallet to be run by `watch`
+#[derive(Clone, Debug, BorshDeserialize, BorshSerialize, PartialEq)]
+pub struct CondenseJob {
+    pub vapor_addr: [u8; 32],
+    /// Leaf of the deposit being condensed
+    pub leaf_index: u64,
+    /// Recipient's total withdrawn once the job has run, worked out when it is scheduled. The condense proves
+    /// this total rather than an amount on top of what has been withdrawn, so retrying a condense that did land
+    /// can't withdraw twice
+    pub total: u64,
+    /// Unix time the job next runs at, picked at random when it is scheduled or retried
+    pub run_at: u64,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub status: JobStatus,
+}
+
+#[derive(Clone, Debug, BorshDeserialize, BorshSerialize, PartialEq)]
+pub enum JobStatus {
+    Pending,
+    Done {
+        signature: String,
+    },
+    /// Gave up after too many failed attempts
+    Failed,
+    /// The recipient's total withdrawn had already reached the job's total when it ran,
+    /// e.g. because an attempt that was reported as failed landed after all
+    Reached,
+}
+
+/// How `watch` retries jobs that fail
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    /// Delay before the first retry in seconds, doubling with each further failure
+    pub delay: u64,
+}
+
+impl CondenseJob {
+    pub fn new<R: Rng>(
+        rng: &mut R,
+        vapor_addr: [u8; 32],
+        leaf_index: u64,
+        total: u64,
+        earliest: u64,
+        latest: u64,
+    ) -> Self {
+        Self {
+            vapor_addr,
+            leaf_index,
+            total,
+            run_at: random_time(rng, earliest, latest),
+            attempts: 0,
+            last_error: None,
+            status: JobStatus::Pending,
+        }
+    }
+
+    pub fn is_due(&self, now: u64) -> bool {
+        self.status == JobStatus::Pending && self.run_at <= now
+    }
+
+    pub fn record_success(&mut self, signature: String) {
+        self.attempts += 1;
+        self.last_error = None;
+        self.status = JobStatus::Done { signature };
+    }
+
+    pub fn record_reached(&mut self) {
+        self.attempts += 1;
+        self.last_error = None;
+        self.status = JobStatus::Reached;
+    }
+
+    /// Record a failed attempt and pick a random time for the next, or give up once the policy's attempts are used.
+    /// The retry time is randomized too so a run of failures doesn't line up with anything observable
+    pub fn record_failure<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        error: String,
+        now: u64,
+        policy: &RetryPolicy,
+    ) {
+        self.attempts += 1;
+        self.last_error = Some(error);
+        if self.attempts >= policy.max_attempts {
+            self.status = JobStatus::Failed;
+            return;
+        }
+        let backoff = policy
+            .delay
+            .saturating_mul(1 << (self.attempts - 1).min(32))
+            .min(MAX_RETRY_DELAY);
+        self.run_at = random_time(
+            rng,
+            now.saturating_add(backoff),
+            now.saturating_add(backoff.saturating_mul(2)),
+        );
+    }
+}
+
+impl Record for CondenseJob {
+    const TYPE_NAME: &'static str = "cli_wallet::schedule::CondenseJob";
+}
+
+impl fmt::Display for CondenseJob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} leaf {} up to a total of {}: ",
+            bs58::encode(self.vapor_addr).into_string(),
+            self.leaf_index,
+            self.total
+        )?;
+        match &self.status {
+            JobStatus::Pending => write!(f, "pending, runs at {}", self.run_at)?,
+            JobStatus::Done { signature } => write!(f, "done in {}", signature)?,
+            JobStatus::Failed => write!(f, "failed after {} attempts", self.attempts)?,
+            JobStatus::Reached => write!(f, "already withdrawn")?,
+        }
+        if let Some(error) = &self.last_error {
+            write!(f, " (last error: {})", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Uniformly random time in `earliest..=latest`
+pub fn random_time<R: Rng>(rng: &mut R, earliest: u64, latest: u64) -> u64 {
+    rng.gen_range(earliest..=latest.max(earliest))
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system clock is before 1970")
+        .as_secs()
+}
+
+/// Parse a duration such as `90`, `30s`, `15m`, `6h` or `2d` into seconds
+pub fn parse_duration(s: &str) -> Result<u64> {
+    let s = s.trim();
+    let (digits, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid_duration(s, "expected e.g. 30s, 15m, 6h or 2d")),
+    };
+    let value: u64 = digits
+        .parse()
+        .map_err(|_| invalid_duration(s, "not a whole number"))?;
+    value
+        .checked_mul(multiplier)
+        .ok_or_else(|| invalid_duration(s, "too long"))
+}
+
+fn invalid_duration(s: &str, reason: &str) -> WalletError {
+    WalletError::InvalidInput {
+        what: "duration",
+        reason: format!("{:?} {}", s, reason),
+    }
+}
+
+/// Store a new job and return its id
+pub(crate) fn add_job(db: &redb::Database, job: &CondenseJob) -> Result<u64> {
+    let write_txn = db.begin_write()?;
+    let id = {
+        let mut jobs = write_txn.open_table(CONDENSE_JOBS)?;
+        let id = match jobs.last()? {
+            Some((id, _)) => id.value() + 1,
+            None => 0,
+        };
+        jobs.insert(id, job)?;
+        id
+    };
+    write_txn.commit()?;
+    Ok(id)
+}
+
+pub(crate) fn update_job(db: &redb::Database, id: u64, job: &CondenseJob) -> Result<()> {
+    let write_txn = db.begin_write()?;
+    write_txn.open_table(CONDENSE_JOBS)?.insert(id, job)?;
+    write_txn.commit()?;
+    Ok(())
+}
+
+/// Remove a job that has not run yet
+pub(crate) fn cancel_job(db: &redb::Database, id: u64) -> Result<()> {
+    let write_txn = db.begin_write()?;
+    {
+        let mut jobs = write_txn.open_table(CONDENSE_JOBS)?;
+        let status = jobs
+            .get(id)?
+            .ok_or(WalletError::JobNotFound(id))?
+            .value()
+            .status;
+        if status != JobStatus::Pending {
+            return Err(WalletError::JobFinished(id));
+        }
+        jobs.remove(id)?;
+    }
+    write_txn.commit()?;
+    Ok(())
+}
+
+/// All jobs by id
+pub(crate) fn load_jobs(db: &redb::Database) -> Result<Vec<(u64, CondenseJob)>> {
+    let read_txn = db.begin_read()?;
+    let jobs = match read_txn.open_table(CONDENSE_JOBS) {
+        Ok(jobs) => jobs,
+        Err(TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    jobs.iter()?
+        .map(|result| {
+            let (id, job) = result?;
+            Ok((id.value(), job.value()))
+        })
+        .collect()
+}
+
+/// The pending job that has been due the longest, if any
+pub(crate) fn next_due(jobs: Vec<(u64, CondenseJob)>, now: u64) -> Option<(u64, CondenseJob)> {
+    jobs.into_iter()
+        .filter(|(_, job)| job.is_due(now))
+        .min_by_key(|(_, job)| job.run_at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    const POLICY: RetryPolicy = RetryPolicy {
+        max_attempts: 3,
+        delay: 600,
+    };
+
+    fn job(rng: &mut StdRng, run_between: (u64, u64)) -> CondenseJob {
+        CondenseJob::new(rng, [1; 32], 4, 100, run_between.0, run_between.1)
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90").unwrap(), 90);
+        assert_eq!(parse_duration("30s").unwrap(), 30);
+        assert_eq!(parse_duration("15m").unwrap(), 900);
+        assert_eq!(parse_duration("6h").unwrap(), 21_600);
+        assert_eq!(parse_duration("2d").unwrap(), 172_800);
+        assert!(parse_duration("2w").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("99999999999999999999d").is_err());
+    }
+
+    #[test]
+    fn test_runs_within_window() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let job = job(&mut rng, (1_000, 2_000));
+            assert!((1_000..=2_000).contains(&job.run_at));
+            assert!(!job.is_due(999));
+            assert!(job.is_due(2_000));
+        }
+        assert_eq!(random_time(&mut rng, 5, 5), 5);
+    }
+
+    #[test]
+    fn test_retries_then_gives_up() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut job = job(&mut rng, (0, 0));
+
+        job.record_failure(&mut rng, "stale root".into(), 1_000, &POLICY);
+        assert_eq!(job.status, JobStatus::Pending);
+        assert!((1_600..=2_200).contains(&job.run_at));
+
+        job.record_failure(&mut rng, "stale root".into(), 3_000, &POLICY);
+        assert_eq!(job.status, JobStatus::Pending);
+        assert!((4_200..=5_400).contains(&job.run_at));
+
+        job.record_failure(&mut rng, "stale root".into(), 6_000, &POLICY);
+        assert_eq!(job.status, JobStatus::Failed);
+        assert!(!job.is_due(u64::MAX));
+        assert_eq!(job.last_error.as_deref(), Some("stale root"));
+    }
+
+    #[test]
+    fn test_queue() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = redb::Database::create(dir.path().join("wallet.redb")).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!(load_jobs(&db).unwrap().is_empty());
+
+        let later = add_job(&db, &job(&mut rng, (500, 500))).unwrap();
+        let sooner = add_job(&db, &job(&mut rng, (100, 100))).unwrap();
+        let cancelled = add_job(&db, &job(&mut rng, (50, 50))).unwrap();
+        assert_eq!((later, sooner, cancelled), (0, 1, 2));
+        cancel_job(&db, cancelled).unwrap();
+
+        assert!(next_due(load_jobs(&db).unwrap(), 99).is_none());
+        let (id, mut job) = next_due(load_jobs(&db).unwrap(), 1_000).unwrap();
+        assert_eq!(id, sooner);
+
+        job.record_success("sig".into());
+        update_job(&db, id, &job).unwrap();
+        assert!(cancel_job(&db, id).is_err());
+        assert_eq!(next_due(load_jobs(&db).unwrap(), 1_000).unwrap().0, later);
+    }
+}
@@ -0,0 +1,116 @@
+use std::str::FromStr;
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use vapor_client::{accounts, instructions, pda};
+use vaporize_addresses::{StealthMetaAddress, StealthPayment};
+
+use crate::{Result, WalletError};
+
+/// Where `send` delivers tokens
+#[derive(Clone, Debug)]
+pub enum Destination {
+    Address(Pubkey),
+    /// A fresh vaporize address is derived for every transfer and announced in a memo
+    Stealth(StealthMetaAddress),
+}
+
+impl FromStr for Destination {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Ok(meta) = s.parse::<StealthMetaAddress>() {
+            return Ok(Destination::Stealth(meta));
+        }
+        Pubkey::from_str(s)
+            .map(Destination::Address)
+            .map_err(|e| WalletError::InvalidInput {
+                what: "destination",
+                reason: e.to_string(),
+            })
+    }
+}
+
+/// A transfer made by `send`
+#[derive(Clone, Debug)]
+pub struct SentTransfer {
+    pub to: Pubkey,
+    pub amount: u64,
+    pub signature: Signature,
+}
+
+/// Transfer tokens from the keypair's token account to a destination wallet.
+/// A stealth meta-address is paid at a fresh vapor address derived for the recipient to find
+pub(crate) fn send(
+    rpc_url: &str,
+    payer: &Keypair,
+    mint: &Pubkey,
+    destination: &Destination,
+    amount: u64,
+) -> Result<SentTransfer> {
+    let client = RpcClient::new(rpc_url.to_string());
+
+    let mint_data = client.get_account_data(mint)?;
+    let decimals = accounts::decode_mint_decimals(&mint_data)?;
+    let extra_account_metas = client.get_account_data(&pda::extra_account_metas(mint).0)?;
+    let tree = accounts::decode_merkle_tree(&client.get_account_data(&pda::merkle_tree(mint).0)?)?;
+
+    // The transfer goes to an address, optionally with a memo announcing a stealth payment
+    let (to, memo) = match destination {
+        Destination::Address(destination) => (*destination, None),
+        Destination::Stealth(meta) => {
+            let payment = StealthPayment::new(&mut rand::thread_rng(), meta)?;
+            (
+                Pubkey::new_from_array(payment.vapor_addr),
+                Some(payment.memo()),
+            )
+        }
+    };
+
+    // Tokens sent to a vaporize address without a leaf are lost, so a stealth payment is not sent unless it is admitted.
+    // A regular destination may or may not be a vaporize address. On transfer fee mints the hook checks the amount
+    // net of the fee for the current epoch
+    let epoch = client.get_epoch_info()?.epoch;
+    let received = accounts::net_amount(&mint_data, epoch, amount)?;
+    if !accounts::admits(&tree, &to, received) {
+        if memo.is_some() {
+            return Err(WalletError::NotAdmitted { amount, received });
+        }
+        tracing::warn!(
+            "The transfer of {} to {} is skipped by the admission policy and will not be recorded",
+            amount,
+            to
+        );
+    }
+
+    let create_ata_ix =
+        instructions::create_associated_token_account_idempotent(&payer.pubkey(), &to, mint);
+    let transfer_ix = instructions::transfer_checked(
+        &payer.pubkey(),
+        mint,
+        &to,
+        amount,
+        decimals,
+        &extra_account_metas,
+    )?;
+
+    let mut ixs: Vec<Instruction> = vec![create_ata_ix, transfer_ix];
+    if let Some(memo) = memo {
+        ixs.push(instructions::memo(&memo));
+    }
+
+    let recent_blockhash = client.get_latest_blockhash()?;
+    let tx =
+        Transaction::new_signed_with_payer(&ixs, Some(&payer.pubkey()), &[payer], recent_blockhash);
+
+    tracing::info!("Sending {} to {}...", amount, to);
+    let signature = client.send_and_confirm_transaction(&tx)?;
+    Ok(SentTransfer {
+        to,
+        amount,
+        signature,
+    })
+}
@@ -0,0 +1,175 @@
+use redb::{ReadableDatabase, ReadableTable, ReadableTableMetadata, TableError};
+use vaporize_addresses::recover_stealth_address;
+
+use crate::{
+    EPHEMERAL_KEYS, Result, SCAN_CURSOR, STEALTH_KEYS, TRANSFERS, VAP_ADDR, VaporAddressRecord,
+};
+
+/// Check the stealth memos synced since the last scan against this wallet's scan keys.
+/// Payments that match are stored as regular vaporize addresses so they can be listed and condensed.
+/// Returns the number of newly found payments
+pub(crate) fn scan(db: &redb::Database) -> Result<usize> {
+    let read_txn = db.begin_read()?;
+    let (found, start, end) = {
+        // Nothing to scan until a meta-address has been created and a stealth memo synced
+        let (keys, memos) = match (
+            read_txn.open_table(STEALTH_KEYS),
+            read_txn.open_table(EPHEMERAL_KEYS),
+        ) {
+            (Ok(keys), Ok(memos)) => (keys, memos),
+            (Err(TableError::TableDoesNotExist(_)), _)
+            | (_, Err(TableError::TableDoesNotExist(_))) => return Ok(0),
+            (Err(e), _) | (_, Err(e)) => return Err(e.into()),
+        };
+        let transfers = read_txn.open_table(TRANSFERS)?;
+        // The cursor only moves once there is a scan key. A key created later can only have been paid
+        // after it was created, so after every leaf synced by then
+        let start = match read_txn.open_table(SCAN_CURSOR) {
+            Ok(cursor) => cursor.get(())?.map_or(0, |cursor| cursor.value()),
+            Err(TableError::TableDoesNotExist(_)) => 0,
+            Err(e) => return Err(e.into()),
+        };
+        let end = transfers.len()?;
+        let addresses = match read_txn.open_table(VAP_ADDR) {
+            Ok(addresses) => Some(addresses),
+            Err(TableError::TableDoesNotExist(_)) => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut found = Vec::new();
+        for key in keys.iter()? {
+            let (_, key) = key?;
+            let key = key.value();
+
+            for memo in memos.range(start..end)? {
+                let (leaf_index, ephemeral_key) = memo?;
+                let Some(transfer) = transfers.get(leaf_index.value())? else {
+                    continue;
+                };
+                let to = transfer.value().to;
+                let known = match &addresses {
+                    Some(addresses) => addresses.get(to)?.is_some(),
+                    None => false,
+                };
+                if known {
+                    continue;
+                }
+
+                let recovered =
+                    recover_stealth_address(&key.scan_secret, key.recipient, ephemeral_key.value())
+                        .filter(|(addr, _)| *addr == to);
+                if let Some((addr, secret)) = recovered {
+                    found.push(VaporAddressRecord {
+                        addr,
+                        recipient: key.recipient,
+                        secret,
+                    });
+                }
+            }
+        }
+        (found, start, end)
+    };
+
+    if end > start {
+        let write_txn = db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(VAP_ADDR)?;
+            for record in &found {
+                tracing::info!(
+                    "Found stealth payment to {}",
+                    bs58::encode(record.addr).into_string()
+                );
+                table.insert(&record.addr, record)?;
+            }
+            write_txn.open_table(SCAN_CURSOR)?.insert((), end)?;
+        }
+        write_txn.commit()?;
+    }
+
+    Ok(found.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StealthKeyRecord, TransferEvent};
+    use vaporize_addresses::{ScanSecret, StealthPayment};
+
+    /// Sync a transfer with an optional memo at the next leaf
+    fn sync_transfer(db: &redb::Database, to: [u8; 32], ephemeral_key: Option<[u8; 32]>) {
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut transfers = write_txn.open_table(TRANSFERS).unwrap();
+            let index = transfers.len().unwrap();
+            let transfer = TransferEvent {
+                to,
+                amount: 100,
+                slot: 1,
+            };
+            transfers.insert(&index, &transfer).unwrap();
+            let mut memos = write_txn.open_table(EPHEMERAL_KEYS).unwrap();
+            if let Some(key) = ephemeral_key {
+                memos.insert(&index, &key).unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_scan_only_new_leaves() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let db = redb::Database::create(file.path()).unwrap();
+        let mut rng = rand::thread_rng();
+
+        let recipient = [3; 32];
+        let scan_secret = ScanSecret::generate(&mut rng);
+        let meta = scan_secret.meta_address(recipient);
+        let write_txn = db.begin_write().unwrap();
+        write_txn
+            .open_table(STEALTH_KEYS)
+            .unwrap()
+            .insert(
+                &recipient,
+                &StealthKeyRecord {
+                    recipient,
+                    scan_secret,
+                },
+            )
+            .unwrap();
+        write_txn.commit().unwrap();
+
+        let first = StealthPayment::new(&mut rng, &meta).unwrap();
+        sync_transfer(&db, [9; 32], None);
+        sync_transfer(&db, first.vapor_addr, Some(first.ephemeral_key));
+        assert_eq!(scan(&db).unwrap(), 1);
+
+        // A memo at a leaf already scanned is not checked again
+        let second = StealthPayment::new(&mut rng, &meta).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        write_txn
+            .open_table(EPHEMERAL_KEYS)
+            .unwrap()
+            .insert(&0, &second.ephemeral_key)
+            .unwrap();
+        write_txn.commit().unwrap();
+        assert_eq!(scan(&db).unwrap(), 0);
+
+        sync_transfer(&db, second.vapor_addr, Some(second.ephemeral_key));
+        assert_eq!(scan(&db).unwrap(), 1);
+
+        let read_txn = db.begin_read().unwrap();
+        let addresses = read_txn.open_table(VAP_ADDR).unwrap();
+        assert!(addresses.get(first.vapor_addr).unwrap().is_some());
+        assert!(addresses.get(second.vapor_addr).unwrap().is_some());
+        assert_eq!(
+            read_txn
+                .open_table(SCAN_CURSOR)
+                .unwrap()
+                .get(())
+                .unwrap()
+                .unwrap()
+                .value(),
+            3
+        );
+    }
+}
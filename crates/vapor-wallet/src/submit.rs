@@ -0,0 +1,255 @@
+use std::thread::sleep;
+use std::time::Duration;
+
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{
+    CommitmentConfig, RpcSendTransactionConfig, RpcSimulateTransactionConfig,
+};
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_instruction_error::InstructionError;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_transaction_error::TransactionError;
+use solana_transaction_status::TransactionConfirmationStatus;
+use vapor_client::errors::{ProgramError, failing_program};
+
+use crate::{Result, WalletError};
+
+/// Upper bound on the compute a single transaction may request
+const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+/// Headroom added on top of the simulated compute units
+const COMPUTE_UNIT_MARGIN_PERCENT: u64 = 10;
+/// How often to poll for the status of a sent transaction
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Debug)]
+pub struct SubmitOptions {
+    /// Compute unit price in micro-lamports. Estimated from recent fees when not set
+    pub priority_fee: Option<u64>,
+    /// Number of times to resend with a fresh blockhash if the transaction expires before confirming
+    pub max_retries: usize,
+}
+
+impl Default for SubmitOptions {
+    fn default() -> Self {
+        Self {
+            priority_fee: None,
+            max_retries: 3,
+        }
+    }
+}
+
+/// Simulate, size and send a transaction, resending with a fresh blockhash until it confirms or retries run out.
+/// Compute budget instructions are added here so `instructions` should not include them.
+pub(crate) fn submit(
+    client: &RpcClient,
+    payer: &Keypair,
+    instructions: &[Instruction],
+    options: &SubmitOptions,
+) -> Result<Signature> {
+    let priority_fee = match options.priority_fee {
+        Some(fee) => fee,
+        None => estimate_priority_fee(client, instructions)?,
+    };
+
+    // Simulate at the maximum limit so the compute budget instructions are accounted for in the result
+    let units_consumed = simulate(
+        client,
+        payer,
+        &with_compute_budget(instructions, MAX_COMPUTE_UNITS, priority_fee),
+    )?;
+    let compute_units = (units_consumed * (100 + COMPUTE_UNIT_MARGIN_PERCENT) / 100)
+        .min(MAX_COMPUTE_UNITS as u64) as u32;
+    tracing::info!(
+        "Simulation used {} CU, requesting {} CU at {} micro-lamports/CU",
+        units_consumed,
+        compute_units,
+        priority_fee
+    );
+
+    let instructions = with_compute_budget(instructions, compute_units, priority_fee);
+    for attempt in 0..=options.max_retries {
+        let blockhash = client.get_latest_blockhash()?;
+        let tx = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            blockhash,
+        );
+
+        // Already simulated so skip the preflight, and handle resending ourselves
+        let sig = client.send_transaction_with_config(
+            &tx,
+            RpcSendTransactionConfig {
+                skip_preflight: true,
+                max_retries: Some(0),
+                ..Default::default()
+            },
+        )?;
+        tracing::info!("Sent transaction {} (attempt {})", sig, attempt + 1);
+
+        let mut last_status = None;
+        while client.is_blockhash_valid(&blockhash, CommitmentConfig::processed())? {
+            let status = client
+                .get_signature_statuses(&[sig])?
+                .value
+                .into_iter()
+                .next()
+                .flatten();
+            if let Some(status) = status {
+                if let Some(err) = status.err {
+                    return Err(WalletError::Transaction(format!(
+                        "transaction {} failed: {}",
+                        sig,
+                        describe(&tx, err.into(), &[])
+                    )));
+                }
+                if status.confirmation_status != last_status {
+                    if let Some(confirmation) = &status.confirmation_status {
+                        tracing::info!("  {:?}", confirmation);
+                    }
+                    last_status = status.confirmation_status.clone();
+                }
+                if matches!(
+                    last_status,
+                    Some(TransactionConfirmationStatus::Confirmed)
+                        | Some(TransactionConfirmationStatus::Finalized)
+                ) {
+                    return Ok(sig);
+                }
+            }
+            sleep(POLL_INTERVAL);
+        }
+
+        // The transaction may have landed just before the blockhash expired
+        if let Some(Ok(())) = client.get_signature_status(&sig)? {
+            return Ok(sig);
+        }
+        tracing::info!("Blockhash expired before the transaction confirmed, retrying");
+    }
+
+    Err(WalletError::Transaction(format!(
+        "transaction did not confirm after {} attempts",
+        options.max_retries + 1
+    )))
+}
+
+fn simulate(client: &RpcClient, payer: &Keypair, instructions: &[Instruction]) -> Result<u64> {
+    let tx = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    let result = client
+        .simulate_transaction_with_config(
+            &tx,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(CommitmentConfig::confirmed()),
+                ..Default::default()
+            },
+        )?
+        .value;
+
+    if let Some(err) = result.err {
+        let logs = result.logs.unwrap_or_default();
+        for log in &logs {
+            tracing::debug!("{}", log);
+        }
+        return Err(WalletError::Transaction(format!(
+            "simulation failed: {}",
+            describe(&tx, err.into(), &logs)
+        )));
+    }
+
+    result.units_consumed.ok_or_else(|| {
+        WalletError::Transaction("simulation did not report compute units consumed".to_string())
+    })
+}
+
+/// Median of the recent prioritization fees paid to write lock the accounts used by `instructions`
+fn estimate_priority_fee(client: &RpcClient, instructions: &[Instruction]) -> Result<u64> {
+    let writable: Vec<Pubkey> = instructions
+        .iter()
+        .flat_map(|ix| ix.accounts.iter())
+        .filter(|meta| meta.is_writable)
+        .map(|meta| meta.pubkey)
+        .collect();
+
+    let mut fees: Vec<u64> = client
+        .get_recent_prioritization_fees(&writable)?
+        .into_iter()
+        .map(|fee| fee.prioritization_fee)
+        .collect();
+    fees.sort_unstable();
+    Ok(fees.get(fees.len() / 2).copied().unwrap_or(0))
+}
+
+fn with_compute_budget(
+    instructions: &[Instruction],
+    compute_units: u32,
+    priority_fee: u64,
+) -> Vec<Instruction> {
+    let mut all = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(compute_units),
+        ComputeBudgetInstruction::set_compute_unit_price(priority_fee),
+    ];
+    all.extend_from_slice(instructions);
+    all
+}
+
+/// Describe a transaction error, decoding custom errors from the vapor token programs.
+/// The program that raised the error is read from `logs` where there are any, since an error raised
+/// through a CPI, such as the transfer hook's through Token-2022, is reported on the outer instruction
+fn describe(tx: &Transaction, err: TransactionError, logs: &[String]) -> String {
+    if let TransactionError::InstructionError(index, InstructionError::Custom(code)) = &err {
+        let program_id = failing_program(logs).or_else(|| {
+            tx.message
+                .instructions
+                .get(*index as usize)
+                .and_then(|ix| tx.message.account_keys.get(ix.program_id_index as usize))
+                .copied()
+        });
+        if let Some(decoded) = program_id.and_then(|id| ProgramError::decode(&id, *code)) {
+            return format!("instruction {}: {}", index, decoded);
+        }
+    }
+    err.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vapor_client::{CONDENSER_PROGRAM_ID, TRANSFER_HOOK_PROGRAM_ID};
+
+    #[test]
+    fn test_describe_error_raised_through_cpi() {
+        let payer = Pubkey::new_unique();
+        let tx = Transaction::new_with_payer(
+            &[Instruction::new_with_bytes(
+                CONDENSER_PROGRAM_ID,
+                &[],
+                vec![],
+            )],
+            Some(&payer),
+        );
+        let err = || TransactionError::InstructionError(0, InstructionError::Custom(6001));
+
+        // Without logs the error is decoded as the condenser's, the program of the instruction
+        assert!(describe(&tx, err(), &[]).contains("condenser error InvalidProof"));
+
+        let logs = vec![
+            format!(
+                "Program {} failed: custom program error: 0x1771",
+                TRANSFER_HOOK_PROGRAM_ID
+            ),
+            format!(
+                "Program {} failed: custom program error: 0x1771",
+                CONDENSER_PROGRAM_ID
+            ),
+        ];
+        assert_eq!(
+            describe(&tx, err(), &logs),
+            "instruction 0: transfer hook error MerkleTreeFull: Merkle tree is full: cannot add more leaves"
+        );
+    }
+}
@@ -0,0 +1,334 @@
+use redb::{ReadableDatabase, ReadableTable, ReadableTableMetadata, TableError};
+use serde::Serialize;
+use solana_client::{
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+    rpc_config::{CommitmentConfig, RpcTransactionConfig},
+    rpc_response::OptionSerializer,
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::UiTransactionEncoding;
+use std::{str::FromStr, time::Duration};
+
+use transfer_tree::TransferTree;
+use vapor_client::{
+    accounts::decode_merkle_tree, events::leaves_with_memos, indexer::IndexerClient,
+};
+use vaporize_addresses::parse_stealth_memo;
+
+use crate::{
+    EPHEMERAL_KEYS, LEGACY_TRANSFERS, RPC_CURSOR, Result, TRANSFERS, TREE_HEIGHT, TransferEvent,
+    WalletError, stealth,
+};
+
+/// What a sync found
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SyncReport {
+    /// Transfers in the wallet once synced
+    pub transfers: u64,
+    /// Transfers in the on-chain tree when it is ahead of the indexer the wallet synced from
+    pub indexer_behind: Option<u64>,
+    /// Stealth payments to this wallet found among the new transfers
+    pub stealth_payments: usize,
+}
+
+/// Fetch new transfers of the mint into the wallet then look for stealth payments among them.
+/// Transfers come from the indexer at `indexer_url` if given, otherwise by scanning the mint's history over RPC
+pub(crate) fn sync(
+    db: &redb::Database,
+    rpc_url: &str,
+    mint: &Pubkey,
+    indexer_url: Option<&str>,
+) -> Result<SyncReport> {
+    let mut report = SyncReport::default();
+    match indexer_url {
+        Some(url) => {
+            fetch_from_indexer(db, url)?;
+            let onchain = check_root(db, rpc_url, mint)?;
+            report.transfers = transfer_count(db)?;
+            report.indexer_behind = (onchain != report.transfers).then_some(onchain);
+        }
+        None => {
+            fetch_transfers(db, rpc_url, mint)?;
+            check_root(db, rpc_url, mint)?;
+            report.transfers = transfer_count(db)?;
+        }
+    }
+    report.stealth_payments = stealth::scan(db)?;
+    Ok(report)
+}
+
+/// Number of transfers in the wallet
+fn transfer_count(db: &redb::Database) -> Result<u64> {
+    let read_txn = db.begin_read()?;
+    match read_txn.open_table(TRANSFERS) {
+        Ok(transfers) => Ok(transfers.len()?),
+        Err(TableError::TableDoesNotExist(_)) => Ok(0),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Drop the slot indexed transfers of earlier wallets
+pub(crate) fn migrate(db: &redb::Database) -> Result<()> {
+    let write_txn = db.begin_write()?;
+    let dropped = write_txn.delete_table(LEGACY_TRANSFERS)?;
+    write_txn.commit()?;
+    if dropped {
+        tracing::info!("Dropped transfers synced by slot, they will be synced again by leaf index");
+    }
+    Ok(())
+}
+
+/// Write transfers to the wallet from leaf `start`, along with the ephemeral key of any stealth memo attached.
+/// A leaf that is already synced must hold the same transfer. Fails rather than overwrite it with another,
+/// which would leave the wallet with a different tree than the chain. Returns the next leaf index
+fn append_transfers(
+    write_txn: &redb::WriteTransaction,
+    start: u64,
+    new_transfers: impl IntoIterator<Item = (TransferEvent, Option<[u8; 32]>)>,
+) -> Result<u64> {
+    let mut transfers = write_txn.open_table(TRANSFERS)?;
+    let mut memos = write_txn.open_table(EPHEMERAL_KEYS)?;
+    let mut index = start;
+    for (transfer, ephemeral_key) in new_transfers {
+        if let Some(existing) = transfers.insert(&index, &transfer)? {
+            if existing.value() != transfer {
+                return Err(WalletError::LeafAlreadySynced(index));
+            }
+        }
+        if let Some(key) = ephemeral_key {
+            memos.insert(&index, &key)?;
+        }
+        index += 1;
+    }
+    Ok(index)
+}
+
+/// Download every leaf after the ones already in the wallet.
+/// Whole ranges are requested so the indexer does not learn which transfers belong to this wallet
+fn fetch_from_indexer(db: &redb::Database, url: &str) -> Result<()> {
+    let indexer = IndexerClient::new(url);
+    let start = transfer_count(db)?;
+    let end = indexer.frontier()?.next_index;
+    tracing::info!("Fetching leaves {}..{} from {}", start, end, url);
+
+    let leaves = indexer.leaves(start, end)?;
+    for (expected, leaf) in (start..).zip(&leaves) {
+        if leaf.index != expected {
+            return Err(WalletError::IndexerOutOfOrder {
+                expected,
+                found: leaf.index,
+            });
+        }
+    }
+
+    let write_txn = db.begin_write()?;
+    append_transfers(
+        &write_txn,
+        start,
+        leaves.iter().map(|leaf| {
+            let transfer = TransferEvent {
+                to: leaf.to,
+                amount: leaf.amount,
+                slot: leaf.slot,
+            };
+            (transfer, leaf.ephemeral_key)
+        }),
+    )?;
+    write_txn.commit()?;
+
+    Ok(())
+}
+
+/// Check the tree rebuilt from the wallet's transfers against the tree account on chain and return the
+/// number of transfers on chain. Neither the indexer nor the RPC history is trusted, a wrong or missing
+/// leaf would otherwise only show up as a failed condense
+fn check_root(db: &redb::Database, rpc_url: &str, mint: &Pubkey) -> Result<u64> {
+    let client = RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed());
+    let (tree_account, _) = vapor_client::pda::merkle_tree(mint);
+    let onchain = decode_merkle_tree(&client.get_account_data(&tree_account)?)?;
+
+    let mut tree = TransferTree::<TREE_HEIGHT>::new();
+    let read_txn = db.begin_read()?;
+    match read_txn.open_table(TRANSFERS) {
+        Ok(transfers) => {
+            for result in transfers.iter()? {
+                let (_, transfer) = result?;
+                tree.append_transfer(transfer.value().to, transfer.value().amount)?;
+            }
+        }
+        Err(TableError::TableDoesNotExist(_)) => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    if tree.next_index() == onchain.next_index && tree.root() != onchain.root {
+        return Err(WalletError::RootMismatch);
+    }
+    Ok(onchain.next_index)
+}
+
+/// Fetch the mint's transactions since the last sync over RPC and append their transfers in the order they were made
+fn fetch_transfers(db: &redb::Database, rpc_url: &str, mint: &Pubkey) -> Result<()> {
+    let client = RpcClient::new_with_timeout_and_commitment(
+        rpc_url.to_string(),
+        Duration::from_secs(30),
+        CommitmentConfig::confirmed(),
+    );
+
+    // Without a cursor the whole history is fetched from the first leaf, which also checks any leaves
+    // synced from an indexer
+    let (until, mut next_index) = {
+        let read_txn = db.begin_read()?;
+        let cursor = match read_txn.open_table(RPC_CURSOR) {
+            Ok(cursor) => cursor.get(())?.map(|cursor| {
+                let (signature, next_index) = cursor.value();
+                (signature.to_string(), next_index)
+            }),
+            Err(TableError::TableDoesNotExist(_)) => None,
+            Err(e) => return Err(e.into()),
+        };
+        match cursor {
+            Some((signature, next_index)) => (Some(parse_signature(&signature)?), next_index),
+            None => (None, 0),
+        }
+    };
+
+    // Signatures come newest first, so collect every new one before appending anything to keep leaves in tree order
+    tracing::info!("Starting sync from mint {}", mint);
+    let mut signatures = Vec::new();
+    let mut before: Option<Signature> = None;
+    loop {
+        let page = client.get_signatures_for_address_with_config(
+            mint,
+            GetConfirmedSignaturesForAddress2Config {
+                limit: Some(1000),
+                before,
+                until,
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )?;
+        let Some(oldest) = page.last() else {
+            break;
+        };
+        before = Some(parse_signature(&oldest.signature)?);
+        let complete = page.len() < 1000;
+        signatures.extend(page);
+        if complete {
+            break;
+        }
+    }
+    tracing::info!("Found {} new transactions", signatures.len());
+
+    for info in signatures.iter().rev() {
+        let mut new_transfers = Vec::new();
+        // Failed transactions made no transfers but still move the cursor
+        if info.err.is_none() {
+            let tx = client.get_transaction_with_config(
+                &parse_signature(&info.signature)?,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Json),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )?;
+            let logs = match tx.transaction.meta.map(|meta| meta.log_messages) {
+                Some(OptionSerializer::Some(logs)) => logs,
+                _ => Vec::new(),
+            };
+
+            // A stealth transfer carries the sender's ephemeral key in a memo right after it.
+            // Transfers skipped by the admission policy have no leaf
+            for (transfer, ephemeral_key) in leaves_with_memos(&logs, parse_stealth_memo) {
+                let event = TransferEvent {
+                    to: transfer.to.to_bytes(),
+                    amount: transfer.amount,
+                    slot: tx.slot,
+                };
+                tracing::debug!("Slot {}: Found transfer event {:?}", tx.slot, event);
+                new_transfers.push((event, ephemeral_key));
+            }
+        }
+
+        // The transfers and the cursor are committed together so an interrupted sync picks up where it stopped
+        let write_txn = db.begin_write()?;
+        next_index = append_transfers(&write_txn, next_index, new_transfers)?;
+        write_txn
+            .open_table(RPC_CURSOR)?
+            .insert((), (info.signature.as_str(), next_index))?;
+        write_txn.commit()?;
+    }
+
+    Ok(())
+}
+
+fn parse_signature(s: &str) -> Result<Signature> {
+    Signature::from_str(s).map_err(|e| WalletError::InvalidInput {
+        what: "signature",
+        reason: e.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(slot: u64) -> TransferEvent {
+        TransferEvent {
+            to: [slot as u8; 32],
+            amount: 100,
+            slot,
+        }
+    }
+
+    #[test]
+    fn test_append_transfers_in_one_slot() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let db = redb::Database::create(file.path()).unwrap();
+
+        let write_txn = db.begin_write().unwrap();
+        let next = append_transfers(
+            &write_txn,
+            0,
+            [(transfer(7), None), (transfer(7), Some([1; 32]))],
+        )
+        .unwrap();
+        write_txn.commit().unwrap();
+        assert_eq!(next, 2);
+        assert_eq!(transfer_count(&db).unwrap(), 2);
+
+        // Syncing the same leaves again is harmless, a different transfer at a synced leaf is not
+        let write_txn = db.begin_write().unwrap();
+        assert_eq!(
+            append_transfers(&write_txn, 1, [(transfer(7), None)]).unwrap(),
+            2
+        );
+        assert!(matches!(
+            append_transfers(&write_txn, 1, [(transfer(8), None)]),
+            Err(WalletError::LeafAlreadySynced(1))
+        ));
+    }
+
+    #[test]
+    fn test_migrate_drops_slot_indexed_transfers() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let db = redb::Database::create(file.path()).unwrap();
+
+        let write_txn = db.begin_write().unwrap();
+        write_txn
+            .open_table(LEGACY_TRANSFERS)
+            .unwrap()
+            .insert(&7, [0u8; 40].as_slice())
+            .unwrap();
+        write_txn.commit().unwrap();
+
+        migrate(&db).unwrap();
+        let read_txn = db.begin_read().unwrap();
+        assert!(matches!(
+            read_txn.open_table(LEGACY_TRANSFERS),
+            Err(TableError::TableDoesNotExist(_))
+        ));
+        drop(read_txn);
+
+        // Opening a wallet without legacy tables is a no-op
+        migrate(&db).unwrap();
+    }
+}
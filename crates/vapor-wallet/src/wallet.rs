@@ -0,0 +1,518 @@
+use std::path::Path;
+
+use condenser_witness::DisclosureWitness;
+use rand::rngs::OsRng;
+use redb::{ReadableDatabase, ReadableTable, TableError};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use vaporize_addresses::{
+    ScanSecret, StealthMetaAddress, VanityProgress, generate_vanity_vaporize_address,
+    generate_vaporize_address,
+};
+
+use crate::anonymity::{AnonymitySet, load_transfers};
+use crate::condense::{self, CondensePlan, CondenseProof, Withdrawal, withdrawal_total};
+use crate::disclosure::{self, DisclosedDeposit, Disclosure};
+use crate::schedule::{self, CondenseJob, RetryPolicy, unix_now};
+use crate::send::{self, Destination, SentTransfer};
+use crate::submit::SubmitOptions;
+use crate::sync::{self, SyncReport};
+use crate::{
+    Deposit, Result, STEALTH_KEYS, StealthKeyRecord, TRANSFERS, TREE_HEIGHT, TransferEvent,
+    VAP_ADDR, VaporAddressRecord, WalletError, build_merkle_proof, prove,
+};
+
+/// Where a wallet reads the chain from and which mint it tracks
+#[derive(Clone, Debug)]
+pub struct WalletConfig {
+    pub rpc_url: String,
+    /// Mint of the vapor token managed by this wallet. Only a single mint is supported per wallet
+    pub mint: Pubkey,
+    /// Sync transfers from a vapor-indexer at this URL instead of scanning the mint's history over RPC
+    pub indexer_url: Option<String>,
+    /// Pass the prover's progress through to stderr. Turn off when stderr is in use, e.g. by a terminal UI,
+    /// and the output is captured and reported only if proving fails
+    pub show_prover_output: bool,
+    /// Version of the condenser verifying key to prove against. It must match the circuit the prover runs
+    pub vk_version: u32,
+    /// Version of the disclosure verifying key to prove disclosures against
+    pub disclosure_vk_version: u32,
+}
+
+/// A vapor token wallet backed by a redb database
+pub struct Wallet {
+    db: redb::Database,
+    config: WalletConfig,
+}
+
+impl Wallet {
+    /// Open the wallet database at `path`, creating it if it does not exist
+    pub fn open(path: impl AsRef<Path>, config: WalletConfig) -> Result<Self> {
+        Self::new(redb::Database::create(path)?, config)
+    }
+
+    /// Wrap an open wallet database, migrating tables written by earlier versions
+    pub fn new(db: redb::Database, config: WalletConfig) -> Result<Self> {
+        sync::migrate(&db)?;
+        Ok(Self { db, config })
+    }
+
+    pub fn config(&self) -> &WalletConfig {
+        &self.config
+    }
+
+    pub fn mint(&self) -> &Pubkey {
+        &self.config.mint
+    }
+
+    /// Fetch new transfers of the mint and look for stealth payments among them
+    pub fn sync(&self) -> Result<SyncReport> {
+        sync::sync(
+            &self.db,
+            &self.config.rpc_url,
+            &self.config.mint,
+            self.config.indexer_url.as_deref(),
+        )
+    }
+
+    /// Generate a vaporize address for the recipient and store it in the wallet.
+    /// The secret in the returned record is required to condense deposits to the address
+    pub fn new_address(&self, recipient: [u8; 32]) -> Result<VaporAddressRecord> {
+        let (addr, secret) = generate_vaporize_address(&mut rand::thread_rng(), recipient);
+        self.store_address(VaporAddressRecord {
+            addr,
+            recipient,
+            secret,
+        })
+    }
+
+    /// Grind vaporize addresses for the recipient until one starts with `prefix` and store it in the wallet.
+    /// `on_progress` is called roughly once a second while searching
+    pub fn new_vanity_address(
+        &self,
+        recipient: [u8; 32],
+        prefix: &str,
+        threads: usize,
+        on_progress: impl FnMut(VanityProgress),
+    ) -> Result<VaporAddressRecord> {
+        let (addr, secret) =
+            generate_vanity_vaporize_address(recipient, prefix, threads, on_progress)?;
+        self.store_address(VaporAddressRecord {
+            addr,
+            recipient,
+            secret,
+        })
+    }
+
+    fn store_address(&self, record: VaporAddressRecord) -> Result<VaporAddressRecord> {
+        let write_txn = self.db.begin_write()?;
+        write_txn
+            .open_table(VAP_ADDR)?
+            .insert(&record.addr, &record)?;
+        write_txn.commit()?;
+        Ok(record)
+    }
+
+    /// Stealth meta-address for the recipient, generating its scan key the first time.
+    /// The scan key is reused so the meta-address stays the same for a recipient
+    pub fn meta_address(&self, recipient: [u8; 32]) -> Result<StealthMetaAddress> {
+        let write_txn = self.db.begin_write()?;
+        let meta = {
+            let mut table = write_txn.open_table(STEALTH_KEYS)?;
+            let existing = table.get(recipient)?.map(|record| record.value());
+            let record = match existing {
+                Some(record) => record,
+                None => {
+                    let record = StealthKeyRecord {
+                        recipient,
+                        scan_secret: ScanSecret::generate(&mut rand::thread_rng()),
+                    };
+                    table.insert(&recipient, &record)?;
+                    record
+                }
+            };
+            record.scan_secret.meta_address(recipient)
+        };
+        write_txn.commit()?;
+        Ok(meta)
+    }
+
+    /// All vaporize addresses held by the wallet
+    pub fn addresses(&self) -> Result<Vec<VaporAddressRecord>> {
+        let read_txn = self.db.begin_read()?;
+        let addresses = match read_txn.open_table(VAP_ADDR) {
+            Ok(addresses) => addresses,
+            Err(TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        addresses
+            .iter()?
+            .map(|result| Ok(result?.1.value()))
+            .collect()
+    }
+
+    pub fn address(&self, vapor_addr: [u8; 32]) -> Result<VaporAddressRecord> {
+        let read_txn = self.db.begin_read()?;
+        let addresses = read_txn.open_table(VAP_ADDR)?;
+        let record = addresses
+            .get(vapor_addr)?
+            .ok_or_else(|| WalletError::AddressNotFound(bs58::encode(vapor_addr).into_string()))?
+            .value();
+        Ok(record)
+    }
+
+    /// Synced deposits to one of the wallet's vaporize addresses
+    pub fn deposits(&self, vapor_addr: [u8; 32]) -> Result<Vec<Deposit>> {
+        self.address(vapor_addr)?;
+
+        let read_txn = self.db.begin_read()?;
+        let transfers = match read_txn.open_table(TRANSFERS) {
+            Ok(transfers) => transfers,
+            Err(TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut deposits = Vec::new();
+        for result in transfers.iter()? {
+            let (leaf_index, transfer) = result?;
+            let transfer = transfer.value();
+            if transfer.to == vapor_addr {
+                deposits.push(Deposit {
+                    leaf_index: leaf_index.value(),
+                    slot: transfer.slot,
+                    amount: transfer.amount,
+                });
+            }
+        }
+        Ok(deposits)
+    }
+
+    /// Every synced transfer of the mint in tree order
+    pub fn transfers(&self) -> Result<Vec<TransferEvent>> {
+        load_transfers(&self.db)
+    }
+
+    /// How well the transfer at `leaf_index` hides among the other synced transfers
+    pub fn anonymity_set(&self, leaf_index: u64) -> Result<AnonymitySet> {
+        Ok(AnonymitySet::analyze(
+            &self.transfers()?,
+            leaf_index as usize,
+        ))
+    }
+
+    /// Merkle proof of the transfer at `leaf_index` against the root of the synced tree
+    #[allow(clippy::type_complexity)]
+    pub fn merkle_proof(
+        &self,
+        leaf_index: u64,
+    ) -> Result<([[u8; 32]; TREE_HEIGHT], [u8; TREE_HEIGHT], [u8; 32])> {
+        build_merkle_proof::build_merkle_proof::<TREE_HEIGHT>(&self.db, leaf_index as usize)
+    }
+
+    /// Total condensed to the recipient so far. A condense of any deposit to the recipient can
+    /// raise it at most to that deposit's amount
+    pub fn total_withdrawn(&self, recipient: [u8; 32]) -> Result<u64> {
+        condense::total_withdrawn(
+            &self.config.rpc_url,
+            &self.config.mint,
+            &Pubkey::new_from_array(recipient),
+        )
+    }
+
+    /// Work out a condense of the deposit at `leaf_index` to one of the wallet's addresses.
+    /// `amount` condenses only part of the deposit, by default everything not yet withdrawn to the recipient.
+    /// Sync first so the proof is against a recent root
+    pub fn plan_condense(
+        &self,
+        vapor_addr: [u8; 32],
+        leaf_index: u64,
+        amount: Option<u64>,
+    ) -> Result<CondensePlan> {
+        condense::plan(
+            &self.db,
+            &self.config.rpc_url,
+            &self.config.mint,
+            vapor_addr,
+            leaf_index,
+            Withdrawal::Amount(amount),
+        )
+    }
+
+    /// Prove a planned condense and verify the proof locally
+    pub fn prove_condense(&self, plan: &CondensePlan) -> Result<CondenseProof> {
+        condense::prove(
+            &self.config.rpc_url,
+            &self.config.mint,
+            self.config.vk_version,
+            plan,
+            self.config.show_prover_output,
+        )
+    }
+
+    pub fn submit_condense(
+        &self,
+        payer: &Keypair,
+        proof: &CondenseProof,
+        options: &SubmitOptions,
+    ) -> Result<Signature> {
+        condense::submit_proof(
+            &self.config.rpc_url,
+            payer,
+            &self.config.mint,
+            proof,
+            options,
+        )
+    }
+
+    /// Plan, prove and submit a condense in one go
+    pub fn condense(
+        &self,
+        payer: &Keypair,
+        vapor_addr: [u8; 32],
+        leaf_index: u64,
+        amount: Option<u64>,
+        options: &SubmitOptions,
+    ) -> Result<Signature> {
+        let plan = self.plan_condense(vapor_addr, leaf_index, amount)?;
+        tracing::info!(
+            "Condensing {} of {} ({} already withdrawn to the recipient)",
+            plan.amount(),
+            plan.deposit_amount,
+            plan.withdrawn
+        );
+        let proof = self.prove_condense(&plan)?;
+        self.submit_condense(payer, &proof, options)
+    }
+
+    /// Transfer tokens from the payer's token account to a destination
+    pub fn send(
+        &self,
+        payer: &Keypair,
+        destination: &Destination,
+        amount: u64,
+    ) -> Result<SentTransfer> {
+        send::send(
+            &self.config.rpc_url,
+            payer,
+            &self.config.mint,
+            destination,
+            amount,
+        )
+    }
+
+    /// Disclosure revealing deposits to one of the wallet's addresses and proving that it commits to its recipient,
+    /// bound to the condense transactions with the given signatures that the deposits account for.
+    /// If no slots are given all deposits to the address are disclosed
+    pub fn disclose(
+        &self,
+        vapor_addr: [u8; 32],
+        slots: &[u64],
+        condenses: &[Signature],
+    ) -> Result<Disclosure> {
+        let addr_record = self.address(vapor_addr)?;
+        let deposits: Vec<DisclosedDeposit> = self
+            .deposits(vapor_addr)?
+            .into_iter()
+            .filter(|deposit| slots.is_empty() || slots.contains(&deposit.slot))
+            .map(|deposit| DisclosedDeposit {
+                slot: deposit.slot,
+                leaf_index: deposit.leaf_index,
+                amount: deposit.amount,
+            })
+            .collect();
+
+        if let Some(slot) = slots
+            .iter()
+            .find(|slot| !deposits.iter().any(|d| d.slot == **slot))
+        {
+            return Err(WalletError::NoDepositInSlot(*slot));
+        }
+        if deposits.is_empty() {
+            return Err(WalletError::NoDeposits);
+        }
+        disclosure::check_condenses(
+            &self.config.rpc_url,
+            &self.config.mint,
+            &addr_record.recipient,
+            &deposits,
+            condenses,
+        )?;
+
+        let verifying_key = disclosure::disclosure_key(
+            &self.config.rpc_url,
+            &self.config.mint,
+            self.config.disclosure_vk_version,
+        )?;
+        let witness = DisclosureWitness::new(
+            addr_record.recipient,
+            vapor_addr,
+            addr_record.secret.clone(),
+        );
+
+        tracing::info!("Generating disclosure proof...");
+        let (proof, pub_witness) =
+            prove::prove_disclosure(witness, self.config.show_prover_output)?;
+
+        let disclosure = Disclosure {
+            mint: self.config.mint.to_string(),
+            recipient: bs58::encode(addr_record.recipient).into_string(),
+            vapor_addr: bs58::encode(vapor_addr).into_string(),
+            deposits,
+            condenses: condenses.iter().map(|s| s.to_string()).collect(),
+            vk_version: self.config.disclosure_vk_version,
+            proof: hex::encode(proof),
+            public_witness: hex::encode(pub_witness),
+        };
+        // A prover running another circuit than the registered key would hand out a disclosure nobody can verify
+        disclosure.verify_proof(&verifying_key)?;
+        Ok(disclosure)
+    }
+
+    /// Check a disclosure against the synced transfers and its condense transactions, and verify its proof
+    /// against the disclosure key registered for the mint. Fails if any part of the disclosure does not hold
+    pub fn verify_disclosure(&self, disclosure: &Disclosure) -> Result<()> {
+        let invalid = |reason: String| Err(WalletError::InvalidDisclosure(reason));
+
+        if disclosure.mint != self.config.mint.to_string() {
+            return invalid(format!(
+                "disclosure is for mint {} but this wallet tracks {}",
+                disclosure.mint, self.config.mint
+            ));
+        }
+
+        let (recipient, vapor_addr) = disclosure.public_inputs()?;
+        if bs58::encode(recipient).into_string() != disclosure.recipient
+            || bs58::encode(vapor_addr).into_string() != disclosure.vapor_addr
+        {
+            return invalid(
+                "proof does not commit to the stated recipient and vapor address".to_string(),
+            );
+        }
+
+        // Every disclosed deposit must exist in the synced transfer tree at the stated position
+        let read_txn = self.db.begin_read()?;
+        {
+            let transfers = read_txn.open_table(TRANSFERS)?;
+            for deposit in &disclosure.deposits {
+                let Some(transfer) = transfers.get(deposit.leaf_index)? else {
+                    return invalid(format!("no transfer found at leaf {}", deposit.leaf_index));
+                };
+                let transfer = transfer.value();
+
+                if transfer.slot != deposit.slot
+                    || transfer.to != vapor_addr
+                    || transfer.amount != deposit.amount
+                {
+                    return invalid(format!(
+                        "transfer at leaf {} does not match the disclosed deposit",
+                        deposit.leaf_index
+                    ));
+                }
+            }
+        }
+
+        disclosure::check_condenses(
+            &self.config.rpc_url,
+            &self.config.mint,
+            &recipient,
+            &disclosure.deposits,
+            &disclosure.condense_signatures()?,
+        )?;
+        disclosure.verify_proof(&disclosure::disclosure_key(
+            &self.config.rpc_url,
+            &self.config.mint,
+            disclosure.vk_version,
+        )?)
+    }
+
+    /// Queue a condense of the deposit at `leaf_index` to run at a random time between `earliest` and `latest`.
+    /// `amount` is added to what the recipient has withdrawn now to give the total the job condenses up to.
+    /// Scheduled condenses are run by `run_next_job`
+    pub fn schedule_condense(
+        &self,
+        vapor_addr: [u8; 32],
+        leaf_index: u64,
+        amount: Option<u64>,
+        earliest: u64,
+        latest: u64,
+    ) -> Result<u64> {
+        let deposit = self
+            .deposits(vapor_addr)?
+            .into_iter()
+            .find(|d| d.leaf_index == leaf_index)
+            .ok_or(WalletError::NotADeposit(leaf_index))?;
+        let withdrawn = self.total_withdrawn(self.address(vapor_addr)?.recipient)?;
+        let total = withdrawal_total(withdrawn, deposit.amount, amount)?;
+        let job = CondenseJob::new(&mut OsRng, vapor_addr, leaf_index, total, earliest, latest);
+        schedule::add_job(&self.db, &job)
+    }
+
+    /// Scheduled condenses and their results by id
+    pub fn jobs(&self) -> Result<Vec<(u64, CondenseJob)>> {
+        schedule::load_jobs(&self.db)
+    }
+
+    /// Remove a scheduled condense that has not run yet
+    pub fn cancel_job(&self, id: u64) -> Result<()> {
+        schedule::cancel_job(&self.db, id)
+    }
+
+    /// Run the pending job that has been due the longest, if any, and record the result.
+    /// The wallet is synced right before proving so the proof is against a recent root.
+    /// A failed job is rescheduled under `retry` rather than returned as an error
+    pub fn run_next_job(
+        &self,
+        payer: &Keypair,
+        retry: &RetryPolicy,
+        options: &SubmitOptions,
+    ) -> Result<Option<(u64, CondenseJob)>> {
+        let Some((id, mut job)) = schedule::next_due(self.jobs()?, unix_now()) else {
+            return Ok(None);
+        };
+
+        tracing::info!("Running job {}", id);
+        let result = self
+            .sync()
+            .and_then(|_| self.condense_job(payer, &job, options));
+        match result {
+            Ok(Some(signature)) => job.record_success(signature.to_string()),
+            Ok(None) => job.record_reached(),
+            Err(e) => {
+                tracing::warn!("job {} failed: {}", id, e);
+                job.record_failure(&mut OsRng, e.to_string(), unix_now(), retry);
+            }
+        }
+        schedule::update_job(&self.db, id, &job)?;
+        Ok(Some((id, job)))
+    }
+
+    /// Condense a job's deposit up to its total. Returns `None` without condensing if the recipient has
+    /// already withdrawn that much
+    fn condense_job(
+        &self,
+        payer: &Keypair,
+        job: &CondenseJob,
+        options: &SubmitOptions,
+    ) -> Result<Option<Signature>> {
+        let recipient = self.address(job.vapor_addr)?.recipient;
+        if self.total_withdrawn(recipient)? >= job.total {
+            tracing::info!("The recipient has already withdrawn {}", job.total);
+            return Ok(None);
+        }
+        let plan = condense::plan(
+            &self.db,
+            &self.config.rpc_url,
+            &self.config.mint,
+            job.vapor_addr,
+            job.leaf_index,
+            Withdrawal::UpTo(job.total),
+        )?;
+        tracing::info!(
+            "Condensing {} of {} ({} already withdrawn to the recipient)",
+            plan.amount(),
+            plan.deposit_amount,
+            plan.withdrawn
+        );
+        let proof = self.prove_condense(&plan)?;
+        self.submit_condense(payer, &proof, options).map(Some)
+    }
+}
@@ -0,0 +1,233 @@
+//! WebAssembly bindings for browser wallets.
+//!
+//! Exposes vapor address generation, transfer leaf hashing, the transfer tree and condenser witness
+//! building, running the same Poseidon and ed25519 code as the CLI wallet so the results always agree.
+//!
+//! Values cross into JS as:
+//! - addresses as base58 strings, as Solana wallets show them
+//! - leaves, roots and other field elements as `0x` prefixed big-endian hex strings
+//! - amounts and leaf indices as `bigint`
+//! - spend secrets as decimal strings, as written in a Prover.toml
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use transfer_tree::Witness;
+use utils::{SpendSecret, fr_to_be_32};
+use wasm_bindgen::prelude::*;
+
+/// Height of the transfer tree kept by the transfer hook
+pub const TREE_HEIGHT: usize = 26;
+
+/// A new vapor address and the secret needed to condense deposits made to it
+#[wasm_bindgen]
+pub struct VaporizeAddress {
+    address: [u8; 32],
+    secret: SpendSecret,
+}
+
+#[wasm_bindgen]
+impl VaporizeAddress {
+    #[wasm_bindgen(getter)]
+    pub fn address(&self) -> String {
+        bs58::encode(self.address).into_string()
+    }
+
+    /// Spend secret as a decimal string. Anyone holding it can condense deposits to the address
+    #[wasm_bindgen(getter)]
+    pub fn secret(&self) -> String {
+        self.secret.to_decimal_string().as_str().to_string()
+    }
+}
+
+/// Generate a vapor address committing to `recipient`, a base58 Solana address
+#[wasm_bindgen(js_name = generateVaporizeAddress)]
+pub fn generate_vaporize_address(recipient: &str) -> Result<VaporizeAddress, JsError> {
+    let recipient = parse_address("recipient", recipient)?;
+    let (address, secret) =
+        vaporize_addresses::generate_vaporize_address(&mut rand::thread_rng(), recipient);
+    Ok(VaporizeAddress { address, secret })
+}
+
+/// Pack bytes into field elements 31 bytes at a time, as the circuits do
+#[wasm_bindgen(js_name = packBytes)]
+pub fn pack_bytes(bytes: &[u8]) -> Vec<String> {
+    utils::pack_bytes(bytes)
+        .iter()
+        .map(|field| to_hex(&fr_to_be_32(field)))
+        .collect()
+}
+
+/// Leaf the transfer hook records for a transfer of `amount` to `destination`
+#[wasm_bindgen(js_name = transferLeaf)]
+pub fn transfer_leaf(destination: &str, amount: u64) -> Result<String, JsError> {
+    let destination = parse_address("destination", destination)?;
+    Ok(to_hex(&transfer_tree::transfer_leaf(&destination, amount)))
+}
+
+/// The transfer tree of a mint, rebuilt by appending its leaves in order.
+/// Inclusion proofs are kept for tracked leaves, usually the deposits to the wallet's own addresses
+#[wasm_bindgen]
+pub struct TransferTree {
+    tree: transfer_tree::TransferTree<TREE_HEIGHT>,
+    witnesses: BTreeMap<u64, Witness<TREE_HEIGHT>>,
+}
+
+impl Default for TransferTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl TransferTree {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            tree: transfer_tree::TransferTree::new(),
+            witnesses: BTreeMap::new(),
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn root(&self) -> String {
+        to_hex(&self.tree.root())
+    }
+
+    /// Number of leaves in the tree
+    #[wasm_bindgen(getter, js_name = nextIndex)]
+    pub fn next_index(&self) -> u64 {
+        self.tree.next_index()
+    }
+
+    /// Append a leaf and return its index. `track` keeps an inclusion proof for it
+    pub fn append(&mut self, leaf: &str, track: bool) -> Result<u64, JsError> {
+        let path = self.tree.append(parse_hex("leaf", leaf)?)?;
+        for witness in self.witnesses.values_mut() {
+            witness.update(&path);
+        }
+        if track {
+            self.witnesses.insert(path.index, Witness::new(&path));
+        }
+        Ok(path.index)
+    }
+
+    /// Append the leaf for a transfer of `amount` to `destination` and return its index
+    #[wasm_bindgen(js_name = appendTransfer)]
+    pub fn append_transfer(
+        &mut self,
+        destination: &str,
+        amount: u64,
+        track: bool,
+    ) -> Result<u64, JsError> {
+        let leaf = transfer_leaf(destination, amount)?;
+        self.append(&leaf, track)
+    }
+
+    /// Inclusion proof of a tracked leaf against the current root
+    #[wasm_bindgen(js_name = merkleProof)]
+    pub fn merkle_proof(&self, index: u64) -> Result<MerkleProof, JsError> {
+        let witness = self
+            .witnesses
+            .get(&index)
+            .ok_or_else(|| JsError::new(&format!("leaf {} is not tracked", index)))?;
+        Ok(MerkleProof {
+            witness: *witness,
+            root: self.tree.root(),
+        })
+    }
+}
+
+#[wasm_bindgen]
+pub struct MerkleProof {
+    witness: Witness<TREE_HEIGHT>,
+    root: [u8; 32],
+}
+
+#[wasm_bindgen]
+impl MerkleProof {
+    #[wasm_bindgen(getter)]
+    pub fn index(&self) -> u64 {
+        self.witness.index
+    }
+
+    /// Root of the tree when the proof was taken
+    #[wasm_bindgen(getter)]
+    pub fn root(&self) -> String {
+        to_hex(&self.root)
+    }
+
+    /// Sibling at each level, starting from the leaf
+    #[wasm_bindgen(getter)]
+    pub fn siblings(&self) -> Vec<String> {
+        self.witness.siblings.iter().map(to_hex).collect()
+    }
+
+    /// Position of the node at each level relative to its sibling, 0 for left and 1 for right
+    #[wasm_bindgen(getter, js_name = pathIndices)]
+    pub fn path_indices(&self) -> Vec<u8> {
+        self.witness.path_indices().to_vec()
+    }
+}
+
+/// Inputs for the `condenser` circuit
+#[wasm_bindgen]
+pub struct CondenserWitness(condenser_witness::CondenserWitness<TREE_HEIGHT>);
+
+#[wasm_bindgen]
+impl CondenserWitness {
+    /// Witness for condensing the deposit of `deposit_amount` to `vapor_addr` proven by `proof`.
+    /// `amount` is the recipient's total withdrawn once this condense is applied, at most `deposit_amount`
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        recipient: &str,
+        amount: u64,
+        vapor_addr: &str,
+        deposit_amount: u64,
+        proof: &MerkleProof,
+        secret: &str,
+    ) -> Result<CondenserWitness, JsError> {
+        Ok(Self(
+            condenser_witness::CondenserWitness::builder()
+                .recipient(parse_address("recipient", recipient)?)
+                .amount(amount)
+                .merkle_root(proof.root)
+                .vapor_addr(parse_address("vapor address", vapor_addr)?)
+                .deposit_amount(deposit_amount)
+                .merkle_proof(proof.witness.siblings)
+                .merkle_proof_indices(proof.witness.path_indices())
+                .secret(SpendSecret::from_str(secret)?)
+                .build(),
+        ))
+    }
+
+    /// Prover.toml contents for nargo
+    #[wasm_bindgen(js_name = toToml)]
+    pub fn to_toml(&self) -> String {
+        self.0.to_toml().as_str().to_string()
+    }
+
+    /// The same inputs as a JSON object, as taken by noir_js
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(&self.0)?)
+    }
+}
+
+fn parse_address(what: &str, s: &str) -> Result<[u8; 32], JsError> {
+    bs58::decode(s)
+        .into_vec()
+        .map_err(|e| JsError::new(&format!("invalid {}: {}", what, e)))?
+        .try_into()
+        .map_err(|_| JsError::new(&format!("invalid {}: must be 32 bytes", what)))
+}
+
+fn parse_hex(what: &str, s: &str) -> Result<[u8; 32], JsError> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s))
+        .map_err(|e| JsError::new(&format!("invalid {}: {}", what, e)))?
+        .try_into()
+        .map_err(|_| JsError::new(&format!("invalid {}: must be 32 bytes", what)))
+}
+
+fn to_hex(bytes: &[u8; 32]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
@@ -0,0 +1,153 @@
+//! Run in a headless browser with `wasm-pack test --headless --chrome crates/vapor-wasm`
+#![cfg(target_arch = "wasm32")]
+
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use transfer_tree::Poseidon;
+use vapor_wasm::*;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+const RECIPIENT: [u8; 32] = [7u8; 32];
+
+fn address(bytes: [u8; 32]) -> String {
+    bs58::encode(bytes).into_string()
+}
+
+fn from_hex(s: &str) -> [u8; 32] {
+    hex::decode(s.strip_prefix("0x").unwrap())
+        .unwrap()
+        .try_into()
+        .unwrap()
+}
+
+#[wasm_bindgen_test]
+fn test_generate_vaporize_address() {
+    for i in 0..16 {
+        let generated = generate_vaporize_address(&address([i; 32])).unwrap();
+        let addr: [u8; 32] = bs58::decode(generated.address())
+            .into_vec()
+            .unwrap()
+            .try_into()
+            .unwrap();
+        CompressedEdwardsY(addr)
+            .decompress()
+            .expect("not a valid point encoding");
+        assert!(generated.secret().parse::<utils::SpendSecret>().is_ok());
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_invalid_address_is_an_error() {
+    assert!(generate_vaporize_address("not base58!").is_err());
+    assert!(generate_vaporize_address(&bs58::encode([1u8; 31]).into_string()).is_err());
+    assert!(transfer_leaf("", 1).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_matches_native_hashing() {
+    let packed: Vec<_> = utils::pack_bytes(&RECIPIENT)
+        .iter()
+        .map(|field| format!("0x{}", hex::encode(utils::fr_to_be_32(field))))
+        .collect();
+    assert_eq!(pack_bytes(&RECIPIENT), packed);
+
+    assert_eq!(
+        from_hex(&transfer_leaf(&address(RECIPIENT), 1000).unwrap()),
+        transfer_tree::transfer_leaf(&RECIPIENT, 1000)
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_tree_matches_native_tree() {
+    let mut tree = TransferTree::new();
+    let mut native = transfer_tree::TransferTree::<TREE_HEIGHT>::new();
+    assert_eq!(from_hex(&tree.root()), native.root());
+
+    for i in 0..5u8 {
+        let index = tree
+            .append_transfer(&address([i; 32]), i as u64 * 10, i == 2)
+            .unwrap();
+        native.append_transfer([i; 32], i as u64 * 10).unwrap();
+        assert_eq!(index, i as u64);
+    }
+    assert_eq!(tree.next_index(), 5);
+    assert_eq!(from_hex(&tree.root()), native.root());
+
+    // The proof of the tracked leaf was kept current by the appends after it
+    let proof = tree.merkle_proof(2).unwrap();
+    let witness = transfer_tree::Witness::<TREE_HEIGHT> {
+        index: proof.index(),
+        siblings: proof
+            .siblings()
+            .iter()
+            .map(|s| from_hex(s))
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap(),
+    };
+    assert_eq!(
+        witness.root::<Poseidon>(transfer_tree::transfer_leaf(&[2u8; 32], 20)),
+        native.root()
+    );
+    assert_eq!(proof.path_indices(), witness.path_indices().to_vec());
+    assert_eq!(from_hex(&proof.root()), native.root());
+
+    assert!(tree.merkle_proof(3).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_condenser_witness() {
+    let generated = generate_vaporize_address(&address(RECIPIENT)).unwrap();
+    let mut tree = TransferTree::new();
+    tree.append_transfer(&address([1u8; 32]), 5, false).unwrap();
+    let index = tree
+        .append_transfer(&generated.address(), 100, true)
+        .unwrap();
+    let proof = tree.merkle_proof(index).unwrap();
+
+    let witness = CondenserWitness::new(
+        &address(RECIPIENT),
+        60,
+        &generated.address(),
+        100,
+        &proof,
+        &generated.secret(),
+    )
+    .unwrap();
+
+    let native =
+        condenser_witness::CondenserWitness::<TREE_HEIGHT>::from_toml(&witness.to_toml()).unwrap();
+    assert_eq!(utils::fr_to_be_32(&native.amount), utils::u64_to_be_32(60));
+    assert_eq!(
+        utils::fr_to_be_32(&native.deposit_amount),
+        utils::u64_to_be_32(100)
+    );
+    assert_eq!(
+        utils::fr_to_be_32(&native.merkle_root),
+        from_hex(&tree.root())
+    );
+    assert_eq!(
+        native.secret.to_decimal_string().as_str(),
+        generated.secret()
+    );
+    assert_eq!(
+        serde_json::from_str::<condenser_witness::CondenserWitness<TREE_HEIGHT>>(
+            &witness.to_json().unwrap()
+        )
+        .unwrap(),
+        native
+    );
+
+    assert!(
+        CondenserWitness::new(
+            &address(RECIPIENT),
+            60,
+            &generated.address(),
+            100,
+            &proof,
+            "not a secret"
+        )
+        .is_err()
+    );
+}
@@ -5,29 +5,44 @@ use ark_ff::{Field, One, PrimeField, UniformRand, Zero};
 use ark_serialize::CanonicalSerialize;
 use light_poseidon::{Poseidon, PoseidonHasher};
 use rand::Rng;
-use utils::pack_bytes;
+use utils::{SpendSecret, pack_bytes};
+
+mod stealth;
+mod vanity;
+
+pub use stealth::{
+    InvalidMetaAddress, STEALTH_MEMO_PREFIX, ScanSecret, StealthMetaAddress, StealthPayment,
+    parse_stealth_memo, recover_stealth_address,
+};
+pub use vanity::{
+    VanityError, VanityProgress, generate_vanity_vaporize_address, prefix_difficulty,
+};
 
 /// Given a recipient address (32 bytes) generate an unspendable vapourize address
 /// and return it plus the secret value required to prove ownership
 pub fn generate_vaporize_address<R: rand::RngCore>(
     rng: &mut R,
     recipient: [u8; 32],
-) -> ([u8; 32], NoirField) {
+) -> ([u8; 32], SpendSecret) {
     let recipient = pack_bytes(&recipient);
 
     // Try random values until we find a valid point on the ed25519 curve
     // This should take on average 2 tries
     let (p, r) = loop {
-        let r = NoirField::rand(rng);
-        let x = hash_3(recipient[0], recipient[1], r);
-        if let Some(p) = ed25519_point_from_x(rng, ed25519_fq_from_noir_field(&x)) {
+        let r = SpendSecret::new(NoirField::rand(rng));
+        let x = hash_3(recipient[0], recipient[1], *r.expose_secret());
+        if let Some(p) = ed25519_point_from_x(ed25519_fq_from_noir_field(&x), rng.gen_bool(0.5)) {
             break (p, r);
         }
     };
 
+    (compress(&p), r)
+}
+
+fn compress(p: &EdwardsAffine) -> [u8; 32] {
     let mut addr = [0u8; 32];
     p.serialize_compressed(&mut addr[..]).unwrap();
-    (addr, r)
+    addr
 }
 
 fn hash_3(a: NoirField, b: NoirField, c: NoirField) -> NoirField {
@@ -40,8 +55,8 @@ fn ed25519_fq_from_noir_field(x: &NoirField) -> Fq {
 }
 
 /// Given an x coordinate, attempt to find the corresponding y coordinate on the ed25519 curve
-/// Note that both the positive and negative y coordinates are valid, this will randomly pick one
-fn ed25519_point_from_x<R: rand::RngCore>(rng: &mut R, x: Fq) -> Option<EdwardsAffine> {
+/// Note that both the positive and negative y coordinates are valid, `negate_y` picks between them
+fn ed25519_point_from_x(x: Fq, negate_y: bool) -> Option<EdwardsAffine> {
     let a = EdwardsConfig::COEFF_A;
     let d = EdwardsConfig::COEFF_D;
 
@@ -56,8 +71,7 @@ fn ed25519_point_from_x<R: rand::RngCore>(rng: &mut R, x: Fq) -> Option<EdwardsA
     let y2 = num * den.inverse()?;
     let y = y2.sqrt()?;
 
-    // randomly pick +y or -y
-    let y = if rng.gen_bool(0.5) { y } else { -y };
+    let y = if negate_y { -y } else { y };
 
     // verify that the point is in the correct subgroup
     let point = EdwardsAffine::new_unchecked(x, y);
@@ -79,14 +93,17 @@ mod tests {
         // fuzz a bunch of recipients and check we get valid addresses
         for i in 0..255 {
             let mut rng = OsRng;
-            let (addr, secret) = generate_vaporize_address(&mut rng, [i; 32]);
+            let (addr, _) = generate_vaporize_address(&mut rng, [i; 32]);
             println!("Generated address: {:?}", addr);
-            println!("Secret: {:?}", secret);
 
-            // check that decompressing works
-            CompressedEdwardsY(addr)
-                .decompress()
-                .expect("not a valid point encoding");
+            assert_valid_address(addr);
         }
     }
+
+    /// Check that decompressing works
+    pub(crate) fn assert_valid_address(addr: [u8; 32]) {
+        CompressedEdwardsY(addr)
+            .decompress()
+            .expect("not a valid point encoding");
+    }
 }
@@ -0,0 +1,274 @@
+//! Sender-derived vapor addresses.
+//!
+//! A recipient publishes a [`StealthMetaAddress`], their recipient address plus a scan public key.
+//! A sender picks an ephemeral key, performs ECDH with the scan key and derives the vapor address secret from
+//! the shared point, then posts the ephemeral public key in a memo alongside the transfer.
+//! The recipient repeats the ECDH with their scan secret for each memo to recognise payments and recover the secret.
+use std::fmt;
+use std::str::FromStr;
+
+use ark_bn254::Fr as NoirField;
+use ark_ff::{BigInteger, PrimeField};
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use curve25519_dalek::scalar::Scalar;
+use utils::{SpendSecret, pack_bytes};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::{compress, ed25519_fq_from_noir_field, ed25519_point_from_x, hash_3};
+
+const META_ADDRESS_PREFIX: &str = "vapormeta:";
+/// Prefix of the memo a sender attaches to a stealth transfer, followed by the base58 ephemeral key
+pub const STEALTH_MEMO_PREFIX: &str = "vapor-stealth:";
+
+/// Published by a recipient so that senders can derive fresh vapor addresses for them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StealthMetaAddress {
+    /// Address that condensed tokens are minted to
+    pub recipient: [u8; 32],
+    /// Compressed ed25519 point of the recipient's scan key
+    pub scan_key: [u8; 32],
+}
+
+impl fmt::Display for StealthMetaAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.recipient);
+        bytes[32..].copy_from_slice(&self.scan_key);
+        write!(
+            f,
+            "{}{}",
+            META_ADDRESS_PREFIX,
+            bs58::encode(bytes).into_string()
+        )
+    }
+}
+
+impl FromStr for StealthMetaAddress {
+    type Err = InvalidMetaAddress;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let encoded = s
+            .strip_prefix(META_ADDRESS_PREFIX)
+            .ok_or(InvalidMetaAddress)?;
+        let bytes: [u8; 64] = bs58::decode(encoded)
+            .into_vec()
+            .map_err(|_| InvalidMetaAddress)?
+            .try_into()
+            .map_err(|_| InvalidMetaAddress)?;
+        let scan_key: [u8; 32] = bytes[32..].try_into().unwrap();
+        if CompressedEdwardsY(scan_key).decompress().is_none() {
+            return Err(InvalidMetaAddress);
+        }
+        Ok(Self {
+            recipient: bytes[..32].try_into().unwrap(),
+            scan_key,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidMetaAddress;
+
+impl fmt::Display for InvalidMetaAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "not a valid {}... meta-address", META_ADDRESS_PREFIX)
+    }
+}
+
+impl std::error::Error for InvalidMetaAddress {}
+
+/// Secret half of the scan key. Anyone holding it can recognise payments and recover their spend secrets
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct ScanSecret(Scalar);
+
+impl ScanSecret {
+    pub fn generate<R: rand::RngCore>(rng: &mut R) -> Self {
+        Self(random_scalar(rng))
+    }
+
+    /// Returns `None` for a non-canonical scalar encoding
+    pub fn from_bytes(bytes: [u8; 32]) -> Option<Self> {
+        Option::from(Scalar::from_canonical_bytes(bytes)).map(Self)
+    }
+
+    pub fn to_bytes(&self) -> Zeroizing<[u8; 32]> {
+        Zeroizing::new(self.0.to_bytes())
+    }
+
+    pub fn meta_address(&self, recipient: [u8; 32]) -> StealthMetaAddress {
+        StealthMetaAddress {
+            recipient,
+            scan_key: (self.0 * ED25519_BASEPOINT_POINT).compress().to_bytes(),
+        }
+    }
+}
+
+impl fmt::Debug for ScanSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ScanSecret(<redacted>)")
+    }
+}
+
+/// A vapor address derived by a sender for a meta-address
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StealthPayment {
+    pub vapor_addr: [u8; 32],
+    /// Compressed ed25519 point the recipient needs to find this payment
+    pub ephemeral_key: [u8; 32],
+}
+
+impl StealthPayment {
+    /// Derive a fresh vapor address for the meta-address.
+    /// The sender learns the spend secret in the process but it only allows condensing to the recipient
+    pub fn new<R: rand::RngCore>(
+        rng: &mut R,
+        meta: &StealthMetaAddress,
+    ) -> Result<Self, InvalidMetaAddress> {
+        let scan_key = CompressedEdwardsY(meta.scan_key)
+            .decompress()
+            .ok_or(InvalidMetaAddress)?;
+        let ephemeral = Zeroizing::new(random_scalar(rng));
+
+        let shared = Zeroizing::new((*ephemeral * scan_key).compress().to_bytes());
+        let (vapor_addr, _) = derive_from_shared(meta.recipient, &shared);
+
+        Ok(Self {
+            vapor_addr,
+            ephemeral_key: (*ephemeral * ED25519_BASEPOINT_POINT).compress().to_bytes(),
+        })
+    }
+
+    /// Memo to attach to the transfer so the recipient can find it
+    pub fn memo(&self) -> String {
+        format!(
+            "{}{}",
+            STEALTH_MEMO_PREFIX,
+            bs58::encode(self.ephemeral_key).into_string()
+        )
+    }
+}
+
+/// Find a stealth memo in `text`, e.g. a memo program log line, and return its ephemeral key
+pub fn parse_stealth_memo(text: &str) -> Option<[u8; 32]> {
+    let start = text.find(STEALTH_MEMO_PREFIX)? + STEALTH_MEMO_PREFIX.len();
+    let encoded: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect();
+    bs58::decode(encoded).into_vec().ok()?.try_into().ok()
+}
+
+/// Recompute the vapor address a sender derived from `ephemeral_key` for this recipient.
+/// Returns `None` if the ephemeral key is not a valid point.
+/// The caller checks the address against the transfer the memo was attached to.
+pub fn recover_stealth_address(
+    scan_secret: &ScanSecret,
+    recipient: [u8; 32],
+    ephemeral_key: [u8; 32],
+) -> Option<([u8; 32], SpendSecret)> {
+    let ephemeral = CompressedEdwardsY(ephemeral_key).decompress()?;
+    let shared = Zeroizing::new((scan_secret.0 * ephemeral).compress().to_bytes());
+    Some(derive_from_shared(recipient, &shared))
+}
+
+/// Deterministic version of [`crate::generate_vaporize_address`] where the candidate secrets
+/// are Poseidon hashes of the shared point and a counter rather than random values
+fn derive_from_shared(recipient: [u8; 32], shared: &[u8; 32]) -> ([u8; 32], SpendSecret) {
+    let recipient = pack_bytes(&recipient);
+    let shared = pack_bytes(shared);
+
+    let mut counter = 0u64;
+    loop {
+        let r = SpendSecret::new(hash_3(shared[0], shared[1], NoirField::from(counter)));
+        let x = hash_3(recipient[0], recipient[1], *r.expose_secret());
+        // Pick the y coordinate from a bit of the secret so it is as unbiased as a random choice
+        let negate_y = r.expose_secret().into_bigint().is_odd();
+        if let Some(p) = ed25519_point_from_x(ed25519_fq_from_noir_field(&x), negate_y) {
+            return (compress(&p), r);
+        }
+        counter += 1;
+    }
+}
+
+fn random_scalar<R: rand::RngCore>(rng: &mut R) -> Scalar {
+    let mut wide = Zeroizing::new([0u8; 64]);
+    rng.fill_bytes(&mut wide[..]);
+    Scalar::from_bytes_mod_order_wide(&wide)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_valid_address;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_recipient_recovers_payment() {
+        let scan_secret = ScanSecret::generate(&mut OsRng);
+        let meta = scan_secret.meta_address([5u8; 32]);
+
+        let payment = StealthPayment::new(&mut OsRng, &meta).unwrap();
+        assert_valid_address(payment.vapor_addr);
+
+        let (addr, _) =
+            recover_stealth_address(&scan_secret, meta.recipient, payment.ephemeral_key).unwrap();
+        assert_eq!(addr, payment.vapor_addr);
+    }
+
+    #[test]
+    fn test_other_scan_key_does_not_match() {
+        let meta = ScanSecret::generate(&mut OsRng).meta_address([5u8; 32]);
+        let payment = StealthPayment::new(&mut OsRng, &meta).unwrap();
+
+        let other = ScanSecret::generate(&mut OsRng);
+        let (addr, _) =
+            recover_stealth_address(&other, meta.recipient, payment.ephemeral_key).unwrap();
+        assert_ne!(addr, payment.vapor_addr);
+    }
+
+    #[test]
+    fn test_payments_are_unlinkable() {
+        let meta = ScanSecret::generate(&mut OsRng).meta_address([5u8; 32]);
+        let a = StealthPayment::new(&mut OsRng, &meta).unwrap();
+        let b = StealthPayment::new(&mut OsRng, &meta).unwrap();
+        assert_ne!(a.vapor_addr, b.vapor_addr);
+        assert_ne!(a.ephemeral_key, b.ephemeral_key);
+    }
+
+    #[test]
+    fn test_meta_address_round_trip() {
+        let meta = ScanSecret::generate(&mut OsRng).meta_address([5u8; 32]);
+        assert_eq!(
+            meta.to_string().parse::<StealthMetaAddress>().unwrap(),
+            meta
+        );
+        assert!("vapormeta:abc".parse::<StealthMetaAddress>().is_err());
+        assert!(
+            bs58::encode([5u8; 32])
+                .into_string()
+                .parse::<StealthMetaAddress>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_stealth_memo() {
+        let meta = ScanSecret::generate(&mut OsRng).meta_address([5u8; 32]);
+        let payment = StealthPayment::new(&mut OsRng, &meta).unwrap();
+        let log = format!("Program log: Memo (len 58): \"{}\"", payment.memo());
+        assert_eq!(parse_stealth_memo(&log), Some(payment.ephemeral_key));
+        assert_eq!(
+            parse_stealth_memo("Program log: Memo (len 5): \"hello\""),
+            None
+        );
+    }
+
+    #[test]
+    fn test_scan_secret_round_trip() {
+        let secret = ScanSecret::generate(&mut OsRng);
+        let restored = ScanSecret::from_bytes(*secret.to_bytes()).unwrap();
+        assert_eq!(restored.meta_address([1; 32]), secret.meta_address([1; 32]));
+        assert_eq!(format!("{:?}", secret), "ScanSecret(<redacted>)");
+    }
+}
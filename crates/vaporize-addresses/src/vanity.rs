@@ -0,0 +1,184 @@
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use utils::SpendSecret;
+
+use crate::generate_vaporize_address;
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Length of a vaporize address in bytes
+const ADDRESS_BYTES: usize = 32;
+
+/// How often the progress callback is invoked while searching
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VanityError {
+    /// The prefix contains a character that can never appear in a base58 address
+    InvalidCharacter(char),
+    EmptyPrefix,
+}
+
+impl fmt::Display for VanityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VanityError::InvalidCharacter(c) => {
+                write!(f, "'{}' is not a base58 character", c)
+            }
+            VanityError::EmptyPrefix => write!(f, "prefix must not be empty"),
+        }
+    }
+}
+
+impl std::error::Error for VanityError {}
+
+#[derive(Debug, Clone, Copy)]
+pub struct VanityProgress {
+    pub attempts: u64,
+    pub elapsed: Duration,
+}
+
+impl VanityProgress {
+    pub fn rate(&self) -> f64 {
+        self.attempts as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Expected number of addresses to generate before one starts with `prefix`, taking addresses as uniform over
+/// the 2^256 values of 32 bytes. Base58 characters are far from equally likely at the start of an address:
+/// a leading `1` encodes a leading zero byte, and the first other character depends on where 2^256 falls
+/// between powers of 58. Infinite if no address can start with `prefix`
+pub fn prefix_difficulty(prefix: &str) -> Result<f64, VanityError> {
+    validate_prefix(prefix)?;
+    let zero_bytes = prefix.chars().take_while(|&c| c == '1').count();
+    let rest = &prefix[zero_bytes..];
+    if zero_bytes > ADDRESS_BYTES {
+        return Ok(f64::INFINITY);
+    }
+    // Addresses with at least `zero_bytes` leading zero bytes are below `end`, those with exactly that many are
+    // at least `end / 256`. The rest of the encoding is the base58 digits of the address as a number
+    let end = 256f64.powi((ADDRESS_BYTES - zero_bytes) as i32);
+    let matching = if rest.is_empty() {
+        end
+    } else {
+        let start = end / 256.0;
+        let value = rest.chars().fold(0.0, |value, c| {
+            value * 58.0 + BASE58_ALPHABET.find(c).unwrap() as f64
+        });
+        // Numbers whose digits start with `rest` and run on for `scale = 58^n` more, within `start..end`
+        let mut matching = 0.0;
+        let mut scale = 1.0;
+        while value * scale < end {
+            let from = (value * scale).max(start);
+            let to = ((value + 1.0) * scale).min(end);
+            matching += (to - from).max(0.0);
+            scale *= 58.0;
+        }
+        matching
+    };
+    Ok(2f64.powi(8 * ADDRESS_BYTES as i32) / matching)
+}
+
+/// Grind vaporize addresses for `recipient` on `threads` threads until one's base58 encoding starts with `prefix`.
+/// Every candidate is a regular vaporize address so the result hides the recipient just the same.
+/// `on_progress` is called roughly once a second from the calling thread.
+pub fn generate_vanity_vaporize_address(
+    recipient: [u8; 32],
+    prefix: &str,
+    threads: usize,
+    mut on_progress: impl FnMut(VanityProgress),
+) -> Result<([u8; 32], SpendSecret), VanityError> {
+    validate_prefix(prefix)?;
+
+    let found = AtomicBool::new(false);
+    let attempts = AtomicU64::new(0);
+    let result = Mutex::new(None);
+    let start = Instant::now();
+    let caller = std::thread::current();
+
+    std::thread::scope(|s| {
+        for _ in 0..threads.max(1) {
+            s.spawn(|| {
+                let mut rng = rand::thread_rng();
+                while !found.load(Ordering::Relaxed) {
+                    let (addr, secret) = generate_vaporize_address(&mut rng, recipient);
+                    attempts.fetch_add(1, Ordering::Relaxed);
+                    if bs58::encode(addr).into_string().starts_with(prefix)
+                        && !found.swap(true, Ordering::Relaxed)
+                    {
+                        *result.lock().unwrap() = Some((addr, secret));
+                        caller.unpark();
+                    }
+                }
+            });
+        }
+
+        while !found.load(Ordering::Relaxed) {
+            std::thread::park_timeout(PROGRESS_INTERVAL);
+            on_progress(VanityProgress {
+                attempts: attempts.load(Ordering::Relaxed),
+                elapsed: start.elapsed(),
+            });
+        }
+    });
+
+    Ok(result
+        .into_inner()
+        .unwrap()
+        .expect("a thread found a match"))
+}
+
+fn validate_prefix(prefix: &str) -> Result<(), VanityError> {
+    if prefix.is_empty() {
+        return Err(VanityError::EmptyPrefix);
+    }
+    match prefix.chars().find(|c| !BASE58_ALPHABET.contains(*c)) {
+        Some(c) => Err(VanityError::InvalidCharacter(c)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::assert_valid_address;
+
+    #[test]
+    fn test_prefix_difficulty() {
+        // A leading 1 is a leading zero byte
+        assert_eq!(prefix_difficulty("1").unwrap(), 256.0);
+        assert_eq!(prefix_difficulty("11").unwrap(), 256.0 * 256.0);
+        // 2^256 is just short of 57 * 58^43 so no 44 digit address starts with z, only the 43 digit ones
+        // in 57 * 58^42..58^43 that have no leading zero byte
+        let z = 2f64.powi(256) / (58f64.powi(43) - 57.0 * 58f64.powi(42));
+        assert!((prefix_difficulty("z").unwrap() / z - 1.0).abs() < 1e-9);
+        assert!((prefix_difficulty("z").unwrap() - 999.3167).abs() < 1e-3);
+        // Every address starting with 1z has exactly one leading zero byte
+        assert!((prefix_difficulty("1z").unwrap() - 56977.9915).abs() < 1e-3);
+        assert!(prefix_difficulty(&"1".repeat(33)).unwrap().is_infinite());
+        assert!(
+            prefix_difficulty(&format!("{}2", "1".repeat(32)))
+                .unwrap()
+                .is_infinite()
+        );
+        assert_eq!(
+            prefix_difficulty("0"),
+            Err(VanityError::InvalidCharacter('0'))
+        );
+        assert_eq!(
+            prefix_difficulty("vaPOr"),
+            Err(VanityError::InvalidCharacter('O'))
+        );
+        assert_eq!(prefix_difficulty(""), Err(VanityError::EmptyPrefix));
+    }
+
+    #[test]
+    fn test_generate_vanity_vaporize_address() {
+        let (addr, _) = generate_vanity_vaporize_address([1; 32], "V", 4, |_| {}).unwrap();
+        assert!(bs58::encode(addr).into_string().starts_with('V'));
+        assert_valid_address(addr);
+    }
+}
@@ -1,18 +1,148 @@
-use gnark_verifier_solana::vk::generate_key_file;
+use std::path::{Path, PathBuf};
 
-fn main() {
-    let command = std::env::args().nth(1).expect("expected first arg");
+use anyhow::Context;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer, read_keypair_file};
+use solana_sdk::transaction::Transaction;
+use vapor_client::proof::VerifyingKey;
+use vapor_client::registry::RegistryEntry;
+use vapor_client::{Circuit, instructions};
 
-    match command.as_str() {
-        "codegen" => codegen(),
-        _ => {
-            panic!("unknown command: {}", command);
+/// Target directories of the circuits whose keys are registered, written by their trusted setup
+const VK_DIRS: [&str; 2] = ["circuits/condenser/target", "circuits/disclosure/target"];
+
+const USAGE: &str = "usage:
+    cargo xtask emit-vk <version>
+    cargo xtask upload-vk <mint> <entry.json>...
+    cargo xtask retire-vk <mint> <version> [condenser|disclosure]
+
+upload-vk and retire-vk send transactions to SOL_RPC (default devnet)
+signed by KEYPAIR (default ~/.config/solana/id.json), the transfer hook authority of the mint";
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["emit-vk", version] => emit_vk(version.parse()?),
+        ["upload-vk", mint, ref entries @ ..] if !entries.is_empty() => {
+            upload_vk(&mint.parse()?, entries)
+        }
+        ["retire-vk", mint, version] => {
+            retire_vk(&mint.parse()?, Circuit::Condenser, version.parse()?)
+        }
+        ["retire-vk", mint, version, "condenser"] => {
+            retire_vk(&mint.parse()?, Circuit::Condenser, version.parse()?)
+        }
+        ["retire-vk", mint, version, "disclosure"] => {
+            retire_vk(&mint.parse()?, Circuit::Disclosure, version.parse()?)
         }
+        _ => anyhow::bail!("{}", USAGE),
     }
 }
 
-fn codegen() {
-    let vk_path = "circuits/condenser/target/condenser.vk";
-    let out_path = "anchor/programs/vaportoken-condenser/src/vk.rs";
-    generate_key_file(vk_path, out_path).expect("failed to generate vk/pk files");
+/// Write a registry entry for each verifying key in the circuits' target directories,
+/// next to the key as `<name>.v<version>.json`. Circuits without a trusted setup yet are skipped
+fn emit_vk(version: u32) -> anyhow::Result<()> {
+    let mut emitted = 0;
+    for dir in VK_DIRS {
+        let Ok(paths) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for path in paths {
+            emitted += emit_entry(&path?.path(), version)?;
+        }
+    }
+    if emitted == 0 {
+        anyhow::bail!(
+            "no verifying keys in {}, run `just trusted_setup` first",
+            VK_DIRS.join(" or ")
+        );
+    }
+    Ok(())
+}
+
+/// Write the entry of the key at `path`, returning how many were written
+fn emit_entry(path: &Path, version: u32) -> anyhow::Result<usize> {
+    if path.extension().is_none_or(|ext| ext != "vk") {
+        return Ok(0);
+    }
+
+    let bytes =
+        std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let entry = RegistryEntry::new(version, &VerifyingKey::from_bytes(&bytes)?)
+        .with_context(|| format!("cannot register {}", path.display()))?;
+
+    let out = path.with_extension(format!("v{}.json", version));
+    std::fs::write(&out, serde_json::to_string_pretty(&entry)?)?;
+    println!("{}", out.display());
+    Ok(1)
+}
+
+/// Register each entry for the mint, one transaction per instruction
+fn upload_vk(mint: &Pubkey, entries: &[&str]) -> anyhow::Result<()> {
+    let (client, authority) = connect()?;
+    for path in entries {
+        let entry: RegistryEntry = serde_json::from_slice(
+            &std::fs::read(path).with_context(|| format!("failed to read {}", path))?,
+        )?;
+        let account = entry.address(mint)?.0;
+        if client.get_account(&account).is_ok() {
+            anyhow::bail!(
+                "version {} is already registered at {}",
+                entry.version,
+                account
+            );
+        }
+
+        println!(
+            "Registering {:?} version {} at {}...",
+            entry.circuit()?,
+            entry.version,
+            account
+        );
+        for ix in entry.instructions(mint, &authority.pubkey())? {
+            send(&client, &authority, ix)?;
+        }
+    }
+    Ok(())
+}
+
+fn retire_vk(mint: &Pubkey, circuit: Circuit, version: u32) -> anyhow::Result<()> {
+    let (client, authority) = connect()?;
+    println!("Retiring {:?} version {}...", circuit, version);
+    send(
+        &client,
+        &authority,
+        instructions::retire_verifying_key(mint, &authority.pubkey(), circuit, version),
+    )
+}
+
+fn connect() -> anyhow::Result<(RpcClient, Keypair)> {
+    let rpc_url =
+        std::env::var("SOL_RPC").unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
+    let keypair: PathBuf = shellexpand::tilde(
+        &std::env::var("KEYPAIR").unwrap_or_else(|_| "~/.config/solana/id.json".to_string()),
+    )
+    .to_string()
+    .into();
+    let authority = read_keypair_file(&keypair)
+        .map_err(|e| anyhow::anyhow!("failed to read keypair {}: {}", keypair.display(), e))?;
+    Ok((RpcClient::new(rpc_url), authority))
+}
+
+fn send(
+    client: &RpcClient,
+    authority: &Keypair,
+    ix: solana_sdk::instruction::Instruction,
+) -> anyhow::Result<()> {
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&authority.pubkey()),
+        &[authority],
+        client.get_latest_blockhash()?,
+    );
+    let sig = client.send_and_confirm_transaction(&tx)?;
+    println!("  {}", sig);
+    Ok(())
 }
@@ -1,19 +1,27 @@
 #!/bin/sh
 set -eu
 
-cd ./circuits/condenser
+CIRCUIT="${1:-condenser}"
 
-# Write the inputs
-cat > Prover.toml
+# Work on a private copy of the circuits so the witness never lands in the mounted directory.
+# It is removed along with the container
+WORKDIR="$(mktemp -d)"
+cp -r /circuits/. "$WORKDIR"
+cd "$WORKDIR/${CIRCUIT}"
+
+# Read the inputs mounted by the wallet
+cp /input/Prover.toml Prover.toml
 
 # create the witness
 nargo execute 1>&2
 
 # generate the gnark proof
-sunspot prove ./target/condenser.json ./target/condenser.gz ./target/condenser.ccs ./target/condenser.pk 1>&2
+sunspot prove "./target/${CIRCUIT}.json" "./target/${CIRCUIT}.gz" "./target/${CIRCUIT}.ccs" "./target/${CIRCUIT}.pk" 1>&2
 
 # Output the proof and witness
 printf -- '%s\n' '---PROOF---'
-cat ./target/condenser.proof
+cat "./target/${CIRCUIT}.proof"
 printf -- '\n%s\n' '---WITNESS---'
-cat ./target/condenser.pw
+cat "./target/${CIRCUIT}.pw"
+
+rm -rf "$WORKDIR"